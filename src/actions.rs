@@ -0,0 +1,295 @@
+use std::sync::Arc;
+use anyhow::{anyhow, Result};
+use crate::{
+    effects::{EffectDuration, EffectKind, EffectSpec, EffectTarget},
+    file_scan::ActionFormat,
+    game::GameState,
+    movement::{MovementType, AREA, GRANT},
+    moves::Move,
+    orientation::Orientation,
+    piece::Piece,
+    position::{AbsolutePosition, RelativePosition},
+    rules::PieceDefinition,
+};
+
+/// 액션을 쓴 횟수를 적어두는 상태 이름의 앞부분. 뒤에 액션 표기가 붙습니다.
+pub const ACTION_USES_STATE: &str = "uses_";
+
+/// ## ActionDefinition
+/// 기물이 자리를 옮기지 않고 하는 행동. (스나이퍼의 저격, 번개의 기절, 매의 catch)
+/// - `action_type`: `SHOOT`, `CATCH`는 닿는 칸 중 하나를 고르고, `AREA`는 닿는 칸 전부에 한 번에 씁니다.
+///   `GRANT`는 닿는 칸의 아군 기물 하나를 골라 같은 차례에 한 번 더 두게 합니다.
+/// - `leaps`: 한 번에 닿는 칸, `rides`: 그 방향으로 처음 만나는 기물까지의 칸
+/// - `captures`: 닿은 적 기물을 잡는지, `effect`: 잡지 않은 기물에 거는 효과
+/// - `limit`: 기물 하나가 한 게임에 쓸 수 있는 횟수. 쓴 횟수는 기물 상태에 적습니다.
+/// - `allows_empty`: `AREA` 액션을 닿는 기물이 없어도 쓸 수 있는지. 기본은 쓸 수 없습니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionDefinition<const D: usize> {
+    name: String,
+    notation: String,
+    action_type: MovementType,
+    leaps: Vec<RelativePosition<D>>,
+    rides: Vec<RelativePosition<D>>,
+    captures: bool,
+    effect: Option<EffectSpec>,
+    limit: Option<usize>,
+    allows_empty: bool,
+}
+
+impl<const D: usize> ActionDefinition<D> {
+    pub fn new(name: impl Into<String>, notation: impl Into<String>, action_type: impl Into<MovementType>) -> Self {
+        Self {
+            name: name.into(),
+            notation: notation.into(),
+            action_type: action_type.into(),
+            leaps: Vec::new(),
+            rides: Vec::new(),
+            captures: false,
+            effect: None,
+            limit: None,
+            allows_empty: false,
+        }
+    }
+
+    pub fn from_format(format: &ActionFormat) -> Result<Self> {
+        let offset = |offset: &Vec<isize>| RelativePosition::try_from(offset.clone())
+            .map_err(|offset| anyhow!("{}: offset {:?} is not {}-dimensional", format.get_name(), offset, D));
+        let mut action = Self::new(format.get_name(), format.get_notation(), format.get_action_type())
+            .with_leaps(format.get_leaps().iter().map(offset).collect::<Result<Vec<_>>>()?)
+            .with_rides(format.get_rides().iter().map(offset).collect::<Result<Vec<_>>>()?);
+        action.captures = format.captures();
+        if let Some(effect) = format.get_effect() {
+            let duration = format.get_effect_turns().map_or(EffectDuration::Permanent, EffectDuration::Turns);
+            action = action.with_effect(EffectSpec::new(EffectKind::from_name(effect), duration));
+        }
+        action.limit = format.get_limit();
+        action.allows_empty = format.allows_empty();
+        Ok(action)
+    }
+
+    pub fn with_leaps(mut self, leaps: impl IntoIterator<Item = RelativePosition<D>>) -> Self {
+        self.leaps.extend(leaps);
+        self
+    }
+
+    pub fn with_rides(mut self, rides: impl IntoIterator<Item = RelativePosition<D>>) -> Self {
+        self.rides.extend(rides);
+        self
+    }
+
+    pub fn capturing(mut self) -> Self {
+        self.captures = true;
+        self
+    }
+
+    pub fn with_effect(mut self, effect: EffectSpec) -> Self {
+        self.effect = Some(effect);
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn allowing_empty(mut self) -> Self {
+        self.allows_empty = true;
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    #[inline(always)]
+    pub fn get_notation(&self) -> &String {
+        &self.notation
+    }
+
+    #[inline(always)]
+    pub fn get_action_type(&self) -> &MovementType {
+        &self.action_type
+    }
+
+    #[inline(always)]
+    pub fn captures(&self) -> bool {
+        self.captures
+    }
+
+    #[inline(always)]
+    pub fn get_effect(&self) -> Option<&EffectSpec> {
+        self.effect.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    #[inline(always)]
+    pub fn allows_empty(&self) -> bool {
+        self.allows_empty
+    }
+
+    #[inline(always)]
+    pub fn uses_state(&self) -> String {
+        format!("{ACTION_USES_STATE}{}", self.notation)
+    }
+
+    /// `piece`가 이 액션을 쓴 횟수.
+    pub fn uses(&self, piece: &Piece) -> usize {
+        piece.get_state().get(&self.uses_state()).and_then(|uses| uses.parse().ok()).unwrap_or(0)
+    }
+
+    /// `piece`가 아직 이 액션을 쓸 수 있는지.
+    pub fn is_available(&self, piece: &Piece) -> bool {
+        self.limit.is_none_or(|limit| self.uses(piece) < limit)
+    }
+
+    /// 플레이어 쪽에서 적은 닿는 칸들을 보드 방향으로 바꿉니다.
+    pub fn oriented(mut self, orientation: &Orientation<D>) -> Self {
+        self.leaps = self.leaps.iter().map(|offset| orientation.orient(offset)).collect();
+        self.rides = self.rides.iter().map(|offset| orientation.orient(offset)).collect();
+        self
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// `from`에서 `action`이 닿는 칸들.
+    pub fn action_reach(&self, from: &AbsolutePosition<D>, action: &ActionDefinition<D>) -> Vec<AbsolutePosition<D>> {
+        let board = self.get_board();
+        let mut reach: Vec<_> = action.leaps.iter().filter_map(|offset| board.offset(from, offset)).collect();
+        for direction in &action.rides {
+            let mut position = *from;
+            while let Some(next) = board.offset(&position, direction) {
+                reach.push(next);
+                if board.get(&next).is_some() {
+                    break;
+                }
+                position = next;
+            }
+        }
+        reach
+    }
+
+    /// 기물 정의에 있는 액션마다 수를 만듭니다. `AREA`는 닿는 칸 전부를 대상으로 하는 수 하나가 되고,
+    /// 닿는 기물이 하나도 없으면 `allows_empty`일 때만 만듭니다.
+    pub(crate) fn action_moves(&self, from: &AbsolutePosition<D>, piece: &Piece, definition: &PieceDefinition<D>, moves: &mut Vec<Move<D>>) {
+        for action in definition.get_actions().iter().filter(|action| action.is_available(piece)) {
+            let reach = self.action_reach(from, action);
+            let base = Move::action(*from, action.get_action_type(), action.get_notation());
+            if action.get_action_type() == AREA {
+                if !action.allows_empty && !reach.iter().any(|target| self.action_hits(piece, action, target)) {
+                    continue;
+                }
+                moves.push(reach.into_iter().fold(base, |mv, target| self.with_action_target(mv, piece, action, target)));
+            } else {
+                for target in reach.into_iter().filter(|target| self.action_hits(piece, action, target)) {
+                    moves.push(self.with_action_target(base.clone(), piece, action, target));
+                }
+            }
+        }
+    }
+
+    /// `from`의 기물이 액션으로 `target`의 적 기물을 잡을 수 있는지.
+    pub(crate) fn action_attacks(&self, from: &AbsolutePosition<D>, piece: &Piece, definition: &PieceDefinition<D>, target: &AbsolutePosition<D>) -> bool {
+        definition.get_actions().iter()
+            .filter(|action| action.captures && action.is_available(piece))
+            .any(|action| self.action_reach(from, action).contains(target))
+    }
+
+    /// 한 칸을 고르는 액션이 `target`에 쓸모가 있는지. 잡는 액션은 적 기물, 효과만 거는 액션은 아무 기물이나 됩니다.
+    fn action_hits(&self, piece: &Piece, action: &ActionDefinition<D>, target: &AbsolutePosition<D>) -> bool {
+        match self.get_board().get(target) {
+            Some(other) if action.action_type == GRANT => !self.is_hostile(self.controller(piece), other),
+            Some(other) if action.captures => self.can_capture(self.controller(piece), target, other),
+            Some(_) => action.effect.is_some(),
+            None => false,
+        }
+    }
+
+    fn with_action_target(&self, mv: Move<D>, piece: &Piece, action: &ActionDefinition<D>, target: AbsolutePosition<D>) -> Move<D> {
+        let mv = mv.with_target(target);
+        if action.action_type == GRANT {
+            return mv.with_grant(target);
+        }
+        match self.get_board().get(&target) {
+            Some(other) if action.captures && self.can_capture(self.controller(piece), &target, other) => {
+                mv.with_capture(target)
+            }
+            Some(_) => match &action.effect {
+                Some(effect) => mv.with_effect(EffectTarget::Piece(target), effect.clone()),
+                None => mv
+            },
+            None => mv
+        }
+    }
+
+    /// 횟수 제한이 있는 액션이면 `from`의 기물에 쓴 횟수를 하나 늘립니다.
+    pub fn record_action_use(&mut self, from: &AbsolutePosition<D>, notation: &str) {
+        let rules = Arc::clone(self.get_rules());
+        let Some(action) = self.get_board().get(from)
+            .and_then(|piece| rules.movement_definition(piece))
+            .and_then(|definition| definition.get_action(notation)) else {
+            return;
+        };
+        if action.get_limit().is_some() {
+            let uses = action.uses(self.get_board().get(from).unwrap()) + 1;
+            self.update_piece(from, |piece| { piece.get_state_mut().set(action.uses_state(), uses.to_string()); });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen::parse_square, presets, transform::MOVES_AS_STATE};
+
+    #[test]
+    fn sniper_shoots_once_and_lightning_stuns_area() {
+        let mut game = GameState::from_fen("4k3/8/8/8/3q4/4p3/4L3/S3K3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_hash_check(true);
+        let sniper = parse_square("a1").unwrap();
+        let shot = game.legal_moves().into_iter().find(|mv| mv.is_action() && *mv.get_from() == sniper).unwrap();
+        assert_eq!(shot.to_string(), "a1sd4");
+        assert_eq!(shot.get_captures(), [parse_square("d4").unwrap()]);
+        game.make_move(shot);
+        assert_eq!(game.get_board().get(&sniper).unwrap().get_name(), "sniper");
+        game.make_move(Move::new(parse_square("e8").unwrap(), parse_square("d8").unwrap(), crate::movement::MOVE));
+        assert!(game.legal_moves().iter().all(|mv| !(mv.is_action() && *mv.get_from() == sniper)));
+
+        let stun = game.legal_moves().into_iter().find(|mv| mv.get_action().is_some_and(|action| action == "s") && *mv.get_from() == parse_square("e2").unwrap()).unwrap();
+        assert_eq!(stun.get_targets().len(), 8);
+        game.make_move(stun);
+        assert!(game.is_immobilized(&parse_square("e3").unwrap()));
+        assert!(game.is_immobilized(&parse_square("e1").unwrap()));
+
+        let mut alone = GameState::from_fen("4k3/8/8/8/8/8/8/L3K3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        assert!(!alone.legal_moves().iter().any(Move::is_action));
+        let mut rules = presets::fairy_chess();
+        for player in 0..2 {
+            let lightning = presets::lightning();
+            let stun = lightning.get_action("s").unwrap().clone().allowing_empty();
+            rules.define(player, PieceDefinition::new("lightning", "L", 3).with_action(stun));
+        }
+        alone = GameState::from_fen("4k3/8/8/8/8/8/8/L3K3 w - - 0 1", presets::standard_board(), Arc::new(rules)).unwrap();
+        assert!(alone.legal_moves().iter().any(|mv| mv.to_string() == "a1s"));
+    }
+
+    #[test]
+    fn borrowed_limited_action_runs_out() {
+        let mut game = GameState::from_fen("4k3/p7/8/8/3q4/8/8/4K3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_hash_check(true);
+        let mimic = parse_square("a1").unwrap();
+        let mut knight = game.get_rules().create_piece(0, "knight").unwrap();
+        knight.get_state_mut().set(MOVES_AS_STATE, "sniper");
+        game.put_piece(mimic, knight);
+        let shots = |game: &mut GameState<2>| game.legal_moves().into_iter().filter(|mv| mv.is_action() && *mv.get_from() == mimic).collect::<Vec<_>>();
+        assert_eq!(shots(&mut game).len(), 2);
+        let shot = shots(&mut game).into_iter().find(|mv| mv.to_string() == "a1sd4").unwrap();
+        game.make_move(shot);
+        game.make_move(Move::new(parse_square("e8").unwrap(), parse_square("d8").unwrap(), crate::movement::MOVE));
+        assert!(shots(&mut game).is_empty());
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::BTreeSet;
+use anyhow::{anyhow, bail, Result};
+use crate::{
+    movement::{DependentMove, IndependentMove, MovementType, CAPTURE, EN_PASSANT, GRASSHOP, HOP, INITIAL, JUMP, LAME, MOVE, PASSABLE},
+    position::RelativePosition,
+    rules::PieceDefinition,
+    symmetry::Symmetry,
+};
+
+/// 베짜 표기의 기본 도약. 글자마다 (큰 값, 작은 값)입니다.
+const ATOMS: [(char, [isize; 2]); 9] = [
+    ('W', [1, 0]), ('F', [1, 1]), ('D', [2, 0]), ('N', [2, 1]), ('A', [2, 2]),
+    ('H', [3, 0]), ('C', [3, 1]), ('Z', [3, 2]), ('G', [3, 3]),
+];
+/// 방향 글자. `u`, `d`는 2번 축(층)의 위, 아래로 3차원 이상을 위한 확장입니다.
+const DIRECTIONS: &str = "fblrvsud";
+/// 방향 두 글자를 묶어 쓸 수 있는 첫 글자와 둘째 글자. (`ff`, `fs`, `fl` 등)
+const PAIR_FIRSTS: &str = "fblr";
+const PAIR_SECONDS: &str = "fblrsv";
+/// 방향 글자 말고 읽을 수 있는 수식어 글자.
+const TYPE_MODIFIERS: &str = "mciepgjn";
+
+/// 기물 정의의 이동 하나를 베짜로 적을 때 필요한 정보. 같은 `Atom`끼리 묶어서 한 번에 적습니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Atom<const D: usize> {
+    types: BTreeSet<MovementType>,
+    range: Option<usize>,
+    base: Vec<isize>,
+    offsets: Vec<RelativePosition<D>>,
+}
+
+/// ## Betza
+/// 베짜(XBetza) 표기로 기물의 이동을 읽고 씁니다. 예: 폰은 `fmWfceFifmW2`, 아크비숍은 `BN`.
+/// - 도약: `W F D N A H C Z G`, 합친 기물 `K`(WF), `R`(WW), `B`(FF), `Q`(RB)
+/// - 라이더: 같은 도약을 두 번 적거나(`NN`) 뒤에 최대 칸 수를 적음(`W3`, `R4`). `0`은 끝까지입니다.
+/// - 수식어: `m` 이동만, `c` 잡기만, `f b l r v s` 방향, `i` 처음만, `e` 앙파상,
+///   `n` 막히는 도약(`LAME`), `j` 뛰어넘는 라이더(`JUMP`), `p` 캐논처럼 넘어서(`HOP`), `g` 넘은 바로 다음 칸(`GRASSHOP`)
+/// - 대각선이나 비스듬한 도약에는 방향 두 글자를 묶어 씁니다. `flF`는 왼쪽 앞 대각선, `ffN`은 앞으로 긴 나이트 두 칸입니다.
+/// - `ifm` 라이더는 지나간 칸이 앙파상 칸이 됩니다. (`PASSABLE`)
+///
+/// 3차원 이상에서는 도약이 모든 축으로 펼쳐지고(`W`는 6방향), `u`/`d`는 2번 축의 위/아래,
+/// `(1,1,1)`처럼 괄호에 적은 값은 그 값을 모든 대칭으로 펼친 도약입니다.
+impl<const D: usize> PieceDefinition<D> {
+    /// 베짜 표기의 이동을 더합니다. 방향은 이 기물을 가진 플레이어 쪽에서 본 방향입니다.
+    pub fn with_betza(mut self, text: &str) -> Result<Self> {
+        let mut rest = text.trim();
+        while !rest.is_empty() {
+            let modifiers: String = rest.chars().take_while(char::is_ascii_lowercase).collect();
+            rest = &rest[modifiers.len()..];
+            let (atoms, after) = parse_atom(rest).map_err(|error| anyhow!("{text:?}: {error}"))?;
+            rest = after;
+            let (doubled, after) = match parse_atom(rest) {
+                Ok((again, after)) if again == atoms && !rest.starts_with(['K', 'Q', 'R', 'B']) => (true, after),
+                _ => (false, rest),
+            };
+            rest = after;
+            let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+            rest = &rest[digits.len()..];
+            let range = match (digits.parse::<usize>().ok(), doubled) {
+                (Some(0), _) | (None, true) => Some(usize::MAX),
+                (Some(range), _) => Some(range),
+                (None, false) => None,
+            };
+            for (base, rider) in atoms {
+                let range = if rider { range.or(Some(usize::MAX)) } else { range };
+                self = self.with_betza_atom(&modifiers, &base, range).map_err(|error| anyhow!("{text:?}: {error}"))?;
+            }
+        }
+        Ok(self)
+    }
+
+    fn with_betza_atom(mut self, modifiers: &str, base: &[isize], range: Option<usize>) -> Result<Self> {
+        if let Some(letter) = modifiers.chars().find(|&letter| !DIRECTIONS.contains(letter) && !TYPE_MODIFIERS.contains(letter)) {
+            bail!("unsupported modifier {letter:?}");
+        }
+        let orbit = orbit::<D>(base)?;
+        let oblique = base.iter().filter(|&&value| value != 0).count() > 1;
+        let offsets: Vec<_> = match direction_tokens(modifiers, oblique) {
+            tokens if tokens.is_empty() => orbit,
+            tokens => orbit.into_iter().filter(|offset| tokens.iter().any(|token| in_direction(token, offset))).collect(),
+        };
+        let has = |letter| modifiers.contains(letter);
+        let mut types = Vec::new();
+        if has('m') || !has('c') {
+            types.push(MOVE);
+        }
+        if has('c') || !has('m') {
+            types.push(CAPTURE);
+        }
+        for (letter, movement_type) in [('i', INITIAL), ('e', EN_PASSANT), ('p', HOP), ('g', GRASSHOP)] {
+            if has(letter) {
+                types.push(movement_type);
+            }
+        }
+        if (has('p') || has('g')) && range.is_none() {
+            bail!("{modifiers}: hoppers need a rider");
+        }
+        if has('j') && range.is_none() {
+            bail!("{modifiers}: only riders can jump");
+        }
+        if has('n') && range.is_some() {
+            bail!("{modifiers}: only leapers can be lame");
+        }
+        match range {
+            Some(range) => {
+                if has('j') {
+                    types.push(JUMP);
+                }
+                if has('i') && has('m') && !has('c') {
+                    types.push(PASSABLE);
+                }
+                for offset in offsets {
+                    self = self.with_dependent_move(DependentMove::new(IndependentMove::new(types.iter().copied(), offset), range));
+                }
+            }
+            None => {
+                if has('n') {
+                    types.push(LAME);
+                }
+                for offset in offsets {
+                    self = self.with_independent_move(IndependentMove::new(types.iter().copied(), offset));
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// 이 기물의 이동을 베짜로 적습니다. 조건이 붙은 이동이나 베짜로 나타낼 수 없는 종류와 방향이 있으면 `None`입니다.
+    pub fn to_betza(&self) -> Option<String> {
+        let mut atoms: Vec<Atom<D>> = Vec::new();
+        let leaps = self.get_independent_moves().iter().map(|leap| (leap, None));
+        let rides = self.get_dependent_moves().iter()
+            .map(|ride| (ride.get_start_offset().iter().all(|&value| value == 0)).then_some((ride.get_step(), Some(ride.max_times()))));
+        for (step, range) in leaps.map(Some).chain(rides).collect::<Option<Vec<_>>>()? {
+            if step.get_guard().is_some() {
+                return None;
+            }
+            let types: BTreeSet<_> = step.get_movement_type().iter().cloned().collect();
+            let mut base: Vec<_> = step.get_offset().iter().map(|value| value.abs()).filter(|&value| value != 0).collect();
+            base.sort_unstable_by(|a, b| b.cmp(a));
+            match atoms.iter_mut().find(|atom| atom.types == types && atom.range == range && atom.base == base) {
+                Some(atom) => atom.offsets.push(*step.get_offset()),
+                None => atoms.push(Atom { types, range, base, offsets: vec![*step.get_offset()] }),
+            }
+        }
+        let mut written: Vec<(String, String, Option<usize>)> = Vec::new();
+        for atom in &atoms {
+            let modifiers = type_modifiers(&atom.types, atom.range)?;
+            let orbit = orbit::<D>(&atom.base).ok()?;
+            let oblique = atom.base.len() > 1;
+            for directions in directions_for(&atom.offsets, &orbit, oblique)? {
+                let (first, rest) = modifiers.split_at(usize::from(modifiers.starts_with('i')));
+                written.push((format!("{first}{directions}{rest}"), atom_name(&atom.base), atom.range));
+            }
+        }
+        Some(merge_compounds(written))
+    }
+}
+
+/// 도약 값과 라이더인지.
+type BetzaAtom = (Vec<isize>, bool);
+
+/// 글자 하나의 도약들. `K`, `Q`, `R`, `B`는 두 도약이거나 라이더라서 여러 개 돌려줍니다.
+fn parse_atom(text: &str) -> Result<(Vec<BetzaAtom>, &str)> {
+    let mut chars = text.chars();
+    let letter = chars.next().ok_or_else(|| anyhow!("missing atom"))?;
+    let atoms = match letter {
+        '(' => {
+            let (inside, rest) = text[1..].split_once(')').ok_or_else(|| anyhow!("unclosed atom"))?;
+            let base = inside.split(',').map(|value| value.trim().parse().map_err(|_| anyhow!("bad atom value {value:?}")))
+                .collect::<Result<Vec<isize>>>()?;
+            return Ok((vec![(base, false)], rest));
+        }
+        'K' => vec![(vec![1, 0], false), (vec![1, 1], false)],
+        'Q' => vec![(vec![1, 0], true), (vec![1, 1], true)],
+        'R' => vec![(vec![1, 0], true)],
+        'B' => vec![(vec![1, 1], true)],
+        letter => {
+            let (_, base) = ATOMS.iter().find(|(name, _)| *name == letter).ok_or_else(|| anyhow!("unknown atom {letter:?}"))?;
+            vec![(base.to_vec(), false)]
+        }
+    };
+    Ok((atoms, chars.as_str()))
+}
+
+/// 도약 하나를 모든 대칭으로 펼친 오프셋들. 값이 `D`개보다 많으면 이 보드에서 쓸 수 없습니다.
+fn orbit<const D: usize>(base: &[isize]) -> Result<Vec<RelativePosition<D>>> {
+    let values: Vec<_> = base.iter().copied().filter(|&value| value != 0).collect();
+    if values.len() > D {
+        bail!("atom {base:?} needs more than {D} axes");
+    }
+    let offset = RelativePosition::from(std::array::from_fn(|axis| values.get(axis).copied().unwrap_or(0)));
+    Ok(offset.symmetries(&Symmetry::all()))
+}
+
+/// 수식어에서 방향 토큰들. 비스듬한 도약이면 붙어 있는 방향 두 글자를 하나로 묶습니다.
+fn direction_tokens(modifiers: &str, oblique: bool) -> Vec<String> {
+    let chars: Vec<char> = modifiers.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let letter = chars[index];
+        if !DIRECTIONS.contains(letter) {
+            index += 1;
+            continue;
+        }
+        let pair = chars.get(index + 1).copied()
+            .filter(|&second| oblique && PAIR_FIRSTS.contains(letter) && PAIR_SECONDS.contains(second) && second != opposite(letter));
+        match pair {
+            Some(second) => {
+                tokens.push(format!("{letter}{second}"));
+                index += 2;
+            }
+            None => {
+                tokens.push(letter.to_string());
+                index += 1;
+            }
+        }
+    }
+    tokens
+}
+
+fn opposite(letter: char) -> char {
+    match letter {
+        'f' => 'b',
+        'b' => 'f',
+        'l' => 'r',
+        'r' => 'l',
+        other => other,
+    }
+}
+
+/// 방향 글자 하나. 0번 축이 앞뒤, 1번 축이 왼쪽 오른쪽, 2번 축이 위아래입니다.
+fn toward<const D: usize>(letter: char, offset: &RelativePosition<D>) -> bool {
+    let value = |axis: usize| if axis < D { offset[axis] } else { 0 };
+    match letter {
+        'f' => value(0) > 0,
+        'b' => value(0) < 0,
+        'l' => value(1) < 0,
+        'r' => value(1) > 0,
+        'u' => value(2) > 0,
+        'd' => value(2) < 0,
+        'v' => value(0).abs() > value(1).abs(),
+        's' => value(1).abs() > value(0).abs(),
+        _ => false,
+    }
+}
+
+/// 방향 토큰 하나에 `offset`이 들어가는지. 같은 글자를 두 번 쓰면 그 축으로 긴 쪽입니다. (`ff`, `ll`)
+fn in_direction<const D: usize>(token: &str, offset: &RelativePosition<D>) -> bool {
+    let mut letters = token.chars();
+    let first = letters.next().unwrap_or(' ');
+    match letters.next() {
+        None => toward(first, offset),
+        Some(second) if second == first => toward(first, offset) && toward(if "fb".contains(first) { 'v' } else { 's' }, offset),
+        Some(second) => toward(first, offset) && toward(second, offset),
+    }
+}
+
+/// `offsets`를 방향 토큰들로 나타냅니다. 비스듬한 도약은 토큰마다 도약을 따로 적어야 해서 토큰 하나씩 돌려줍니다.
+fn directions_for<const D: usize>(offsets: &[RelativePosition<D>], orbit: &[RelativePosition<D>], oblique: bool) -> Option<Vec<String>> {
+    let wanted: BTreeSet<_> = offsets.iter().copied().collect();
+    if wanted.len() != offsets.len() || !wanted.iter().all(|offset| orbit.contains(offset)) {
+        return None;
+    }
+    if wanted.len() == orbit.len() {
+        return Some(vec![String::new()]);
+    }
+    let mut candidates: Vec<String> = DIRECTIONS.chars().map(String::from).collect();
+    if oblique {
+        for first in PAIR_FIRSTS.chars() {
+            candidates.extend(PAIR_SECONDS.chars().filter(|&second| second != opposite(first)).map(|second| format!("{first}{second}")));
+        }
+    }
+    let covers = |token: &String| -> BTreeSet<RelativePosition<D>> { orbit.iter().copied().filter(|offset| in_direction(token, offset)).collect() };
+    let mut covered = BTreeSet::new();
+    let mut tokens = Vec::new();
+    while covered != wanted {
+        let best = candidates.iter().rev()
+            .map(|token| (token, covers(token)))
+            .filter(|(_, set)| !set.is_empty() && set.is_subset(&wanted))
+            .max_by_key(|(_, set)| set.difference(&covered).count())
+            .filter(|(_, set)| !set.is_subset(&covered))?;
+        covered.extend(best.1);
+        tokens.push(best.0.clone());
+    }
+    Some(if oblique { tokens } else { vec![tokens.concat()] })
+}
+
+/// 이동 종류를 수식어로 바꿉니다. `i`는 방향보다 앞에 오도록 맨 앞에 둡니다.
+fn type_modifiers(types: &BTreeSet<MovementType>, range: Option<usize>) -> Option<String> {
+    let has = |movement_type: &str| types.contains(movement_type);
+    let mut modifiers = String::new();
+    if has(INITIAL) {
+        modifiers.push('i');
+    }
+    match (has(MOVE), has(CAPTURE)) {
+        (true, true) => {}
+        (true, false) => modifiers.push('m'),
+        (false, true) => modifiers.push('c'),
+        (false, false) => return None,
+    }
+    let mut known = vec![MOVE, CAPTURE, INITIAL];
+    for (letter, movement_type, rider) in [('e', EN_PASSANT, None), ('n', LAME, Some(false)), ('j', JUMP, Some(true)), ('p', HOP, Some(true)), ('g', GRASSHOP, Some(true))] {
+        if has(movement_type) && rider.is_none_or(|rider| rider == range.is_some()) {
+            modifiers.push(letter);
+            known.push(movement_type);
+        }
+    }
+    if has(PASSABLE) && modifiers.starts_with("im") && range.is_some() {
+        known.push(PASSABLE);
+    }
+    if range.is_none() {
+        known.push(JUMP);
+    }
+    types.iter().all(|movement_type| known.contains(&movement_type.as_str())).then_some(modifiers)
+}
+
+/// 도약의 이름. 글자가 있으면 글자, 없으면 괄호에 적은 값입니다.
+fn atom_name(base: &[isize]) -> String {
+    let padded = [base.first().copied().unwrap_or(0), base.get(1).copied().unwrap_or(0)];
+    match ATOMS.iter().find(|(_, atom)| base.len() <= 2 && *atom == padded) {
+        Some((letter, _)) => letter.to_string(),
+        None => format!("({})", base.iter().map(isize::to_string).collect::<Vec<_>>().join(",")),
+    }
+}
+
+/// 적을 도약들을 이어 붙입니다. 수식어와 거리가 같은 `W`와 `F`는 `K`나 `Q`로, 끝까지 가는 `W`, `F`는 `R`, `B`로 씁니다.
+fn merge_compounds(mut written: Vec<(String, String, Option<usize>)>) -> String {
+    let mut text = String::new();
+    while !written.is_empty() {
+        let (modifiers, name, range) = written.remove(0);
+        let unlimited = range == Some(usize::MAX);
+        let partner = (name == "W" && (range.is_none() || unlimited))
+            .then(|| written.iter().position(|(other, other_name, other_range)| *other == modifiers && other_name == "F" && *other_range == range))
+            .flatten()
+            .filter(|_| !modifiers.chars().any(|letter| DIRECTIONS.contains(letter)));
+        let name = match (partner.map(|index| written.remove(index)), name.as_str()) {
+            (Some(_), _) => (if unlimited { "Q" } else { "K" }).to_string(),
+            (None, "W") if unlimited => "R".to_string(),
+            (None, "F") if unlimited => "B".to_string(),
+            (None, _) if unlimited => format!("{name}{name}"),
+            (None, _) => name,
+        };
+        let range = match range {
+            Some(range) if !unlimited => range.to_string(),
+            _ => String::new(),
+        };
+        text.push_str(&format!("{modifiers}{name}{range}"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{game::GameState, presets, rules::Ruleset};
+
+    fn betza(text: &str) -> PieceDefinition<2> {
+        PieceDefinition::new("piece", "P", 1).with_betza(text).unwrap()
+    }
+
+    #[test]
+    fn betza_round_trips_and_moves() {
+        for text in ["N", "K", "Q", "R", "B", "BN", "fmWfceFifmW2", "NN", "W3", "ffN", "fsN", "flFfrW", "mRcpR", "gQ", "nN", "jQ", "vRsW"] {
+            let written = betza(text).to_betza().unwrap();
+            assert_eq!(betza(&written), betza(text), "{text} -> {written}");
+        }
+        for text in ["K", "Q", "fmWfceFifmW2", "ffN", "mRcpR", "gQ"] {
+            assert_eq!(betza(text).to_betza().as_deref(), Some(text));
+        }
+        assert_eq!(presets::knight().to_betza().as_deref(), Some("N"));
+        assert_eq!(presets::queen().to_betza().as_deref(), Some("Q"));
+        assert_eq!(presets::king().to_betza().as_deref(), Some("K"));
+        assert_eq!(presets::pawn().to_betza().as_deref(), Some("fmWfceFifmW2"));
+        assert_eq!(presets::drone().to_betza().as_deref(), Some("jQ"));
+        assert_eq!(betza("ffN").get_independent_moves().len(), 2);
+        assert_eq!(betza("fsN").get_independent_moves().len(), 2);
+        assert_eq!(betza("fN").get_independent_moves().len(), 4);
+        assert!(presets::turtle().to_betza().is_none());
+        assert!(PieceDefinition::<2>::new("x", "X", 1).with_betza("Y").is_err());
+        for text in ["aN", "oR", "xyB", "jN", "nR"] {
+            assert!(PieceDefinition::<2>::new("x", "X", 1).with_betza(text).is_err(), "{text}");
+        }
+
+        let cube = PieceDefinition::<3>::new("piece", "P", 1).with_betza("W(1,1,1)").unwrap();
+        assert_eq!(cube.get_independent_moves().len(), 14);
+        assert_eq!(PieceDefinition::<3>::new("piece", "P", 1).with_betza("uW").unwrap().get_independent_moves().len(), 1);
+        assert!(PieceDefinition::<2>::new("piece", "P", 1).with_betza("(1,1,1)").is_err());
+
+        let mut rules = Ruleset::new(2);
+        for player in 0..2 {
+            rules.define(player, presets::king());
+            rules.define(player, betza("mRcpR").with_betza("nN").unwrap());
+            rules.define(player, PieceDefinition::new("grasshopper", "G", 2).with_betza("gQ").unwrap());
+        }
+        let mut game = GameState::from_fen("4k3/8/8/4p3/8/8/1p6/PPG1K3 w - - 0 1", presets::standard_board(), Arc::new(rules)).unwrap();
+        let moves: BTreeSet<_> = game.legal_moves().iter().map(ToString::to_string).collect();
+        assert!(moves.contains("c1a3") && !moves.contains("c1b2") && !moves.contains("c1c2"));
+        assert!(moves.contains("a1a2") && moves.contains("a1b3") && !moves.contains("a1c2"));
+        assert!(!moves.contains("b1b2") && !moves.contains("b1b3"));
+    }
+}
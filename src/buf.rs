@@ -1,22 +1,6 @@
-use std::{
-    fmt::{self, Display, Formatter},
-    collections::{
-        HashMap,
-        hash_map::Entry,
-        BTreeMap,
-        HashSet
-    },
-    sync::Arc,
-    any::Any,
-    vec
-};
-use rayon::prelude::{ParallelIterator, IntoParallelIterator};
+use std::collections::HashMap;
 use regex::Regex;
 use lazy_static::lazy_static;
-use crate::{
-    traits::Dimension,
-    position::{AbsolutePosition, RelativePosition},
-};
 
 /*
 Piece
@@ -291,7 +275,7 @@ Ls
 */
 
 lazy_static! {
-    static ref PLAYER_INPUT_RE: Regex = Regex::new(
+    pub(crate) static ref PLAYER_INPUT_RE: Regex = Regex::new(
         r"(?P<name>[A-Za-z]*)(?P<start_col>[A-Za-z]*)(?P<start_row>\d*)(?P<takes>[Xx]?)(?P<end_col>[A-Za-z]+)(?P<end_row>\d+)(?P<other>.*)"
     ).unwrap();
     static ref OTHER_MOVE_CAPTURE: HashMap<String, Vec<String>> = HashMap::from([("move_type".to_string(), vec!["move".to_string(), "capture".to_string()])]);
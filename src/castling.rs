@@ -0,0 +1,271 @@
+use crate::{
+    game::GameState,
+    movement::CASTLING,
+    moves::Move,
+    ownership::Owner,
+    piece::PlayerId,
+    position::{AbsolutePosition, RelativePosition},
+    rules::CASTLE,
+};
+
+/// 표준 체스에서 캐슬링하는 축. (파일이 바뀌는 축)
+const FILE_AXIS: usize = 1;
+
+/// ## CastlingDistance
+/// 캐슬링이 끝났을 때 킹과 파트너가 서는 자리.
+/// - `Fixed`: 킹 쪽 축 좌표가 작은 쪽과 큰 쪽으로 캐슬링할 때 킹과 파트너가 서는 좌표 (체스960)
+/// - `Steps`: 킹이 파트너 쪽으로 이만큼 가고, 파트너는 킹을 넘어 바로 옆에 섬 (표준 체스는 2)
+/// - `Any`: 킹이 파트너 쪽으로 두 칸 이상 아무 데나 가고, 파트너는 킹을 넘어 바로 옆에 섬
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CastlingDistance {
+    Fixed { king: (usize, usize), partner: (usize, usize) },
+    Steps(usize),
+    Any,
+}
+
+/// ## Castling
+/// 캐슬링 규칙. `CASTLE` 기물(킹)이 같은 줄의 파트너 기물과 함께 움직입니다.
+/// - `partners`: 킹과 캐슬링할 수 있는 기물 이름. 비어 있으면 캐슬링이 없습니다.
+/// - `axes`: 캐슬링할 수 있는 축. 킹과 파트너는 이 축을 뺀 좌표가 같아야 합니다.
+/// - `requires_unmoved`: 켜져 있으면 캐슬링 권리(`GameState::get_castling`)가 남은 파트너와만 캐슬링합니다.
+///   꺼져 있으면 움직인 적이 있어도 킹에서 그 축으로 처음 만나는 자기 파트너와 캐슬링합니다.
+/// - `max_gap`: 킹과 파트너 사이 거리의 최댓값. 없으면 거리 제한이 없습니다.
+///
+/// 어느 경우든 킹과 파트너가 지나가거나 서는 칸은 비어 있어야 하고, 킹이 지나가는 칸은 공격받지 않아야 합니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Castling {
+    partners: Vec<String>,
+    axes: Vec<usize>,
+    requires_unmoved: bool,
+    distance: CastlingDistance,
+    max_gap: Option<usize>,
+}
+
+impl Castling {
+    /// 표준 체스: 움직인 적 없는 킹과 룩이 랭크를 따라 캐슬링하고, 킹은 룩 쪽으로 두 칸 갑니다.
+    pub fn standard() -> Self {
+        Self {
+            partners: vec!["rook".to_string()],
+            axes: vec![FILE_AXIS],
+            requires_unmoved: true,
+            distance: CastlingDistance::Steps(2),
+            max_gap: None,
+        }
+    }
+
+    /// 체스960: 킹과 룩이 어디서 시작하든 끝나면 표준 체스처럼 c/d 파일이나 g/f 파일에 섭니다.
+    pub fn chess960() -> Self {
+        Self::standard().with_distance(CastlingDistance::Fixed { king: (2, 6), partner: (3, 5) })
+    }
+
+    /// `buf.rs`의 장거리 캐슬링: 킹과 같은 랭크나 파일에 있는 룩과 거리에 상관없이, 둘 다 움직였어도 캐슬링합니다.
+    pub fn long_range() -> Self {
+        Self::standard().with_axes([0, FILE_AXIS]).with_requires_unmoved(false)
+    }
+
+    /// 캐슬링이 없는 규칙.
+    pub fn none() -> Self {
+        Self { partners: Vec::new(), ..Self::standard() }
+    }
+
+    pub fn with_partner(mut self, name: impl Into<String>) -> Self {
+        self.partners.push(name.into());
+        self
+    }
+
+    pub fn with_axes(mut self, axes: impl IntoIterator<Item = usize>) -> Self {
+        self.axes = axes.into_iter().collect();
+        self
+    }
+
+    pub fn with_requires_unmoved(mut self, requires_unmoved: bool) -> Self {
+        self.requires_unmoved = requires_unmoved;
+        self
+    }
+
+    pub fn with_distance(mut self, distance: CastlingDistance) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    pub fn with_max_gap(mut self, max_gap: usize) -> Self {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    #[inline(always)]
+    pub fn is_partner(&self, name: &str) -> bool {
+        self.partners.iter().any(|partner| partner == name)
+    }
+
+    #[inline(always)]
+    pub fn get_axes(&self) -> &[usize] {
+        &self.axes
+    }
+
+    #[inline(always)]
+    pub fn requires_unmoved(&self) -> bool {
+        self.requires_unmoved
+    }
+
+    #[inline(always)]
+    pub fn get_distance(&self) -> &CastlingDistance {
+        &self.distance
+    }
+
+    #[inline(always)]
+    pub fn get_max_gap(&self) -> Option<usize> {
+        self.max_gap
+    }
+
+    /// 킹이 `king`에서, 파트너가 `gap`칸 떨어진 `direction` 쪽에 있을 때 (킹이 설 좌표, 파트너가 설 좌표) 후보들.
+    fn landings(&self, king: usize, gap: usize, direction: isize) -> Vec<(usize, usize)> {
+        let toward = |steps: usize| king.checked_add_signed(direction * steps as isize);
+        let behind = |steps: usize| Some((toward(steps)?, toward(steps - 1)?));
+        match &self.distance {
+            CastlingDistance::Fixed { king, partner } if direction < 0 => vec![(king.0, partner.0)],
+            CastlingDistance::Fixed { king, partner } => vec![(king.1, partner.1)],
+            CastlingDistance::Steps(steps) => (*steps > 0 && *steps < gap).then(|| behind(*steps)).flatten().into_iter().collect(),
+            CastlingDistance::Any => (2..gap).filter_map(behind).collect(),
+        }
+    }
+}
+
+impl Default for Castling {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// 차례인 플레이어의 캐슬링 수. 규칙의 `Castling`이 정한 축과 파트너마다 만듭니다.
+    pub(crate) fn castling_moves(&self, player: PlayerId, moves: &mut Vec<Move<D>>) {
+        let rules = self.get_rules();
+        let castling = rules.get_castling();
+        if castling.partners.is_empty() {
+            return;
+        }
+        let board = self.get_board();
+        let kings: Vec<_> = board.pieces()
+            .filter(|(position, piece)| piece.get_owner() == Owner::Player(player) && !self.is_immobilized(position))
+            .filter(|(_, piece)| rules.get_definition(piece).is_some_and(|definition| definition.has_attribute(CASTLE)))
+            .map(|(position, _)| *position)
+            .collect();
+        for king in &kings {
+            for &axis in castling.axes.iter().filter(|&&axis| axis < D) {
+                for direction in [-1, 1] {
+                    for partner in self.castling_partners(player, king, axis, direction) {
+                        let gap = king[axis].abs_diff(partner[axis]);
+                        if castling.max_gap.is_some_and(|max_gap| gap > max_gap) {
+                            continue;
+                        }
+                        for (king_to, partner_to) in castling.landings(king[axis], gap, direction) {
+                            if let Some(mv) = self.castling_move(player, king, &partner, axis, king_to, partner_to) {
+                                moves.push(mv);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// `king`에서 `axis` 축 `direction` 쪽으로 캐슬링할 수 있는 파트너의 칸.
+    /// 캐슬링 권리가 필요하면 권리가 남은 칸을, 아니면 그 쪽으로 처음 만나는 기물이 자기 파트너일 때 그 칸을 돌려줍니다.
+    fn castling_partners(&self, player: PlayerId, king: &AbsolutePosition<D>, axis: usize, direction: isize) -> Vec<AbsolutePosition<D>> {
+        let board = self.get_board();
+        let castling = self.get_rules().get_castling();
+        let is_partner = |position: &AbsolutePosition<D>| !self.is_immobilized(position) && board.get(position)
+            .is_some_and(|piece| piece.get_owner() == Owner::Player(player) && castling.is_partner(piece.get_name()));
+        if castling.requires_unmoved {
+            return self.get_castling().iter()
+                .filter(|partner| (0..D).all(|other| other == axis || partner[other] == king[other]))
+                .filter(|partner| (partner[axis] as isize - king[axis] as isize).signum() == direction)
+                .filter(|partner| is_partner(partner))
+                .copied()
+                .collect();
+        }
+        let mut step = [0; D];
+        step[axis] = direction;
+        let step = RelativePosition::from(step);
+        let mut position = *king;
+        while let Some(next) = board.offset(&position, &step) {
+            if board.get(&next).is_some() {
+                return if is_partner(&next) { vec![next] } else { Vec::new() };
+            }
+            position = next;
+        }
+        Vec::new()
+    }
+
+    /// 킹이 `axis` 축 좌표 `king_to`로, 파트너가 `partner_to`로 가는 캐슬링 수.
+    /// 그 사이가 비어 있지 않거나 킹이 지나가는 칸이 공격받으면 `None`입니다.
+    fn castling_move(&self, player: PlayerId, king: &AbsolutePosition<D>, partner: &AbsolutePosition<D>, axis: usize, king_to: usize, partner_to: usize) -> Option<Move<D>> {
+        let board = self.get_board();
+        let on_axis = |base: &AbsolutePosition<D>, coordinate: usize| {
+            let mut position = *base;
+            position[axis] = coordinate;
+            board.contains(&position).then_some(position)
+        };
+        let king_square = on_axis(king, king_to)?;
+        let partner_square = on_axis(partner, partner_to)?;
+        let coordinates = [king[axis], partner[axis], king_to, partner_to];
+        let (low, high) = (*coordinates.iter().min()?, *coordinates.iter().max()?);
+        let clear = (low..=high)
+            .filter_map(|coordinate| on_axis(king, coordinate))
+            .all(|position| position == *king || position == *partner || board.get(&position).is_none());
+        if !clear {
+            return None;
+        }
+        let (path_low, path_high) = (king[axis].min(king_to), king[axis].max(king_to));
+        let safe = self.is_fog_of_war() || (path_low..=path_high)
+            .filter_map(|coordinate| on_axis(king, coordinate))
+            .all(|position| !self.is_attacked_by_others(&position, player));
+        safe.then(|| Move::new(*king, king_square, CASTLING).with_displacement(*partner, partner_square))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{fen::parse_square, presets};
+
+    fn castles(game: &mut GameState<2>) -> Vec<String> {
+        let mut castles: Vec<_> = game.legal_moves().into_iter()
+            .filter(|mv| !mv.get_displacements().is_empty())
+            .map(|mv| mv.to_string())
+            .collect();
+        castles.sort();
+        castles
+    }
+
+    #[test]
+    fn castling_follows_partners_axes_and_distance() {
+        let mut game = GameState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        assert_eq!(castles(&mut game), ["e1c1", "e1g1"]);
+
+        let fen = "4k3/8/8/8/8/8/8/RK5R w HA - 0 1";
+        let mut game = GameState::from_fen(fen, presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        assert_eq!(castles(&mut game), ["b1d1"]);
+        let mut game = GameState::from_fen(fen, presets::standard_board(), Arc::new(presets::chess960())).unwrap();
+        assert_eq!(castles(&mut game), ["b1c1", "b1g1"]);
+
+        let fen = "2r1k3/8/8/8/4R3/8/8/R3K3 w - - 0 1";
+        let mut game = GameState::from_fen(fen, presets::standard_board(), Arc::new(presets::long_castling_chess())).unwrap();
+        game.set_hash_check(true);
+        assert_eq!(castles(&mut game), ["e1e3"]);
+        let hash = game.hash();
+        let mv = game.legal_moves().into_iter().find(|mv| mv.to_string() == "e1e3").unwrap();
+        game.make_move(mv);
+        assert_eq!(game.get_board().get(&parse_square("e2").unwrap()).unwrap().get_name(), "rook");
+        game.unmake_move();
+        assert_eq!(game.hash(), hash);
+
+        let rules = presets::standard_chess().with_castling(Castling::long_range().with_max_gap(3));
+        let mut game = GameState::from_fen("4k3/8/8/8/4R3/8/8/R3K3 w - - 0 1", presets::standard_board(), Arc::new(rules)).unwrap();
+        assert_eq!(castles(&mut game), ["e1e3"]);
+        let mut game = GameState::from_fen("4k3/8/8/8/4R3/8/8/R3K3 w - - 0 1", presets::standard_board(), Arc::new(presets::long_castling_chess())).unwrap();
+        assert_eq!(castles(&mut game), ["e1c1", "e1e3"]);
+    }
+}
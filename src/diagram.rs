@@ -0,0 +1,604 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::Arc,
+};
+use anyhow::{bail, Result};
+use crate::{
+    effects::EffectKind,
+    file_scan::{ActionFormat, FileFormat, MoveFormat},
+    game::GameState,
+    movement::{IndependentMove, MOVE, CAPTURE, JUMP, HOP, GRASSHOP, SHOOT, AREA, CATCH, GRANT, RIDE},
+    position::{AbsolutePosition, Board},
+    rules::{PieceDefinition, Ruleset},
+};
+
+/// ## DiagramStyle
+/// 이동 그림을 찍는 방법.
+/// - `Emoji`: `buf.rs`의 그림처럼 ⬜️⬛️ 칸에 ⭕️❌️ 같은 이모지
+/// - `Ascii`: 이모지를 못 쓰는 곳을 위한 글자 한 개짜리 칸
+/// - `Ansi`: `Ascii`에 터미널 색을 입힌 것
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiagramStyle {
+    #[default]
+    Emoji,
+    Ascii,
+    Ansi,
+}
+
+/// ## LegendEntry
+/// 움직임 종류 하나를 그림에서 어떻게 보여줄지.
+/// `kind`는 이동 종류(`MOVE`, `CAPTURE`, ..), 액션 종류(`CATCH`, ..), 액션 효과 이름(`stun`) 중 하나입니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegendEntry {
+    kind: String,
+    label: String,
+    emoji: String,
+    ascii: char,
+    color: u8,
+}
+
+impl LegendEntry {
+    pub fn new(kind: impl Into<String>, label: impl Into<String>, emoji: impl Into<String>, ascii: char, color: u8) -> Self {
+        Self { kind: kind.into(), label: label.into(), emoji: emoji.into(), ascii, color }
+    }
+
+    #[inline(always)]
+    pub fn get_kind(&self) -> &String {
+        &self.kind
+    }
+
+    #[inline(always)]
+    pub fn get_label(&self) -> &String {
+        &self.label
+    }
+}
+
+/// `buf.rs`의 범례. 앞에 있을수록 먼저 보입니다. 액션은 같은 칸의 이동보다 앞입니다.
+fn default_legend() -> Vec<LegendEntry> {
+    vec![
+        LegendEntry::new(RIDE, "ride", "⬇️", 'v', 36),
+        LegendEntry::new(CATCH, "catch", "🔺️", '^', 35),
+        LegendEntry::new("stun", "stun", "💫", '*', 33),
+        LegendEntry::new(SHOOT, "shoot", "🎯", '+', 31),
+        LegendEntry::new(AREA, "area", "💥", '#', 33),
+        LegendEntry::new(GRANT, "grant", "🎵", '!', 34),
+        LegendEntry::new(MOVE, "move", "⭕️", 'o', 32),
+        LegendEntry::new(CAPTURE, "take", "❌️", 'x', 31),
+    ]
+}
+
+/// ## Diagram
+/// 기물 하나가 `origin`에서 닿는 칸을 `buf.rs`의 그림처럼 찍습니다. 게임을 시작하지 않고 기물 정의를 확인할 때 씁니다.
+/// - `axes`: 3차원 이상에서 자를 두 축. (행, 열) 순서이고 나머지 축은 `origin`의 값으로 고정합니다.
+/// - `piece`: `origin`에 그릴 글자. 없으면 기물의 짧은 이름입니다.
+/// - `legend`: 움직임 종류별 그림. `with_legend_entry`로 바꾸거나 더합니다.
+///
+/// 위쪽 줄이 큰 행(8랭크)이고, 그림 아래에 그림에 나온 종류만 `⭕️=move`처럼 적습니다.
+/// 한 칸에 이동과 잡기가 같이 있으면 `move&take`이고, 액션이 닿는 칸은 액션으로 보입니다.
+/// 보드의 다른 기물은 이동을 막고, 잡기는 기물이 없어도 닿는 칸에 전부 표시합니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagram<const D: usize> {
+    origin: AbsolutePosition<D>,
+    axes: (usize, usize),
+    style: DiagramStyle,
+    piece: Option<String>,
+    legend: Vec<LegendEntry>,
+}
+
+impl<const D: usize> Diagram<D> {
+    pub fn new(origin: AbsolutePosition<D>) -> Self {
+        Self { origin, axes: (0, 1), style: DiagramStyle::default(), piece: None, legend: default_legend() }
+    }
+
+    pub fn with_axes(mut self, row: usize, column: usize) -> Self {
+        self.axes = (row, column);
+        self
+    }
+
+    pub fn with_style(mut self, style: DiagramStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn with_piece(mut self, piece: impl Into<String>) -> Self {
+        self.piece = Some(piece.into());
+        self
+    }
+
+    /// 같은 `kind`가 있으면 바꾸고, 없으면 맨 앞에 더합니다.
+    pub fn with_legend_entry(mut self, entry: LegendEntry) -> Self {
+        match self.legend.iter_mut().find(|old| old.kind == entry.kind) {
+            Some(old) => *old = entry,
+            None => self.legend.insert(0, entry),
+        }
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_origin(&self) -> &AbsolutePosition<D> {
+        &self.origin
+    }
+
+    #[inline(always)]
+    pub fn get_style(&self) -> DiagramStyle {
+        self.style
+    }
+
+    /// 빈 `board`에 `definition`을 놓고 그립니다.
+    pub fn render_empty(&self, board: Board<D>, definition: &PieceDefinition<D>) -> String {
+        self.render(&GameState::new(board, Arc::new(Ruleset::new(1))), definition)
+    }
+
+    /// `game`의 보드에 `definition`을 놓고 그립니다. `origin`에 있던 기물은 없는 것으로 봅니다.
+    pub fn render(&self, game: &GameState<D>, definition: &PieceDefinition<D>) -> String {
+        let marks = self.marks(game, definition);
+        let board = game.get_board();
+        let (min, max) = (board.get_min_position(), board.get_max_position());
+        let (row, column) = self.axes;
+        let mut text = String::new();
+        for rank in (min[row]..=max[row]).rev() {
+            for file in min[column]..=max[column] {
+                let mut position = self.origin;
+                position[row] = rank;
+                position[column] = file;
+                let light = (rank + file) % 2 == 1;
+                if position == self.origin {
+                    let name = self.piece.clone().unwrap_or_else(|| definition.get_short_name().clone());
+                    text += &self.cell_text(&name, light, None);
+                } else if let Some(&(_, visual)) = marks.get(&position) {
+                    let entry = &self.legend[visual];
+                    text += &self.cell_text(&entry.emoji, light, Some(entry));
+                } else if let Some(piece) = board.get(&position) {
+                    text += &self.cell_text(piece.get_short_name(), light, None);
+                } else {
+                    text += &self.cell_text("", light, None);
+                }
+            }
+            text.push('\n');
+        }
+        let mut shown: Vec<_> = marks.values().collect::<BTreeSet<_>>().into_iter().collect();
+        shown.sort_by_key(|(label, visual)| (*visual, label.contains('&')));
+        for (label, visual) in shown {
+            let entry = &self.legend[*visual];
+            text += &format!("{}={label}\n", self.cell_text(&entry.emoji, false, Some(entry)));
+        }
+        text
+    }
+
+    /// 칸 하나. `mark`가 없으면 `text`는 기물 이름이고, 비어 있으면 빈 칸입니다.
+    fn cell_text(&self, text: &str, light: bool, mark: Option<&LegendEntry>) -> String {
+        let letter = mark.map_or_else(|| text.chars().next().unwrap_or(if light { '.' } else { ':' }), |entry| entry.ascii);
+        match self.style {
+            DiagramStyle::Emoji => match (mark, text.chars().next()) {
+                (Some(_), _) => text.to_string(),
+                (None, None) => if light { "⬜️" } else { "⬛️" }.to_string(),
+                (None, Some(_)) if text.chars().count() > 1 || !text.is_ascii() => text.to_string(),
+                (None, Some(letter)) => full_width(letter).to_string(),
+            },
+            DiagramStyle::Ascii => letter.to_string(),
+            DiagramStyle::Ansi => {
+                let background = if light { 47 } else { 100 };
+                let foreground = mark.map_or(30, |entry| entry.color);
+                format!("\x1b[{background};{foreground}m{letter} \x1b[0m")
+            }
+        }
+    }
+
+    /// 그림에 나올 칸마다 (범례 이름, 그릴 범례 번호).
+    fn marks(&self, game: &GameState<D>, definition: &PieceDefinition<D>) -> BTreeMap<AbsolutePosition<D>, (String, usize)> {
+        let index = |kind: &str| self.legend.iter().position(|entry| entry.kind == kind);
+        let mut kinds: BTreeMap<AbsolutePosition<D>, BTreeSet<usize>> = BTreeMap::new();
+        for (position, step) in self.step_reach(game, definition) {
+            kinds.entry(position).or_default().extend(step.get_movement_type().iter().filter_map(|kind| index(kind)));
+        }
+        for action in definition.get_actions() {
+            let effect = action.get_effect().map(|effect| effect.kind.name());
+            let Some(kind) = effect.and_then(index).or_else(|| index(action.get_action_type())) else {
+                continue;
+            };
+            for position in game.action_reach(&self.origin, action) {
+                kinds.entry(position).or_default().insert(kind);
+            }
+        }
+        let (moves, takes) = (index(MOVE), index(CAPTURE));
+        let mut marks: BTreeMap<_, _> = kinds.into_iter()
+            .filter(|(position, _)| self.in_slice(position))
+            .filter_map(|(position, kinds)| {
+                let first = *kinds.first()?;
+                let label = match takes {
+                    Some(take) if Some(first) == moves && kinds.contains(&take) => format!("{}&{}", self.legend[first].label, self.legend[take].label),
+                    _ => self.legend[first].label.clone(),
+                };
+                Some((position, (label, first)))
+            })
+            .collect();
+        // 이동만 하는 칸이 있으면 move&take는 ❌️로 그립니다. (신호등 그림)
+        if let (Some(moves), Some(takes)) = (moves, takes) {
+            let single = |visual| marks.values().any(|(label, other)| *other == visual && !label.contains('&'));
+            if single(moves) && !single(takes) {
+                for (label, visual) in marks.values_mut() {
+                    if *visual == moves && label.contains('&') {
+                        *visual = takes;
+                    }
+                }
+            }
+        }
+        marks
+    }
+
+    /// 자른 면 위의 칸인지.
+    fn in_slice(&self, position: &AbsolutePosition<D>) -> bool {
+        (0..D).all(|axis| axis == self.axes.0 || axis == self.axes.1 || position[axis] == self.origin[axis])
+    }
+
+    /// 이동 정의로 닿는 칸과 그 칸에 닿은 이동. 기물의 상태와 조건은 보지 않습니다.
+    fn step_reach(&self, game: &GameState<D>, definition: &PieceDefinition<D>) -> Vec<(AbsolutePosition<D>, IndependentMove<D>)> {
+        let board = game.get_board();
+        let from = &self.origin;
+        let occupied = |position: &AbsolutePosition<D>| position != from && board.get(position).is_some();
+        let mut reach = Vec::new();
+        for leap in definition.get_independent_moves() {
+            let lame = leap.lame_path().iter().any(|offset| board.offset(from, offset).is_some_and(|position| occupied(&position)));
+            if let Some(target) = board.offset(from, leap.get_offset()).filter(|_| !lame) {
+                reach.push((target, leap.clone()));
+            }
+        }
+        for ride in definition.get_dependent_moves() {
+            let hopper = ride.get_step().has_type(HOP) || ride.get_step().has_type(GRASSHOP);
+            let mut hurdle = false;
+            for step in ride.clone() {
+                let Some(target) = board.offset(from, step.get_offset()) else {
+                    break;
+                };
+                if hopper && !hurdle {
+                    hurdle = occupied(&target);
+                    continue;
+                }
+                reach.push((target, step.clone()));
+                if step.has_type(GRASSHOP) || (occupied(&target) && !step.has_type(JUMP)) {
+                    break;
+                }
+            }
+        }
+        reach
+    }
+}
+
+/// 이모지 칸과 너비를 맞춘 전각 글자.
+fn full_width(letter: char) -> char {
+    if letter.is_ascii_graphic() {
+        char::from_u32(letter as u32 - 0x21 + 0xFF01).unwrap_or(letter)
+    } else {
+        letter
+    }
+}
+
+/// 보드 칸으로 읽는 글자. 이모지의 변형 선택자는 빼고 비교합니다.
+const SQUARES: [&str; 4] = ["⬜", "⬛", ".", ":"];
+
+/// 그 방향으로 미끄러진다는 화살표. (선풍기의 push 그림)
+const ARROWS: [(&str, (isize, isize)); 12] = [
+    ("⬆", (1, 0)), ("⬇", (-1, 0)), ("⬅", (0, -1)), ("➡", (0, 1)),
+    ("↗", (1, 1)), ("↖", (1, -1)), ("↘", (-1, 1)), ("↙", (-1, -1)),
+    ("^", (1, 0)), ("v", (-1, 0)), ("<", (0, -1)), (">", (0, 1)),
+];
+
+/// ## ParsedDiagram
+/// `parse_diagram`이 읽은 기물 파일과, 그림만으로는 하나로 정할 수 없어서 하나를 고른 곳들의 경고.
+pub struct ParsedDiagram {
+    format: FileFormat,
+    warnings: Vec<String>,
+}
+
+impl ParsedDiagram {
+    #[inline(always)]
+    pub fn get_format(&self) -> &FileFormat {
+        &self.format
+    }
+
+    #[inline(always)]
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
+/// 한 줄을 칸 글자들로 나눕니다. 변형 선택자는 빼고, ZWJ로 이어진 이모지는 한 칸입니다.
+fn glyphs(line: &str) -> Vec<String> {
+    let mut glyphs: Vec<String> = Vec::new();
+    let mut joined = false;
+    for letter in line.chars().filter(|&letter| letter != '\u{FE0F}') {
+        if letter.is_whitespace() {
+            joined = false;
+            continue;
+        }
+        match glyphs.last_mut() {
+            Some(last) if joined || letter == '\u{200D}' || letter == '\u{20E3}' => last.push(letter),
+            _ => glyphs.push(letter.to_string()),
+        }
+        joined = letter == '\u{200D}';
+    }
+    glyphs
+}
+
+/// 범례 이름(`move&take`)을 움직임 종류들로 바꿉니다. 모르는 이름은 따로 돌려줍니다.
+fn legend_kinds(label: &str) -> (Vec<String>, Vec<String>) {
+    let legend = default_legend();
+    let (mut known, mut unknown) = (Vec::new(), Vec::new());
+    for word in label.split('&').map(|word| word.trim().to_lowercase()) {
+        match legend.iter().find(|entry| entry.label == word || entry.kind == word) {
+            Some(entry) => known.push(entry.kind.clone()),
+            None if is_effect(&word) => known.push(word),
+            None => unknown.push(word),
+        }
+    }
+    (known, unknown)
+}
+
+/// 액션이 거는 효과 이름인지.
+fn is_effect(kind: &str) -> bool {
+    !matches!(EffectKind::from_name(kind), EffectKind::Custom(_))
+}
+
+/// 칸에 닿는 이동이 아니라 제자리에서 하는 액션인지.
+fn is_action(kind: &str) -> bool {
+    [CATCH, SHOOT, AREA, GRANT].contains(&kind) || is_effect(kind)
+}
+
+fn gcd(first: isize, second: isize) -> isize {
+    if second == 0 { first.abs() } else { gcd(second, first % second) }
+}
+
+/// `buf.rs`처럼 그린 이동 그림을 기물 파일로 읽습니다. 2차원 그림이고 위쪽 줄이 앞입니다.
+/// - 범례에 없는 글자 하나가 기물입니다. 보드 칸(⬜️⬛️ 또는 `.` `:`)과 화살표가 아닌 글자가 둘 이상이면 오류입니다.
+/// - `⭕️=move` 같은 범례 줄이 기본 범례(`Diagram`의 범례)를 덮어씁니다. 종류를 알 수 없는 범례의 글자는 표시가 아닙니다.
+/// - 기물에서 한 방향으로 빈틈없이 이어진 칸들은 미끄러지는 이동이고, 보드 끝까지 가면 끝없이 갑니다.
+///   따로 떨어진 칸은 한 번에 가는 이동입니다.
+/// - 화살표 칸은 기물에서 그 방향으로 끝없이 미끄러진다는 뜻입니다. 종류는 그 방향의 가장 가까운 표시를 따릅니다.
+///
+/// 보드 끝에 닿는 한 칸, 끝까지 가지 않는 줄, 액션 표시 뒤에 가려진 줄은 한쪽으로 읽고 경고를 남깁니다.
+pub fn parse_diagram(text: &str, name: &str, short_name: &str, point: i32) -> Result<ParsedDiagram> {
+    let mut warnings = Vec::new();
+    let mut marks: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in default_legend() {
+        marks.insert(glyphs(&entry.emoji).concat(), vec![entry.kind.clone()]);
+        marks.insert(entry.ascii.to_string(), vec![entry.kind]);
+    }
+    let mut explicit = HashSet::new();
+    let mut rows = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let Some((glyph_text, label)) = line.split_once('=') else {
+            rows.push(glyphs(line));
+            continue;
+        };
+        let (known, unknown) = legend_kinds(label);
+        if !known.is_empty() {
+            for word in unknown {
+                warnings.push(format!("unknown movement kind {word:?} in {line:?} is ignored"));
+            }
+        }
+        for glyph in glyphs(glyph_text) {
+            explicit.insert(glyph.clone());
+            if known.is_empty() {
+                marks.remove(&glyph);
+            } else {
+                marks.insert(glyph, known.clone());
+            }
+        }
+    }
+    let width = rows.first().map_or(0, Vec::len);
+    if rows.iter().any(|row| row.len() != width) {
+        bail!("diagram rows have different widths");
+    }
+
+    let mut pieces = Vec::new();
+    let mut marked = BTreeMap::new();
+    let mut arrows = Vec::new();
+    for (row, glyphs) in rows.iter().enumerate() {
+        for (column, glyph) in glyphs.iter().enumerate() {
+            let arrow = ARROWS.iter().find(|(arrow, _)| arrow == glyph).map(|&(_, direction)| direction);
+            if let (Some(_), Some(kinds)) = (arrow, marks.get(glyph)) {
+                if !explicit.contains(glyph) {
+                    warnings.push(format!("{glyph} is read as {} rather than an arrow", kinds.join("&")));
+                }
+            }
+            if SQUARES.contains(&glyph.as_str()) {
+                continue;
+            } else if let Some(kinds) = marks.get(glyph) {
+                marked.insert((row, column), kinds.clone());
+            } else if let Some(direction) = arrow {
+                arrows.push(((row, column), direction));
+            } else {
+                pieces.push((row, column, glyph));
+            }
+        }
+    }
+    let (origin_row, origin_column) = match pieces[..] {
+        [(row, column, _)] => (row as isize, column as isize),
+        [] => bail!("diagram has no piece"),
+        _ => bail!("several glyphs could be the piece: {}", pieces.iter().map(|(_, _, glyph)| glyph.as_str()).collect::<Vec<_>>().join(" ")),
+    };
+    let offset = |(row, column): (usize, usize)| (origin_row - row as isize, column as isize - origin_column);
+    let on_board = |(rank, file): (isize, isize)| {
+        (0..rows.len() as isize).contains(&(origin_row - rank)) && (0..width as isize).contains(&(origin_column + file))
+    };
+    let marked: BTreeMap<_, _> = marked.into_iter().map(|(cell, kinds)| (offset(cell), kinds)).collect();
+
+    // 화살표가 가리키는 방향과 그 방향으로 미끄러지는 종류들.
+    let mut slides: BTreeMap<(isize, isize), Vec<String>> = BTreeMap::new();
+    for (cell, direction) in arrows {
+        let (rank, file) = offset(cell);
+        let steps = if direction.0 != 0 { rank / direction.0 } else { file / direction.1 };
+        if steps < 1 || (direction.0 * steps, direction.1 * steps) != (rank, file) {
+            warnings.push(format!("arrow at ({rank}, {file}) does not point away from the piece and is ignored"));
+            continue;
+        }
+        let nearest = (1..steps).rev().find_map(|step| marked.get(&(direction.0 * step, direction.1 * step)));
+        let kinds = nearest.cloned().unwrap_or_else(|| {
+            warnings.push(format!("arrow at ({rank}, {file}) has no movement kind before it and is read as move"));
+            vec![MOVE.to_string()]
+        });
+        slides.entry(direction).or_default().extend(kinds);
+    }
+
+    // 종류마다, 방향마다 미끄러짐과 한 번에 가는 칸을 나눕니다.
+    let mut found: Vec<String> = marked.values().chain(slides.values()).flatten().cloned().collect();
+    found.sort();
+    found.dedup();
+    let mut moves: BTreeMap<(Vec<isize>, Option<usize>), Vec<String>> = BTreeMap::new();
+    for kind in &found {
+        let cells: BTreeSet<_> = marked.iter().filter(|(_, kinds)| kinds.contains(kind)).map(|(&cell, _)| cell).collect();
+        let mut directions: BTreeSet<_> = cells.iter().map(|&(rank, file)| (rank / gcd(rank, file), file / gcd(rank, file))).collect();
+        directions.extend(slides.iter().filter(|(_, kinds)| kinds.contains(kind)).map(|(&direction, _)| direction));
+        for (rank, file) in directions {
+            let at = |step: isize| (rank * step, file * step);
+            let edge = (1..).find(|&step| !on_board(at(step))).unwrap();
+            let steps: Vec<_> = (1..edge).filter(|&step| cells.contains(&at(step))).collect();
+            let run = steps.iter().enumerate().take_while(|&(index, &step)| step == index as isize + 1).count() as isize;
+            let mut push = |offset: (isize, isize), max_times| moves.entry((vec![offset.0, offset.1], max_times)).or_default().push(kind.clone());
+            if slides.get(&(rank, file)).is_some_and(|kinds| kinds.contains(kind)) {
+                push((rank, file), Some(usize::MAX));
+                continue;
+            }
+            let hidden = run == 0 && steps.first().is_some_and(|&first| {
+                steps.len() as isize == edge - first && (1..first).all(|step| marked.contains_key(&at(step)))
+            });
+            if hidden {
+                warnings.push(format!("{kind} toward ({rank}, {file}) is read as a slide hidden under other marks"));
+                push((rank, file), Some(usize::MAX));
+                continue;
+            }
+            match run {
+                0 => {}
+                1 => {
+                    if edge == 2 {
+                        warnings.push(format!("{kind} at ({rank}, {file}) touches the edge and could also be a slide"));
+                    }
+                    push((rank, file), None);
+                }
+                _ if run + 1 == edge => push((rank, file), Some(usize::MAX)),
+                _ => {
+                    warnings.push(format!("{kind} toward ({rank}, {file}) is read as a slide of {run}, not {run} leaps"));
+                    push((rank, file), Some(run as usize));
+                }
+            }
+            for &step in steps.iter().skip(run as usize) {
+                push(at(step), None);
+            }
+        }
+    }
+
+    let mut format = FileFormat::new(name, short_name, point);
+    if found.iter().any(|kind| kind == RIDE) {
+        warnings.push("ride is not played by the engine and is kept only as a movement type".to_string());
+    }
+    let mut actions: Vec<ActionFormat> = Vec::new();
+    for kind in found.iter().filter(|kind| is_action(kind)) {
+        let notation = kind.chars().find(|letter| !actions.iter().any(|action| action.get_notation().starts_with(*letter))).unwrap_or('a');
+        let mut action = match kind.as_str() {
+            GRANT => ActionFormat::new(kind.as_str(), notation.to_string(), GRANT),
+            CATCH | SHOOT | AREA => ActionFormat::new(kind.as_str(), notation.to_string(), kind.as_str()).capturing(),
+            effect => ActionFormat::new(effect, notation.to_string(), AREA).with_effect(effect, Some(1)),
+        };
+        for ((offset, max_times), _) in moves.iter().filter(|(_, kinds)| kinds.contains(kind)) {
+            action = match max_times {
+                None => action.with_leap(offset.clone()),
+                Some(usize::MAX) => action.with_ride(offset.clone()),
+                Some(times) => (1..=*times as isize).fold(action, |action, step| action.with_leap(offset.iter().map(|value| value * step).collect())),
+            };
+        }
+        actions.push(action);
+    }
+    for ((offset, max_times), kinds) in moves {
+        let movement_type: Vec<_> = kinds.into_iter().filter(|kind| !is_action(kind)).collect();
+        if !movement_type.is_empty() {
+            format = format.with_move(MoveFormat::new(movement_type, offset, max_times));
+        }
+    }
+    for action in actions {
+        format = format.with_action(action);
+    }
+    Ok(ParsedDiagram { format, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen::parse_square, presets};
+
+    #[test]
+    fn diagrams_show_moves_actions_and_slices() {
+        let lightning = Diagram::new(parse_square("d4").unwrap()).with_piece("⚡️");
+        let expected = "\
+⬜️⬛️⬜️⬛️⬜️⬛️⬜️⭕️
+⭕️⬜️⬛️⬜️⬛️⬜️⭕️⬜️
+⬜️⭕️⬜️⬛️⬜️⭕️⬜️⬛️
+⬛️⬜️💫💫💫⬜️⬛️⬜️
+⬜️⬛️💫⚡️💫⬛️⬜️⬛️
+⬛️⬜️💫💫💫⬜️⬛️⬜️
+⬜️⭕️⬜️⬛️⬜️⭕️⬜️⬛️
+⭕️⬜️⬛️⬜️⬛️⬜️⭕️⬜️
+💫=stun
+⭕️=move
+";
+        assert_eq!(lightning.render_empty(presets::standard_board(), &presets::lightning()), expected);
+
+        let traffic_light = Diagram::new(parse_square("a1").unwrap()).with_style(DiagramStyle::Ascii);
+        let text = traffic_light.render_empty(presets::standard_board(), &presets::traffic_light());
+        assert_eq!(text.lines().rev().nth(2), Some("Xxxxxxxx"));
+        assert!(text.ends_with("o=move\nx=move&take\n"));
+        let rook = Diagram::new(parse_square("a1").unwrap()).with_style(DiagramStyle::Ascii);
+        assert!(rook.render_empty(presets::standard_board(), &presets::rook()).ends_with("\no=move&take\n"));
+
+        let game = GameState::from_fen("8/8/8/8/8/8/8/R1p5 w - - 0 1", presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        let first_rank = rook.render(&game, &presets::rook());
+        assert_eq!(first_rank.lines().nth(7), Some("Roo.:.:."));
+        let hawk = Diagram::new(parse_square("e4").unwrap()).with_style(DiagramStyle::Ansi).render_empty(presets::standard_board(), &presets::hawk());
+        assert!(hawk.contains("\x1b[47;35m^ \x1b[0m") && hawk.ends_with("=catch\n"));
+
+        let cube = Board::new(AbsolutePosition::from([0, 0, 0]), AbsolutePosition::from([2, 2, 2]), Default::default());
+        let king = PieceDefinition::new("king", "K", 0).with_betza("K").unwrap();
+        let slice = Diagram::new(AbsolutePosition::from([1, 1, 1])).with_axes(2, 0).with_style(DiagramStyle::Ascii);
+        assert_eq!(slice.render_empty(cube, &king), "ooo\noKo\nooo\no=move&take\n");
+    }
+
+    #[test]
+    fn diagrams_parse_back_into_pieces() {
+        let fan = "\
+⬜️⬛️⬜️⬆️⬜️⬛️⬜️⬛️
+⬛️⬜️⬛️⬆️⬛️⬜️⬛️⬜️
+⬜️⬛️⬜️⬆️⬜️⬛️⬜️⬛️
+⬛️⬜️❌⭕❌⬜⬛⬜️
+⬅️⬅️⭕️⚛️⭕️➡️➡️➡️
+⬛️⬜❌⭕❌⬜⬛⬜️
+⬜⬛⬜⬇️⬜⬛⬜⬛️
+⬛⬜⬛⬇️⬛⬜⬛⬜️
+⭕️=move&push
+❌️=take
+⬆️⬇️⬅️➡️=push로 밀어낼 수 있는 방향
+⚛️=선풍기 이모티콘이 없음";
+        let parsed = parse_diagram(fan, "fan", "F", 3).unwrap();
+        assert!(parsed.get_warnings().iter().any(|warning| warning.contains("\"push\"")));
+        let json = parsed.get_format().to_json_string().unwrap();
+        let fan = PieceDefinition::<2>::from_file_format(&json.parse::<FileFormat>().unwrap()).unwrap();
+        assert_eq!((fan.get_dependent_moves().len(), fan.get_independent_moves().len()), (4, 4));
+        assert!(fan.get_dependent_moves().iter().all(|ride| ride.get_step().has_type(MOVE) && !ride.get_step().has_type(CAPTURE)));
+        assert!(fan.get_independent_moves().iter().all(|leap| leap.has_type(CAPTURE) && !leap.has_type(MOVE)));
+
+        let diagram = Diagram::new(parse_square("d4").unwrap()).with_piece("⚡️");
+        for original in [presets::lightning(), presets::queen(), presets::knight(), presets::pawn()] {
+            let text = diagram.render_empty(presets::standard_board(), &original);
+            let parsed = parse_diagram(&text, original.get_name(), original.get_short_name(), 0).unwrap();
+            let definition = PieceDefinition::from_file_format(parsed.get_format()).unwrap();
+            assert_eq!(diagram.render_empty(presets::standard_board(), &definition), text, "{}", original.get_name());
+        }
+        let lightning = parse_diagram(&diagram.render_empty(presets::standard_board(), &presets::lightning()), "lightning", "L", 3).unwrap();
+        assert!(lightning.get_warnings().iter().any(|warning| warning.contains("hidden")));
+
+        let corner = parse_diagram("o.\nKo", "corner", "C", 1).unwrap();
+        assert_eq!(corner.get_warnings().len(), 2);
+        let limited = parse_diagram("...\n.o.\n.o.\n.K.\n...", "limited", "M", 1).unwrap();
+        assert!(limited.get_warnings()[0].contains("slide of 2"));
+        assert!(parse_diagram("K.\n.Q", "two", "T", 1).is_err());
+        assert!(parse_diagram("K..\n.o", "ragged", "R", 1).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeSet;
+use crate::{
+    game::GameState,
+    moves::Move,
+    movement::PROMOTED_STATE,
+    ownership::Owner,
+    piece::{Piece, PlayerId},
+    position::AbsolutePosition,
+    rules::{PieceDefinition, ROYAL},
+};
+
+/// 코인 자원의 이름.
+pub const COINS: &str = "coins";
+/// 이 기물로 잡으면 잡은 기물에 따라 코인을 받음. (김씨)
+pub const EARNS: &str = "earns";
+
+/// ## DropRestriction
+/// 기물을 놓을 수 없는 칸의 규칙. 랭크는 0번 축, 파일은 1번 축입니다.
+/// - `NotOnPromotionRank`: 자기 프로모션 구역에는 못 놓음
+/// - `NotOnRanks`: `name` 기물은 `ranks`에 못 놓음 (크레이지하우스의 폰)
+/// - `OnePerFile`: 자기 `name` 기물이 이미 있는 파일에는 못 놓음 (쇼기의 니후)
+/// - `NotInRegion`: `name` 기물은 (없으면 모든 기물은) 자기에게 `region`인 구역에 못 놓음
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DropRestriction {
+    NotOnPromotionRank,
+    NotOnRanks { name: String, ranks: Vec<usize> },
+    OnePerFile(String),
+    NotInRegion { name: Option<String>, region: String },
+}
+
+/// ## Economy
+/// 기물을 손에 들고 놓는 규칙.
+/// - `captures_to_hand`: 잡은 기물이 잡은 플레이어의 손에 들어감 (크레이지하우스, 쇼기)
+/// - `purchases`: 코인으로 기물을 사서 바로 빈 칸에 놓을 수 있음. 값은 기물 점수입니다.
+/// - `restrictions`: 놓을 수 없는 칸
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Economy {
+    captures_to_hand: bool,
+    purchases: bool,
+    restrictions: Vec<DropRestriction>,
+}
+
+impl Economy {
+    pub fn with_captures_to_hand(mut self) -> Self {
+        self.captures_to_hand = true;
+        self
+    }
+
+    pub fn with_purchases(mut self) -> Self {
+        self.purchases = true;
+        self
+    }
+
+    pub fn with_restriction(mut self, restriction: DropRestriction) -> Self {
+        self.restrictions.push(restriction);
+        self
+    }
+
+    #[inline(always)]
+    pub fn captures_to_hand(&self) -> bool {
+        self.captures_to_hand
+    }
+
+    #[inline(always)]
+    pub fn purchases(&self) -> bool {
+        self.purchases
+    }
+
+    #[inline(always)]
+    pub fn get_restrictions(&self) -> &[DropRestriction] {
+        &self.restrictions
+    }
+}
+
+/// 기물을 사는 값. 기물 파일의 `point`입니다.
+#[inline(always)]
+pub fn price<const D: usize>(definition: &PieceDefinition<D>) -> i64 {
+    definition.get_point() as i64
+}
+
+/// `EARNS` 기물로 잡았을 때 받는 코인. 폰(1점)은 1, 나머지는 점수 - 1입니다.
+#[inline(always)]
+pub fn reward(piece: &Piece) -> i64 {
+    (piece.piece_score() as i64 - 1).max(1)
+}
+
+impl<const D: usize> GameState<D> {
+    /// `player`가 `definition` 기물을 `at`에 놓을 수 있는지. 빈 칸이고 규칙의 제한에 걸리지 않아야 합니다.
+    pub fn can_drop(&self, player: PlayerId, definition: &PieceDefinition<D>, at: &AbsolutePosition<D>) -> bool {
+        if self.get_board().get(at).is_some() {
+            return false;
+        }
+        self.get_rules().get_economy().get_restrictions().iter().all(|restriction| match restriction {
+            DropRestriction::NotOnPromotionRank => !self.promotes_at(definition, player, at),
+            DropRestriction::NotOnRanks { name, ranks } => definition.get_name() != name || !ranks.contains(&at[0]),
+            DropRestriction::OnePerFile(name) => definition.get_name() != name || !self.get_board().pieces().any(|(position, piece)| {
+                piece.get_owner() == Owner::Player(player) && piece.get_name() == name && position[1] == at[1]
+            }),
+            DropRestriction::NotInRegion { name, region } => name.as_ref().is_some_and(|name| definition.get_name() != name) || !self.in_region(region, player, at),
+        })
+    }
+
+    /// 손에 든 기물을 놓는 수와, 코인으로 기물을 사서 놓는 수. 손에 든 기물은 사지 않습니다.
+    pub(crate) fn drop_moves(&self, player: PlayerId, moves: &mut Vec<Move<D>>) {
+        let rules = self.get_rules();
+        let economy = rules.get_economy();
+        let state = self.get_player(player);
+        let coins = state.get_resource(COINS);
+        let mut names: BTreeSet<&String> = state.hand().filter(|(_, count)| **count > 0).map(|(name, _)| name).collect();
+        if economy.purchases() && coins > 0 {
+            names.extend(rules.definitions(player)
+                .filter(|definition| !definition.has_attribute(ROYAL) && (1..=coins).contains(&price(definition)))
+                .map(PieceDefinition::get_name));
+        }
+        if names.is_empty() {
+            return;
+        }
+        let empty: BTreeSet<_> = self.get_board().empty_squares().collect();
+        for name in names {
+            let (Some(definition), Some(piece)) = (rules.get(player, name), rules.create_piece(player, name)) else {
+                continue;
+            };
+            let bought = state.get_hand_count(name) == 0;
+            for at in empty.iter().filter(|at| self.can_drop(player, definition, at)) {
+                let mv = Move::drop(piece.clone(), *at);
+                moves.push(if bought { mv.with_price(price(definition)) } else { mv });
+            }
+        }
+    }
+
+    /// 드롭한 기물 값을 치릅니다. 사서 놓으면 코인을, 아니면 손에 든 기물 하나를 씁니다.
+    pub(crate) fn pay_for_drop(&mut self, player: PlayerId, mv: &Move<D>) {
+        let Some(piece) = mv.get_drop() else {
+            return;
+        };
+        if mv.get_price() > 0 {
+            self.add_resource(player, COINS, -mv.get_price());
+        } else {
+            let count = self.get_player(player).get_hand_count(piece.get_name());
+            self.set_hand_count(player, piece.get_name(), count.saturating_sub(1));
+        }
+    }
+
+    /// 잡은 기물을 정산합니다. 규칙에 따라 잡은 기물을 손에 넣고, `captor`가 `EARNS` 기물이면 코인을 받습니다.
+    /// 프로모션한 기물은 프로모션하기 전 기물로 손에 들어갑니다. (`PROMOTED_STATE`)
+    pub(crate) fn settle_captures(&mut self, player: PlayerId, captor: &AbsolutePosition<D>, captured: &[(AbsolutePosition<D>, Piece)]) {
+        let rules = self.get_rules().clone();
+        let earns = self.get_board().get(captor)
+            .filter(|piece| self.controls(player, piece))
+            .and_then(|piece| rules.get_definition(piece))
+            .is_some_and(|definition| definition.has_attribute(EARNS));
+        let victims: Vec<_> = captured.iter().map(|(_, piece)| piece).filter(|piece| self.is_hostile(player, piece)).collect();
+        for piece in victims {
+            if earns {
+                self.add_resource(player, COINS, reward(piece));
+            }
+            let royal = rules.get_definition(piece).is_some_and(|definition| definition.has_attribute(ROYAL));
+            let name = piece.get_state().get(PROMOTED_STATE).unwrap_or(piece.get_name());
+            if rules.get_economy().captures_to_hand() && !royal && rules.get(player, name).is_some() {
+                let count = self.get_player(player).get_hand_count(name);
+                self.set_hand_count(player, name, count + 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{fen::parse_square, presets};
+
+    fn play(game: &mut GameState<2>, text: &str) {
+        let mv = game.legal_moves().into_iter().find(|mv| mv.to_string() == text).unwrap();
+        game.make_move(mv);
+    }
+
+    #[test]
+    fn gimcy_earns_coins_and_buys_pieces() {
+        let mut game = GameState::from_fen("4k3/8/8/8/8/8/2r5/2C1K3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_hash_check(true);
+        let hash = game.hash();
+        assert!(!game.legal_moves().iter().any(Move::is_drop));
+        let capture = game.legal_moves().into_iter().find(|mv| mv.to_string() == "c1c2").unwrap();
+        game.make_move(capture);
+        assert_eq!(game.get_player(0).get_resource(COINS), 4);
+        play(&mut game, "e8d8");
+
+        let moves = game.legal_moves();
+        assert!(moves.iter().any(|mv| mv.to_string() == "N$a8"));
+        assert!(!moves.iter().any(|mv| mv.to_string() == "P$a8"));
+        assert!(!moves.iter().any(|mv| mv.to_string().starts_with("R$")));
+        game.make_move(moves.into_iter().find(|mv| mv.to_string() == "N$h1").unwrap());
+        assert_eq!(game.get_player(0).get_resource(COINS), 1);
+        assert_eq!(game.get_board().get(&parse_square("h1").unwrap()).unwrap().get_name(), "knight");
+        for _ in 0..3 {
+            game.unmake_move();
+        }
+        assert_eq!(game.hash(), hash);
+    }
+
+    #[test]
+    fn crazyhouse_drops_captured_pieces() {
+        let mut game = GameState::from_fen("4k3/8/8/8/8/8/3n4/4K3 w - - 0 1", presets::standard_board(), Arc::new(presets::crazyhouse())).unwrap();
+        game.set_hash_check(true);
+        play(&mut game, "e1d2");
+        assert_eq!(game.get_player(0).get_hand_count("knight"), 1);
+        play(&mut game, "e8e7");
+        let drops: Vec<_> = game.legal_moves().into_iter().filter(Move::is_drop).collect();
+        assert_eq!(drops.len(), 62);
+        game.make_move(drops.into_iter().find(|mv| mv.to_string() == "N@c6").unwrap());
+        assert_eq!(game.get_player(0).get_hand_count("knight"), 0);
+        assert!(game.in_check(1));
+
+        let mut game = GameState::from_fen("8/1P5k/8/8/8/8/r7/4K3 w - - 0 1", presets::standard_board(), Arc::new(presets::crazyhouse())).unwrap();
+        game.set_hash_check(true);
+        play(&mut game, "b7b8q");
+        play(&mut game, "a2a8");
+        play(&mut game, "e1d2");
+        play(&mut game, "a8b8");
+        assert_eq!((game.get_player(1).get_hand_count("pawn"), game.get_player(1).get_hand_count("queen")), (1, 0));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{
+    game::GameState,
+    piece::PlayerId,
+    position::AbsolutePosition,
+};
+
+/// ## EffectKind
+/// 기물이나 칸에 붙는 효과의 종류.
+/// - `Stun`, `Freeze`, `Anchor`: 붙은 기물은 움직일 수 없고 다른 기물을 공격하지도 못합니다.
+/// - `Shield`: 칸에 붙으며, 그 칸에 있는 `source` 플레이어의 기물은 잡히지 않습니다.
+/// - `Blind`: 붙은 기물은 안개 게임에서 아무 칸도 밝히지 못합니다.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EffectKind {
+    Stun,
+    Freeze,
+    Anchor,
+    Shield,
+    Blind,
+    Custom(String),
+}
+
+impl EffectKind {
+    /// 이름으로 효과 종류를 찾습니다. 모르는 이름은 `Custom`이 됩니다.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "stun" => EffectKind::Stun,
+            "freeze" => EffectKind::Freeze,
+            "anchor" => EffectKind::Anchor,
+            "shield" => EffectKind::Shield,
+            "blind" => EffectKind::Blind,
+            _ => EffectKind::Custom(name.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            EffectKind::Stun => "stun",
+            EffectKind::Freeze => "freeze",
+            EffectKind::Anchor => "anchor",
+            EffectKind::Shield => "shield",
+            EffectKind::Blind => "blind",
+            EffectKind::Custom(name) => name,
+        }
+    }
+
+    #[inline(always)]
+    pub fn immobilizes(&self) -> bool {
+        matches!(self, EffectKind::Stun | EffectKind::Freeze | EffectKind::Anchor)
+    }
+}
+
+/// ## EffectDuration
+/// 효과가 걸린 수 다음부터 몇 ply(한 플레이어의 한 수) 또는 몇 턴(모든 플레이어가 한 수씩) 동안 남는지.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectDuration {
+    Plies(usize),
+    Turns(usize),
+    Permanent,
+}
+
+/// ## Stacking
+/// 같은 종류, 같은 플레이어의 효과가 이미 있을 때 어떻게 할지.
+/// - `Refresh`: 더 늦게 끝나는 쪽으로 맞춤
+/// - `Extend`: 남은 시간에 새 시간을 더함
+/// - `Stack`: 따로 하나 더 붙임
+/// - `Keep`: 원래 효과를 그대로 둠
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stacking {
+    Refresh,
+    Extend,
+    Stack,
+    Keep,
+}
+
+/// ## EffectSpec
+/// 걸 효과. 수에 실려서 `make_move` 때 적용되고, 그때 끝나는 ply가 정해집니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EffectSpec {
+    pub kind: EffectKind,
+    pub duration: EffectDuration,
+    pub stacking: Stacking,
+}
+
+impl EffectSpec {
+    pub fn new(kind: EffectKind, duration: EffectDuration) -> Self {
+        Self { kind, duration, stacking: Stacking::Refresh }
+    }
+
+    pub fn with_stacking(mut self, stacking: Stacking) -> Self {
+        self.stacking = stacking;
+        self
+    }
+}
+
+/// ## EffectTarget
+/// 효과를 기물에 걸지(기물이 움직이면 따라감), 칸에 걸지.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EffectTarget<const D: usize> {
+    Piece(AbsolutePosition<D>),
+    Square(AbsolutePosition<D>),
+}
+
+/// ## Effect
+/// 걸려 있는 효과. `expires_at` ply가 되면 사라집니다. `None`이면 사라지지 않습니다.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Effect {
+    kind: EffectKind,
+    source: PlayerId,
+    expires_at: Option<usize>,
+}
+
+impl Effect {
+    pub fn new(kind: EffectKind, source: PlayerId, expires_at: Option<usize>) -> Self {
+        Self { kind, source, expires_at }
+    }
+
+    #[inline(always)]
+    pub fn get_kind(&self) -> &EffectKind {
+        &self.kind
+    }
+
+    #[inline(always)]
+    pub fn get_source(&self) -> PlayerId {
+        self.source
+    }
+
+    #[inline(always)]
+    pub fn expires_at(&self) -> Option<usize> {
+        self.expires_at
+    }
+
+    #[inline(always)]
+    pub fn is_active(&self, ply: usize) -> bool {
+        self.expires_at.is_none_or(|expires_at| ply < expires_at)
+    }
+}
+
+/// `effects`에 `effect`를 `stacking` 규칙대로 더합니다.
+pub fn stack_effect(effects: &mut Vec<Effect>, effect: Effect, stacking: Stacking, ply: usize) {
+    let same = effects.iter_mut().find(|old| old.kind == effect.kind && old.source == effect.source);
+    match (same, stacking) {
+        (None, _) | (Some(_), Stacking::Stack) => effects.push(effect),
+        (Some(_), Stacking::Keep) => {}
+        (Some(old), Stacking::Refresh) => {
+            old.expires_at = old.expires_at.zip(effect.expires_at).map(|(old, new)| old.max(new));
+        }
+        (Some(old), Stacking::Extend) => {
+            old.expires_at = old.expires_at.zip(effect.expires_at).map(|(old, new)| old + new.saturating_sub(ply + 1));
+        }
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// 효과가 끝나는 ply. 지금 두는 수(`ply`) 다음 ply부터 센 길이입니다.
+    pub fn effect_expiry(&self, duration: EffectDuration) -> Option<usize> {
+        match duration {
+            EffectDuration::Plies(plies) => Some(self.ply() + 1 + plies),
+            EffectDuration::Turns(turns) => Some(self.ply() + 1 + turns * self.player_count()),
+            EffectDuration::Permanent => None,
+        }
+    }
+
+    /// `source` 플레이어가 거는 효과. 기물 대상인데 기물이 없으면 아무 일도 없습니다.
+    pub fn apply_effect(&mut self, target: EffectTarget<D>, spec: &EffectSpec, source: PlayerId) {
+        let effect = Effect::new(spec.kind.clone(), source, self.effect_expiry(spec.duration));
+        let ply = self.ply();
+        match target {
+            EffectTarget::Piece(position) => {
+                self.update_piece(&position, |piece| stack_effect(piece.get_effects_mut(), effect, spec.stacking, ply));
+            }
+            EffectTarget::Square(position) => {
+                let mut effects = self.get_square_effects(&position).to_vec();
+                stack_effect(&mut effects, effect, spec.stacking, ply);
+                self.set_square_effects(position, effects);
+            }
+        }
+    }
+
+    /// 기물이나 그 기물이 있는 칸에 움직이지 못하게 하는 효과가 있는지.
+    pub fn is_immobilized(&self, position: &AbsolutePosition<D>) -> bool {
+        let ply = self.ply();
+        let on_piece = self.get_board().get(position).is_some_and(|piece| piece.get_effects().iter().any(|effect| effect.is_active(ply) && effect.kind.immobilizes()));
+        on_piece || self.get_square_effects(position).iter().any(|effect| effect.is_active(ply) && effect.kind.immobilizes())
+    }
+
+    /// 칸에 `player`의 방패가 있어서 그 플레이어의 기물이 잡히지 않는지.
+    pub fn is_shielded(&self, position: &AbsolutePosition<D>, player: PlayerId) -> bool {
+        let ply = self.ply();
+        self.get_square_effects(position).iter().any(|effect| effect.is_active(ply) && effect.kind == EffectKind::Shield && effect.source == player)
+    }
+
+    /// 끝난 효과들을 지웁니다. `make_move`가 차례를 넘길 때마다 부릅니다.
+    pub fn expire_effects(&mut self) {
+        let ply = self.ply();
+        let expired_pieces: Vec<_> = self.get_board().pieces()
+            .filter(|(_, piece)| piece.get_effects().iter().any(|effect| !effect.is_active(ply)))
+            .map(|(position, _)| *position)
+            .collect();
+        for position in expired_pieces {
+            self.update_piece(&position, |piece| piece.get_effects_mut().retain(|effect| effect.is_active(ply)));
+        }
+        let expired_squares: Vec<_> = self.square_effects()
+            .filter(|(_, effects)| effects.iter().any(|effect| !effect.is_active(ply)))
+            .map(|(position, effects)| (*position, effects.iter().filter(|effect| effect.is_active(ply)).cloned().collect()))
+            .collect();
+        for (position, effects) in expired_squares {
+            self.set_square_effects(position, effects);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{fen::parse_square, moves::Move, movement::MOVE, presets, zobrist::Zobrist};
+
+    #[test]
+    fn effects_block_moves_expire_and_unmake() {
+        let mut game = GameState::from_fen("4k3/7p/8/8/8/8/8/R3K3 w - - 0 1", presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        game.set_hash_check(true);
+        let king = parse_square("e8").unwrap();
+        let hash = game.hash();
+        let stun = EffectSpec::new(EffectKind::Stun, EffectDuration::Turns(1));
+        game.make_move(Move::new(parse_square("e1").unwrap(), parse_square("e2").unwrap(), MOVE).with_effect(EffectTarget::Piece(king), stun));
+        assert!(game.is_immobilized(&king));
+        assert!(game.legal_moves().iter().all(|mv| *mv.get_from() != king));
+        game.make_move(Move::new(parse_square("h7").unwrap(), parse_square("h6").unwrap(), MOVE));
+        game.make_move(Move::new(parse_square("a1").unwrap(), parse_square("a2").unwrap(), MOVE));
+        assert!(!game.is_immobilized(&king));
+        assert!(game.get_board().get(&king).unwrap().get_effects().is_empty());
+        for _ in 0..3 {
+            game.unmake_move();
+        }
+        assert_eq!(game.hash(), hash);
+
+        let stack = EffectSpec::new(EffectKind::Stun, EffectDuration::Plies(3)).with_stacking(Stacking::Stack);
+        game.apply_effect(EffectTarget::Square(king), &stack, 0);
+        let once = game.hash();
+        game.apply_effect(EffectTarget::Square(king), &stack, 0);
+        assert!(game.hash() != once && game.hash() != hash);
+        assert_eq!(game.hash(), game.compute_hash());
+
+        let zobrist = Zobrist::default();
+        let stun = |expires_at| [Effect::new(EffectKind::Stun, 0, Some(expires_at))];
+        assert_eq!(zobrist.square_effect_clock(&king, &stun(5), 2), zobrist.square_effect_clock(&king, &stun(7), 4));
+        assert_ne!(zobrist.square_effect_clock(&king, &stun(5), 2), zobrist.square_effect_clock(&king, &stun(5), 3));
+    }
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+use anyhow::{anyhow, bail, Result};
+use crate::{
+    game::GameState,
+    movement::MOVED_STATE,
+    ownership::Owner,
+    piece::{Piece, PlayerId},
+    position::{AbsolutePosition, Board},
+    rules::{Ruleset, CASTLE},
+};
+
+/// FEN에서 공유 기물 글자 앞에 붙이는 표시.
+pub const SHARED_MARK: char = '*';
+/// FEN에서 중립 기물 글자 앞에 붙이는 표시.
+pub const NEUTRAL_MARK: char = '?';
+
+impl GameState<2> {
+    /// FEN을 읽습니다. 기물 글자는 `short_name`으로 찾고, 대문자는 플레이어 0, 소문자는 플레이어 1입니다.
+    /// 기물 글자 앞의 `*`는 공유 기물, `?`는 중립 기물이고 이때 글자는 대문자로 씁니다. (`*Z`, `?P`)
+    /// 빈 칸 수는 여러 자리로 적어도 됩니다. (10줄 보드의 `10`)
+    /// 캐슬링은 `KQkq`와 파일 글자(`HAha`, Shredder-FEN) 둘 다 받습니다.
+    /// 처음 위치(폰은 2랭크, 킹은 캐슬링 권리가 있을 때)에 있지 않은 기물은 움직인 기물로 봅니다.
+    pub fn from_fen(fen: &str, board: Board<2>, rules: Arc<Ruleset<2>>) -> Result<Self> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let [placement, side, castling, en_passant, ..] = fields[..] else {
+            bail!("fen needs at least 4 fields: {fen}");
+        };
+        let ranks = board.get_max_position()[0] + 1;
+        let files = board.get_max_position()[1] + 1;
+
+        let mut game = GameState::new(board, Arc::clone(&rules));
+        let rows: Vec<&str> = placement.split('/').collect();
+        if rows.len() != ranks {
+            bail!("fen has {} ranks, board has {}", rows.len(), ranks);
+        }
+        for (row, text) in rows.iter().enumerate() {
+            let rank = ranks - 1 - row;
+            let mut file = 0;
+            let mut owner = None;
+            let mut empty = 0;
+            for letter in text.chars() {
+                if owner.is_none() && (letter == SHARED_MARK || letter == NEUTRAL_MARK) {
+                    owner = Some(if letter == SHARED_MARK { Owner::Shared } else { Owner::Neutral });
+                    continue;
+                }
+                if let Some(digit) = letter.to_digit(10).filter(|_| owner.is_none()) {
+                    empty = empty * 10 + digit as usize;
+                    continue;
+                }
+                file += std::mem::take(&mut empty);
+                if file >= files {
+                    bail!("rank {} is wider than the board", rank + 1);
+                }
+                let player = if letter.is_ascii_uppercase() { 0 } else { 1 };
+                let mut piece = piece_for_letter(&rules, player, letter)?;
+                if let Some(owner) = owner.take() {
+                    piece.set_owner(owner);
+                }
+                game.put_piece(AbsolutePosition::from([rank, file]), piece);
+                file += 1;
+            }
+            if owner.is_some() {
+                bail!("ownership mark without a piece on rank {}", rank + 1);
+            }
+            if file + empty > files {
+                bail!("rank {} is wider than the board", rank + 1);
+            }
+        }
+
+        match side {
+            "w" => game.set_side_to_move(0),
+            "b" => game.set_side_to_move(1),
+            _ => bail!("unknown side to move: {side}")
+        }
+
+        if castling != "-" {
+            for letter in castling.chars() {
+                let player = if letter.is_ascii_uppercase() { 0 } else { 1 };
+                let rook = castling_rook(&game, player, letter)?;
+                game.add_castling(rook);
+            }
+        }
+
+        if en_passant != "-" {
+            let target = parse_square(en_passant)?;
+            let victim_rank = if game.side_to_move() == 0 { target[0].checked_sub(1) } else { Some(target[0] + 1) };
+            let victim = victim_rank.map(|rank| AbsolutePosition::from([rank, target[1]]));
+            match victim {
+                Some(victim) if game.get_board().contains(&target) && game.get_board().contains(&victim) => {
+                    game.set_en_passant(Some((target, victim)));
+                }
+                _ => bail!("bad en passant square: {en_passant}")
+            }
+        }
+
+        mark_moved_pieces(&mut game, &rules);
+        Ok(game)
+    }
+}
+
+fn piece_for_letter(rules: &Ruleset<2>, player: PlayerId, letter: char) -> Result<Piece> {
+    let short_name = letter.to_ascii_uppercase().to_string();
+    rules.definitions(player)
+        .find(|definition| *definition.get_short_name() == short_name)
+        .and_then(|definition| rules.create_piece(player, definition.get_name()))
+        .ok_or_else(|| anyhow!("no piece with short name {short_name} for player {player}"))
+}
+
+/// `e4` 같은 칸 이름을 읽습니다. 보드를 모르므로 보드 안인지는 부르는 쪽이 `Board::contains`로 봅니다.
+pub fn parse_square(square: &str) -> Result<AbsolutePosition<2>> {
+    let mut chars = square.chars();
+    let file = chars.next().filter(char::is_ascii_lowercase).ok_or_else(|| anyhow!("bad square: {square}"))?;
+    let rank: usize = chars.as_str().parse().map_err(|_| anyhow!("bad square: {square}"))?;
+    if rank == 0 {
+        bail!("bad square: {square}");
+    }
+    Ok(AbsolutePosition::from([rank - 1, (file as u8 - b'a') as usize]))
+}
+
+/// 캐슬링 글자에 해당하는 파트너(룩)의 칸. `K`/`Q`는 킹 바깥쪽에서 가장 먼 파트너입니다.
+fn castling_rook(game: &GameState<2>, player: PlayerId, letter: char) -> Result<AbsolutePosition<2>> {
+    let board = game.get_board();
+    let rules = game.get_rules();
+    let king = board.pieces()
+        .find(|(_, piece)| piece.get_owner() == Owner::Player(player) && rules.get_definition(piece).is_some_and(|definition| definition.has_attribute(CASTLE)))
+        .map(|(position, _)| *position)
+        .ok_or_else(|| anyhow!("castling right {letter} without a king"))?;
+    let rank = king[0];
+    let is_rook = |file: usize| board.get(&AbsolutePosition::from([rank, file]))
+        .is_some_and(|piece| piece.get_owner() == Owner::Player(player) && rules.get_castling().is_partner(piece.get_name()));
+    let files = board.get_max_position()[1] + 1;
+    let file = match letter.to_ascii_uppercase() {
+        'K' => (king[1] + 1..files).rev().find(|&file| is_rook(file)),
+        'Q' => (0..king[1]).find(|&file| is_rook(file)),
+        file @ 'A'..='Z' => Some((file as u8 - b'A') as usize).filter(|&file| is_rook(file)),
+        _ => None
+    };
+    file.map(|file| AbsolutePosition::from([rank, file]))
+        .ok_or_else(|| anyhow!("no rook for castling right {letter}"))
+}
+
+fn mark_moved_pieces(game: &mut GameState<2>, rules: &Ruleset<2>) {
+    let home_ranks = [1, game.get_board().get_max_position()[0] - 1];
+    let castling_players: Vec<Owner> = game.get_castling().iter()
+        .filter_map(|rook| game.get_board().get(rook).map(Piece::get_owner))
+        .collect();
+    let moved: Vec<_> = game.get_board().pieces()
+        .filter(|(position, piece)| {
+            if !rules.tracks_moved_state(piece) {
+                return false;
+            }
+            let Some(definition) = rules.disguise_definition(piece).or(rules.get_definition(piece)) else {
+                return false;
+            };
+            if definition.has_attribute(CASTLE) {
+                !castling_players.contains(&piece.get_owner())
+            } else {
+                piece.get_owner().player().and_then(|player| home_ranks.get(player)) != Some(&position[0])
+            }
+        })
+        .map(|(position, _)| *position)
+        .collect();
+    for position in moved {
+        game.update_piece(&position, |piece| piece.get_state_mut().set(MOVED_STATE, "true"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{presets, zobrist::Zobrist};
+
+    #[test]
+    fn empty_runs_can_take_several_digits() {
+        let board = || Board::new(AbsolutePosition::from([0, 0]), AbsolutePosition::from([9, 9]), Zobrist::default());
+        let rules = Arc::new(presets::standard_chess());
+        let game = GameState::from_fen("4k5/10/10/10/10/10/10/10/10/R8K w - - 0 1", board(), Arc::clone(&rules)).unwrap();
+        assert_eq!(game.get_board().get(&parse_square("j1").unwrap()).unwrap().get_name(), "king");
+        assert_eq!(game.get_board().get(&parse_square("e10").unwrap()).unwrap().get_name(), "king");
+        assert!(GameState::from_fen("4k5/11/10/10/10/10/10/10/10/R8K w - - 0 1", board(), rules).unwrap_err().to_string().contains("wider"));
+    }
+
+    #[test]
+    fn en_passant_square_must_be_on_the_board() {
+        let game = |fen: &str| GameState::from_fen(fen, presets::standard_board(), Arc::new(presets::standard_chess()));
+        let passable = game("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let (target, victim) = passable.get_en_passant().unwrap();
+        assert_eq!((target, victim), (&parse_square("d6").unwrap(), &parse_square("d5").unwrap()));
+        for fen in ["4k3/8/8/8/8/8/8/4K3 w - e1 0 1", "4k3/8/8/8/8/8/8/4K3 b - e8 0 1", "4k3/8/8/8/8/8/8/4K3 w - i6 0 1"] {
+            assert!(game(fen).unwrap_err().to_string().contains("bad en passant square"), "{fen}");
+        }
+    }
+}
@@ -1,18 +1,136 @@
 use serde::{Serialize, Deserialize};
 use serde_json::Result;
+use std::str::FromStr;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Default)]
 pub struct FileFormat {
     name: String,
     short_name: String,
     point: i32,
     code: String,
+    #[serde(default)]
+    attributes: Vec<String>,
+    #[serde(default)]
+    moves: Vec<MoveFormat>,
+    #[serde(default)]
+    betza: Option<String>,
+    #[serde(default)]
+    promotions: Vec<String>,
+    #[serde(default)]
+    promotion_rank: Option<usize>,
+    #[serde(default)]
+    promotion_zone: Vec<usize>,
+    #[serde(default)]
+    promotion_region: Option<String>,
+    #[serde(default)]
+    actions: Vec<ActionFormat>,
+    #[serde(default)]
+    continuation: Option<ContinuationFormat>,
+    #[serde(default)]
+    disguise: Option<String>,
+    #[serde(default)]
+    gambles: Vec<String>,
+    #[serde(default)]
+    on_captured: Vec<HookFormat>,
+    #[serde(default)]
+    on_capture: Vec<HookFormat>,
+    #[serde(default)]
+    transforms: Vec<TransformFormat>,
+}
+
+/// ## MoveFormat
+/// 기물 파일에 적는 이동 하나.
+/// `max_times`가 없으면 한 번에 가는 이동, 있으면 그 횟수까지 이어서 가는 이동입니다.
+/// `guard`는 이 이동을 쓸 수 있는 조건식입니다. (`Guard`)
+/// `symmetries`가 있으면 `offset`을 그 대칭들로 펼친 모든 방향의 이동이 됩니다. (`Symmetry`)
+#[derive(Serialize, Deserialize)]
+pub struct MoveFormat {
+    movement_type: Vec<String>,
+    offset: Vec<isize>,
+    #[serde(default)]
+    max_times: Option<usize>,
+    #[serde(default)]
+    guard: Option<String>,
+    #[serde(default)]
+    symmetries: Vec<String>,
+}
+
+/// ## ActionFormat
+/// 기물 파일에 적는 액션 하나. `effect`는 효과 이름이고, `effect_turns`가 없으면 효과가 사라지지 않습니다.
+/// `allow_empty`를 켜면 `area` 액션이 닿는 기물이 없어도 쓸 수 있습니다.
+#[derive(Serialize, Deserialize)]
+pub struct ActionFormat {
+    name: String,
+    notation: String,
+    action_type: String,
+    #[serde(default)]
+    leaps: Vec<Vec<isize>>,
+    #[serde(default)]
+    rides: Vec<Vec<isize>>,
+    #[serde(default)]
+    captures: bool,
+    #[serde(default)]
+    effect: Option<String>,
+    #[serde(default)]
+    effect_turns: Option<usize>,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    allow_empty: bool,
+}
+
+/// ## HookFormat
+/// 기물 파일에 적는 잡기 훅 하나. `kind`는 `return_victim`, `remove_captor`, `captor_effect`입니다.
+/// `plies`는 `return_victim`이 몇 수 전으로 돌아갈지, `effect`와 `effect_turns`는 `captor_effect`가 걸 효과입니다.
+#[derive(Serialize, Deserialize)]
+pub struct HookFormat {
+    kind: String,
+    #[serde(default)]
+    plies: Option<usize>,
+    #[serde(default)]
+    effect: Option<String>,
+    #[serde(default)]
+    effect_turns: Option<usize>,
+}
+
+/// ## TransformFormat
+/// 기물 파일에 적는 변신 규칙 하나. (`TransformRule::from_format`)
+#[derive(Serialize, Deserialize)]
+pub struct TransformFormat {
+    trigger: String,
+    result: String,
+    #[serde(default)]
+    ranks: Vec<usize>,
+    #[serde(default)]
+    region: Option<String>,
+    #[serde(default)]
+    plies: Option<usize>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// ## ContinuationFormat
+/// 한 차례에 이 기물로 이어서 두는 규칙. `condition`은 `always` 또는 `after_capture`입니다.
+#[derive(Serialize, Deserialize)]
+pub struct ContinuationFormat {
+    condition: String,
+    max_legs: usize,
 }
 
 impl FileFormat {
-    #[inline(always)]
-    pub fn from_str(json: &str) -> Result<FileFormat> {
-        serde_json::from_str(json)
+    /// 이동도 액션도 없는 기물 파일. 기물 파일을 코드에서 만들 때 씁니다. (`parse_diagram`)
+    pub fn new(name: impl Into<String>, short_name: impl Into<String>, point: i32) -> Self {
+        Self { name: name.into(), short_name: short_name.into(), point, ..Self::default() }
+    }
+
+    pub fn with_move(mut self, format: MoveFormat) -> Self {
+        self.moves.push(format);
+        self
+    }
+
+    pub fn with_action(mut self, format: ActionFormat) -> Self {
+        self.actions.push(format);
+        self
     }
 
     #[inline(always)]
@@ -39,4 +157,269 @@ impl FileFormat {
     pub fn get_code(&self) -> &String {
         &self.code
     }
-}
\ No newline at end of file
+
+    #[inline(always)]
+    pub fn get_attributes(&self) -> &Vec<String> {
+        &self.attributes
+    }
+
+    #[inline(always)]
+    pub fn get_moves(&self) -> &Vec<MoveFormat> {
+        &self.moves
+    }
+
+    /// `moves`에 더해지는 베짜 표기의 이동.
+    #[inline(always)]
+    pub fn get_betza(&self) -> Option<&String> {
+        self.betza.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_promotions(&self) -> &Vec<String> {
+        &self.promotions
+    }
+
+    #[inline(always)]
+    pub fn get_promotion_rank(&self) -> Option<usize> {
+        self.promotion_rank
+    }
+
+    #[inline(always)]
+    pub fn get_promotion_zone(&self) -> &Vec<usize> {
+        &self.promotion_zone
+    }
+
+    #[inline(always)]
+    pub fn get_promotion_region(&self) -> Option<&String> {
+        self.promotion_region.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_actions(&self) -> &Vec<ActionFormat> {
+        &self.actions
+    }
+
+    #[inline(always)]
+    pub fn get_continuation(&self) -> Option<&ContinuationFormat> {
+        self.continuation.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_disguise(&self) -> Option<&String> {
+        self.disguise.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_gambles(&self) -> &Vec<String> {
+        &self.gambles
+    }
+
+    #[inline(always)]
+    pub fn get_on_captured(&self) -> &Vec<HookFormat> {
+        &self.on_captured
+    }
+
+    #[inline(always)]
+    pub fn get_on_capture(&self) -> &Vec<HookFormat> {
+        &self.on_capture
+    }
+
+    #[inline(always)]
+    pub fn get_transforms(&self) -> &Vec<TransformFormat> {
+        &self.transforms
+    }
+}
+
+/// 기물 파일의 JSON을 읽습니다. (`json.parse::<FileFormat>()`)
+impl FromStr for FileFormat {
+    type Err = serde_json::Error;
+
+    fn from_str(json: &str) -> Result<FileFormat> {
+        serde_json::from_str(json)
+    }
+}
+
+impl TransformFormat {
+    #[inline(always)]
+    pub fn get_trigger(&self) -> &String {
+        &self.trigger
+    }
+
+    #[inline(always)]
+    pub fn get_result(&self) -> &String {
+        &self.result
+    }
+
+    #[inline(always)]
+    pub fn get_ranks(&self) -> &Vec<usize> {
+        &self.ranks
+    }
+
+    #[inline(always)]
+    pub fn get_region(&self) -> Option<&String> {
+        self.region.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_plies(&self) -> Option<usize> {
+        self.plies
+    }
+
+    #[inline(always)]
+    pub fn get_name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+}
+
+impl HookFormat {
+    #[inline(always)]
+    pub fn get_kind(&self) -> &String {
+        &self.kind
+    }
+
+    #[inline(always)]
+    pub fn get_plies(&self) -> Option<usize> {
+        self.plies
+    }
+
+    #[inline(always)]
+    pub fn get_effect(&self) -> Option<&String> {
+        self.effect.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_effect_turns(&self) -> Option<usize> {
+        self.effect_turns
+    }
+}
+
+impl ContinuationFormat {
+    #[inline(always)]
+    pub fn get_condition(&self) -> &String {
+        &self.condition
+    }
+
+    #[inline(always)]
+    pub fn get_max_legs(&self) -> usize {
+        self.max_legs
+    }
+}
+
+impl ActionFormat {
+    pub fn new(name: impl Into<String>, notation: impl Into<String>, action_type: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            notation: notation.into(),
+            action_type: action_type.into(),
+            leaps: Vec::new(),
+            rides: Vec::new(),
+            captures: false,
+            effect: None,
+            effect_turns: None,
+            limit: None,
+            allow_empty: false,
+        }
+    }
+
+    pub fn with_leap(mut self, offset: Vec<isize>) -> Self {
+        self.leaps.push(offset);
+        self
+    }
+
+    pub fn with_ride(mut self, offset: Vec<isize>) -> Self {
+        self.rides.push(offset);
+        self
+    }
+
+    pub fn capturing(mut self) -> Self {
+        self.captures = true;
+        self
+    }
+
+    pub fn with_effect(mut self, effect: impl Into<String>, turns: Option<usize>) -> Self {
+        self.effect = Some(effect.into());
+        self.effect_turns = turns;
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    #[inline(always)]
+    pub fn get_notation(&self) -> &String {
+        &self.notation
+    }
+
+    #[inline(always)]
+    pub fn get_action_type(&self) -> &String {
+        &self.action_type
+    }
+
+    #[inline(always)]
+    pub fn get_leaps(&self) -> &Vec<Vec<isize>> {
+        &self.leaps
+    }
+
+    #[inline(always)]
+    pub fn get_rides(&self) -> &Vec<Vec<isize>> {
+        &self.rides
+    }
+
+    #[inline(always)]
+    pub fn captures(&self) -> bool {
+        self.captures
+    }
+
+    #[inline(always)]
+    pub fn get_effect(&self) -> Option<&String> {
+        self.effect.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_effect_turns(&self) -> Option<usize> {
+        self.effect_turns
+    }
+
+    #[inline(always)]
+    pub fn get_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    #[inline(always)]
+    pub fn allows_empty(&self) -> bool {
+        self.allow_empty
+    }
+}
+
+impl MoveFormat {
+    pub fn new(movement_type: Vec<String>, offset: Vec<isize>, max_times: Option<usize>) -> Self {
+        Self { movement_type, offset, max_times, guard: None, symmetries: Vec::new() }
+    }
+
+    #[inline(always)]
+    pub fn get_movement_type(&self) -> &Vec<String> {
+        &self.movement_type
+    }
+
+    #[inline(always)]
+    pub fn get_offset(&self) -> &Vec<isize> {
+        &self.offset
+    }
+
+    #[inline(always)]
+    pub fn get_max_times(&self) -> Option<usize> {
+        self.max_times
+    }
+
+    #[inline(always)]
+    pub fn get_guard(&self) -> Option<&String> {
+        self.guard.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_symmetries(&self) -> &Vec<String> {
+        &self.symmetries
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::{
+    effects::EffectKind,
+    game::GameState,
+    moves::Move,
+    piece::PlayerId,
+    position::{AbsolutePosition, Board},
+    random::GameRng,
+    search::{SearchLimits, Searcher},
+};
+
+impl<const D: usize> GameState<D> {
+    /// `player`가 보는 칸. 자기 기물이 있는 칸과 그 기물들이 움직이거나 잡거나 액션을 쓸 수 있는 칸입니다.
+    /// `Blind` 효과가 걸린 기물은 자기 칸만 봅니다.
+    pub fn visible_squares(&self, player: PlayerId) -> BTreeSet<AbsolutePosition<D>> {
+        let ply = self.ply();
+        let mut visible = BTreeSet::new();
+        let mut moves = Vec::new();
+        for (position, piece) in self.get_board().pieces().filter(|(_, piece)| self.controls(player, piece)) {
+            visible.insert(*position);
+            let blind = piece.get_effects().iter().any(|effect| effect.is_active(ply) && *effect.get_kind() == EffectKind::Blind);
+            if !blind {
+                self.piece_moves(position, piece, &mut moves);
+            }
+        }
+        for mv in &moves {
+            visible.insert(*mv.get_to());
+            visible.extend(mv.get_captures().iter().chain(mv.get_targets()).copied());
+        }
+        visible
+    }
+
+    /// `player`가 보지 못하는 다른 플레이어의 기물들을 보이지 않는 빈 칸에 무작위로 다시 놓은 게임.
+    /// 기물은 `player`에게 보이는 모습(`seen_by`)으로 놓고 지나간 수와 난수 상태도 옮기지 않으므로
+    /// 숨은 기물의 실제 위치나 정체는 결과에 드러나지 않습니다.
+    /// 기물을 자기 프로모션 구역에는 놓지 않고, 그런 칸밖에 없으면 남은 칸 아무 데나 놓아서 기물(royal 기물 포함)이 빠지지 않게 합니다.
+    /// 숨은 기물마다 원래 칸도 후보에 들어가므로 놓을 칸이 모자라지 않습니다.
+    /// 캐슬링 권리, 앙파상, 칸 효과도 `player`가 아는 칸(보이는 칸과 자기가 움직이는 기물의 칸)의 것만 남깁니다.
+    /// 칸 효과는 `player`가 건 것이면 어디든 남깁니다.
+    pub fn determinize(&self, player: PlayerId, seed: u64) -> GameState<D> {
+        let visible = self.visible_squares(player);
+        let board = self.get_board();
+        let mut view = Board::new(*board.get_min_position(), *board.get_max_position(), *board.get_zobrist());
+        let mut hidden = Vec::new();
+        for (position, piece) in board.pieces() {
+            if self.controls(player, piece) || visible.contains(position) {
+                view.set_piece(*position, self.seen_by(player, piece));
+            } else {
+                hidden.push((*position, self.seen_by(player, piece)));
+            }
+        }
+        hidden.sort_unstable_by_key(|(position, _)| *position);
+        let mut empty: Vec<_> = hidden.iter().map(|(position, _)| *position)
+            .chain(board.empty_squares().filter(|position| !visible.contains(position)))
+            .collect();
+        let mut rng = GameRng::new(seed);
+        let rules = self.get_rules();
+        for (_, piece) in hidden {
+            let definition = rules.get_definition(&piece);
+            let controller = self.controller(&piece);
+            let allowed: Vec<_> = (0..empty.len()).filter(|&index| definition.is_none_or(|definition| !self.promotes_at(definition, controller, &empty[index]))).collect();
+            let candidates = if allowed.is_empty() { (0..empty.len()).collect() } else { allowed };
+            let index = candidates[rng.below(candidates.len())];
+            view.set_piece(empty.swap_remove(index), piece);
+        }
+        let known = |position: &AbsolutePosition<D>| visible.contains(position) || board.get(position).is_some_and(|piece| self.controls(player, piece));
+        let mut sample = self.with_board(view, rng.next_u64());
+        for position in self.get_castling().iter().filter(|position| !known(position)) {
+            sample.remove_castling(position);
+        }
+        if self.get_en_passant().is_some_and(|(_, victim)| !known(victim)) {
+            sample.set_en_passant(None);
+        }
+        for (position, effects) in self.square_effects().filter(|(position, _)| !known(position)) {
+            let own = effects.iter().filter(|effect| effect.get_source() == player).cloned().collect();
+            sample.set_square_effects(*position, own);
+        }
+        sample
+    }
+
+    /// 안개 게임에서 차례인 플레이어가 둘 수. 보이지 않는 기물을 `samples`번 무작위로 놓아 보고
+    /// 각각 찾은 가장 좋은 수 중 가장 많이 나온 수를 고릅니다. (같으면 점수 합이 큰 수)
+    /// 치환표는 `searcher`의 것을 같이 쓰므로 부를 때마다 새로 만들지 않습니다.
+    pub fn fog_search(&self, searcher: &mut Searcher<D>, limits: SearchLimits, samples: usize, seed: u64) -> Option<Move<D>> {
+        let player = self.side_to_move();
+        let mut votes: HashMap<u32, (usize, i64, Move<D>)> = HashMap::new();
+        let mut rng = GameRng::new(seed);
+        for _ in 0..samples.max(1) {
+            let mut sample = self.determinize(player, rng.next_u64());
+            let result = searcher.search(&mut sample, limits);
+            if let Some(mv) = result.best_move {
+                let vote = votes.entry(mv.fingerprint()).or_insert((0, 0, mv));
+                vote.0 += 1;
+                vote.1 += result.score as i64;
+            }
+        }
+        votes.into_values().max_by_key(|(count, score, _)| (*count, *score)).map(|(_, _, mv)| mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{effects::Effect, fen::parse_square, ownership::Owner, presets};
+
+    fn fog_game(fen: &str) -> GameState<2> {
+        let mut game = GameState::from_fen(fen, presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        game.set_fog_of_war(true);
+        game
+    }
+
+    #[test]
+    fn fog_hides_unseen_pieces() {
+        let mut game = fog_game("r3k3/8/8/8/8/8/4q3/R3K3 w - - 0 1");
+        let view = game.view_for(0);
+        assert!(view.get(&parse_square("e2").unwrap()).is_some());
+        assert!(view.get(&parse_square("a8").unwrap()).is_some());
+        assert!(view.get(&parse_square("e8").unwrap()).is_none());
+        assert!(game.legal_moves().iter().any(|mv| mv.to_string() == "e1f1"));
+
+        let sample = game.determinize(0, 7);
+        assert_eq!(sample.get_board().pieces().count(), 5);
+        assert_eq!(sample.get_board().get(&parse_square("e2").unwrap()).unwrap().get_name(), "queen");
+        let mut searcher = Searcher::new(1);
+        assert_eq!(game.fog_search(&mut searcher, SearchLimits { depth: Some(2), ..SearchLimits::default() }, 4, 1).unwrap().to_string(), "e1e2");
+
+        for text in ["e1f1", "e8d8"] {
+            let mv = game.legal_moves().into_iter().find(|mv| mv.to_string() == text).unwrap();
+            game.make_move(mv);
+        }
+        let sample = game.determinize(0, 7);
+        assert!(sample.history().is_empty());
+        assert_eq!(sample.get_board().pieces().count(), 5);
+        assert_eq!(sample.hash(), sample.compute_hash());
+
+        let crowded = fog_game("pppkpppp/pppppppp/pppppppp/pppppppp/pppppppp/pppppppp/3ppppp/1K1ppppp w - - 0 1");
+        for seed in 0..8 {
+            let sample = crowded.determinize(0, seed);
+            assert_eq!(sample.get_board().pieces().count(), crowded.get_board().pieces().count());
+            assert!(sample.get_board().pieces().any(|(_, piece)| piece.get_name() == "king" && piece.get_owner() == Owner::Player(1)));
+        }
+    }
+
+    #[test]
+    fn determinize_drops_hidden_state() {
+        let square = |name| parse_square(name).unwrap();
+        let mut game = fog_game("r3k2r/p6p/8/3p4/8/8/P6P/R3K2R w KQkq d6 0 1");
+        game.set_square_effects(square("c6"), vec![Effect::new(EffectKind::Shield, 1, None), Effect::new(EffectKind::Shield, 0, None)]);
+        game.set_square_effects(square("f7"), vec![Effect::new(EffectKind::Shield, 1, None)]);
+        let sample = game.determinize(0, 3);
+        assert_eq!(sample.get_castling().iter().copied().collect::<Vec<_>>(), [square("a1"), square("h1")]);
+        assert!(sample.get_en_passant().is_none());
+        assert_eq!(sample.get_square_effects(&square("c6")), [Effect::new(EffectKind::Shield, 0, None)]);
+        assert!(sample.get_square_effects(&square("f7")).is_empty());
+        assert_eq!(sample.hash(), sample.compute_hash());
+    }
+}
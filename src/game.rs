@@ -0,0 +1,661 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+use crate::{
+    effects::Effect,
+    hooks::HookEvent,
+    movement::{MOVED_STATE, PROMOTED_STATE},
+    moves::Move,
+    ownership::Owner,
+    piece::{Piece, PlayerId},
+    position::{AbsolutePosition, Board},
+    random::GameRng,
+    rules::{PieceDefinition, Ruleset, CASTLE},
+    transform::TIMER_STATE,
+    turns::Continuation,
+    visibility::RevealEvent,
+};
+
+/// ## PlayerState
+/// 플레이어마다 따로 가지는 자원 (코인 등)과 손에 든 기물.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerState {
+    resources: BTreeMap<String, i64>,
+    hand: BTreeMap<String, usize>,
+}
+
+impl PlayerState {
+    #[inline(always)]
+    pub fn get_resource(&self, name: &str) -> i64 {
+        self.resources.get(name).copied().unwrap_or(0)
+    }
+
+    #[inline(always)]
+    pub fn resources(&self) -> impl Iterator<Item = (&String, &i64)> {
+        self.resources.iter()
+    }
+
+    /// 손에 든 `name` 기물 수.
+    #[inline(always)]
+    pub fn get_hand_count(&self, name: &str) -> usize {
+        self.hand.get(name).copied().unwrap_or(0)
+    }
+
+    #[inline(always)]
+    pub fn hand(&self) -> impl Iterator<Item = (&String, &usize)> {
+        self.hand.iter()
+    }
+}
+
+/// ## Change
+/// 수를 두면서 바뀐 것 하나. 바뀌기 전 값을 들고 있어서 거꾸로 적용하면 원래대로 돌아갑니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change<const D: usize> {
+    Square { position: AbsolutePosition<D>, old: Option<Piece> },
+    SquareEffects { position: AbsolutePosition<D>, old: Vec<Effect> },
+    SideToMove(PlayerId),
+    Castling { position: AbsolutePosition<D>, added: bool },
+    EnPassant(Option<(AbsolutePosition<D>, AbsolutePosition<D>)>),
+    Continuation(Option<Continuation<D>>),
+    Ply(usize),
+    Resource { player: PlayerId, name: String, old: i64 },
+    Hand { player: PlayerId, name: String, old: usize },
+    Counter { name: String, old: i64 },
+    Rng(GameRng),
+    Reveal,
+}
+
+/// ## Undo
+/// 수 하나와 그 수가 만든 모든 변경, 그리고 잡기 훅이 일으킨 일. `unmake_move`는 변경을 역순으로 되돌립니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Undo<const D: usize> {
+    mv: Move<D>,
+    changes: Vec<Change<D>>,
+    events: Vec<HookEvent<D>>,
+}
+
+impl<const D: usize> Undo<D> {
+    #[inline(always)]
+    pub fn get_move(&self) -> &Move<D> {
+        &self.mv
+    }
+
+    #[inline(always)]
+    pub fn get_changes(&self) -> &[Change<D>] {
+        &self.changes
+    }
+
+    #[inline(always)]
+    pub fn get_events(&self) -> &[HookEvent<D>] {
+        &self.events
+    }
+}
+
+/// 기보에 적는 형태. 수 뒤에 훅이 일으킨 일을 `;`로 이어 적습니다. (`a5a7;a7>a1`)
+impl<const D: usize> Display for Undo<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.mv)?;
+        for event in &self.events {
+            write!(f, ";{event}")?;
+        }
+        Ok(())
+    }
+}
+
+/// ## GameState
+/// 보드와 보드 밖의 게임 상태(차례, 캐슬링, 앙파상)를 같이 들고 있습니다.
+/// 앙파상은 (잡으러 들어가는 칸, 잡히는 기물이 있는 칸)으로 저장합니다.
+/// 상태를 바꾸는 함수들은 전부 Zobrist 키를 같이 갱신하므로 `hash()`는 항상 현재 국면의 키입니다.
+/// `make_move` 도중에 상태를 바꾸면 그 변경은 `Undo`에 기록되어 `unmake_move`로 되돌릴 수 있습니다.
+/// 부가 효과를 구현할 때는 `get_board_mut`로 보드를 직접 바꾸지 말고 이 타입의 함수들을 쓰세요.
+#[derive(Debug, Clone)]
+pub struct GameState<const D: usize> {
+    board: Board<D>,
+    rules: Arc<Ruleset<D>>,
+    side_to_move: PlayerId,
+    castling: BTreeSet<AbsolutePosition<D>>,
+    en_passant: Option<(AbsolutePosition<D>, AbsolutePosition<D>)>,
+    continuation: Option<Continuation<D>>,
+    square_effects: BTreeMap<AbsolutePosition<D>, Vec<Effect>>,
+    reveal_events: Vec<RevealEvent<D>>,
+    fog_of_war: bool,
+    rng: GameRng,
+    ply: usize,
+    players: Vec<PlayerState>,
+    counters: BTreeMap<String, i64>,
+    state_hash: u64,
+    hash_check: bool,
+    undo_stack: Vec<Undo<D>>,
+    pending: Option<Vec<Change<D>>>,
+}
+
+impl<const D: usize> GameState<D> {
+    pub fn new(board: Board<D>, rules: Arc<Ruleset<D>>) -> Self {
+        let player_count = rules.player_count();
+        let mut game = Self {
+            board,
+            rules,
+            side_to_move: 0,
+            castling: BTreeSet::new(),
+            en_passant: None,
+            continuation: None,
+            square_effects: BTreeMap::new(),
+            reveal_events: Vec::new(),
+            fog_of_war: false,
+            rng: GameRng::new(0),
+            ply: 0,
+            players: vec![PlayerState::default(); player_count],
+            counters: BTreeMap::new(),
+            state_hash: 0,
+            hash_check: false,
+            undo_stack: Vec::new(),
+            pending: None,
+        };
+        game.state_hash = game.compute_state_hash();
+        game
+    }
+
+    /// 보드만 `board`로 바꾼 게임. 보드 밖 상태는 그대로 옮기지만 지나간 수, 드러남 사건, 난수 상태는 옮기지 않습니다.
+    /// 숨은 정보가 들어 있으면 안 되는 게임(`determinize`)을 만들 때 씁니다.
+    pub(crate) fn with_board(&self, board: Board<D>, seed: u64) -> Self {
+        Self {
+            board,
+            rules: Arc::clone(&self.rules),
+            side_to_move: self.side_to_move,
+            castling: self.castling.clone(),
+            en_passant: self.en_passant,
+            continuation: self.continuation,
+            square_effects: self.square_effects.clone(),
+            reveal_events: Vec::new(),
+            fog_of_war: self.fog_of_war,
+            rng: GameRng::new(seed),
+            ply: self.ply,
+            players: self.players.clone(),
+            counters: self.counters.clone(),
+            state_hash: self.state_hash,
+            hash_check: self.hash_check,
+            undo_stack: Vec::new(),
+            pending: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_board(&self) -> &Board<D> {
+        &self.board
+    }
+
+    #[inline(always)]
+    pub fn get_board_mut(&mut self) -> &mut Board<D> {
+        &mut self.board
+    }
+
+    #[inline(always)]
+    pub fn get_rules(&self) -> &Arc<Ruleset<D>> {
+        &self.rules
+    }
+
+    #[inline(always)]
+    pub fn player_count(&self) -> usize {
+        self.rules.player_count()
+    }
+
+    #[inline(always)]
+    pub fn side_to_move(&self) -> PlayerId {
+        self.side_to_move
+    }
+
+    #[inline(always)]
+    pub fn get_castling(&self) -> &BTreeSet<AbsolutePosition<D>> {
+        &self.castling
+    }
+
+    #[inline(always)]
+    pub fn get_en_passant(&self) -> Option<&(AbsolutePosition<D>, AbsolutePosition<D>)> {
+        self.en_passant.as_ref()
+    }
+
+    /// 이어 두는 중인 차례. 있으면 차례가 아직 넘어가지 않은 것입니다.
+    #[inline(always)]
+    pub fn get_continuation(&self) -> Option<&Continuation<D>> {
+        self.continuation.as_ref()
+    }
+
+    /// 안개 게임인지. 안개 게임에서는 각 플레이어가 `visible_squares`만 보고,
+    /// 체크를 알려주면 숨은 기물이 드러나므로 체크 규칙 없이 royal 기물을 잡으면 이깁니다.
+    #[inline(always)]
+    pub fn is_fog_of_war(&self) -> bool {
+        self.fog_of_war
+    }
+
+    pub fn set_fog_of_war(&mut self, fog_of_war: bool) {
+        self.fog_of_war = fog_of_war;
+    }
+
+    /// 지금까지 있었던 드러남 사건. 수를 되돌리면 같이 사라집니다.
+    #[inline(always)]
+    pub fn reveal_events(&self) -> &[RevealEvent<D>] {
+        &self.reveal_events
+    }
+
+    #[inline(always)]
+    pub fn get_square_effects(&self, position: &AbsolutePosition<D>) -> &[Effect] {
+        self.square_effects.get(position).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    #[inline(always)]
+    pub fn square_effects(&self) -> impl Iterator<Item = (&AbsolutePosition<D>, &Vec<Effect>)> {
+        self.square_effects.iter()
+    }
+
+    /// 게임의 난수 생성기. 무작위는 `roll`로 뽑으세요.
+    #[inline(always)]
+    pub fn get_rng(&self) -> &GameRng {
+        &self.rng
+    }
+
+    /// 난수 생성기를 `seed`로 새로 시작합니다. 게임을 준비할 때 씁니다.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = GameRng::new(seed);
+    }
+
+    #[inline(always)]
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// 현재 국면의 Zobrist 키. 효과의 남은 시간(`Zobrist::piece_effect_clock`)은 ply마다 바뀌므로 여기서 더합니다.
+    #[inline(always)]
+    pub fn hash(&self) -> u64 {
+        self.board.hash() ^ self.state_hash ^ self.effect_clock_hash()
+    }
+
+    /// 걸려 있는 효과들의 남은 시간 키. 효과가 없으면 0입니다.
+    fn effect_clock_hash(&self) -> u64 {
+        let zobrist = self.board.get_zobrist();
+        let pieces = self.board.pieces()
+            .filter(|(_, piece)| !piece.get_effects().is_empty())
+            .fold(0, |hash, (position, piece)| hash ^ zobrist.piece_effect_clock(position, piece.get_effects(), self.ply));
+        self.square_effects.iter().fold(pieces, |hash, (position, effects)| hash ^ zobrist.square_effect_clock(position, effects, self.ply))
+    }
+
+    #[inline(always)]
+    pub fn get_player(&self, player: PlayerId) -> &PlayerState {
+        &self.players[player]
+    }
+
+    #[inline(always)]
+    pub fn get_counter(&self, name: &str) -> i64 {
+        self.counters.get(name).copied().unwrap_or(0)
+    }
+
+    #[inline(always)]
+    pub fn history(&self) -> &[Undo<D>] {
+        &self.undo_stack
+    }
+
+    #[inline(always)]
+    pub fn next_player(&self, player: PlayerId) -> PlayerId {
+        (player + 1) % self.player_count()
+    }
+
+    fn record(&mut self, change: Change<D>) {
+        if let Some(pending) = &mut self.pending {
+            pending.push(change);
+        }
+    }
+
+    /// 기물을 놓고 원래 있던 기물을 돌려줍니다. `Timer` 변신 규칙이 있는 기물이 처음 놓이면 타이머를 지금부터 셉니다.
+    pub fn put_piece(&mut self, position: AbsolutePosition<D>, mut piece: Piece) -> Option<Piece> {
+        if piece.get_state().get(TIMER_STATE).is_none() && self.rules.get_definition(&piece).is_some_and(PieceDefinition::has_timer) {
+            piece.get_state_mut().set(TIMER_STATE, self.ply.to_string());
+        }
+        let old = self.board.set_piece(position, piece);
+        self.record(Change::Square { position, old: old.clone() });
+        old
+    }
+
+    pub fn take_piece(&mut self, position: &AbsolutePosition<D>) -> Option<Piece> {
+        let old = self.board.remove_piece(position);
+        if old.is_some() {
+            self.record(Change::Square { position: *position, old: old.clone() });
+        }
+        old
+    }
+
+    /// 기물의 상태 등을 바꿉니다. 바뀌기 전 기물 전체를 기록합니다.
+    pub fn update_piece<R>(&mut self, position: &AbsolutePosition<D>, f: impl FnOnce(&mut Piece) -> R) -> Option<R> {
+        let old = self.board.get(position)?.clone();
+        let result = self.board.update_piece(position, f);
+        self.record(Change::Square { position: *position, old: Some(old) });
+        result
+    }
+
+    pub fn set_side_to_move(&mut self, player: PlayerId) {
+        let zobrist = *self.board.get_zobrist();
+        self.record(Change::SideToMove(self.side_to_move));
+        self.state_hash ^= zobrist.side_to_move(self.side_to_move) ^ zobrist.side_to_move(player);
+        self.side_to_move = player;
+    }
+
+    pub fn set_ply(&mut self, ply: usize) {
+        self.record(Change::Ply(self.ply));
+        self.state_hash ^= self.ply_key(self.ply) ^ self.ply_key(ply);
+        self.ply = ply;
+    }
+
+    /// 이동 조건이 ply를 읽으면 그 값의 키. 조건이 보는 만큼만 넣어야 반복 판정이 살아 있습니다.
+    fn ply_key(&self, ply: usize) -> u64 {
+        self.rules.get_ply_dependence().map_or(0, |dependence| self.board.get_zobrist().ply(dependence.key_of(ply)))
+    }
+
+    /// 캐슬링 권리 추가. 이미 있었으면 false.
+    pub fn add_castling(&mut self, position: AbsolutePosition<D>) -> bool {
+        let inserted = self.castling.insert(position);
+        if inserted {
+            self.state_hash ^= self.board.get_zobrist().castling(&position);
+            self.record(Change::Castling { position, added: true });
+        }
+        inserted
+    }
+
+    /// 캐슬링 권리 제거. 원래 없었으면 false.
+    pub fn remove_castling(&mut self, position: &AbsolutePosition<D>) -> bool {
+        let removed = self.castling.remove(position);
+        if removed {
+            self.state_hash ^= self.board.get_zobrist().castling(position);
+            self.record(Change::Castling { position: *position, added: false });
+        }
+        removed
+    }
+
+    pub fn set_en_passant(&mut self, en_passant: Option<(AbsolutePosition<D>, AbsolutePosition<D>)>) -> Option<(AbsolutePosition<D>, AbsolutePosition<D>)> {
+        let zobrist = *self.board.get_zobrist();
+        if let Some((old, _)) = &self.en_passant {
+            self.state_hash ^= zobrist.en_passant(old);
+        }
+        if let Some((new, _)) = &en_passant {
+            self.state_hash ^= zobrist.en_passant(new);
+        }
+        let old = std::mem::replace(&mut self.en_passant, en_passant);
+        self.record(Change::EnPassant(old));
+        old
+    }
+
+    pub fn set_continuation(&mut self, continuation: Option<Continuation<D>>) -> Option<Continuation<D>> {
+        let zobrist = *self.board.get_zobrist();
+        for changed in self.continuation.iter().chain(continuation.iter()) {
+            self.state_hash ^= zobrist.continuation(changed);
+        }
+        let old = std::mem::replace(&mut self.continuation, continuation);
+        self.record(Change::Continuation(old));
+        old
+    }
+
+    pub fn push_reveal(&mut self, event: RevealEvent<D>) {
+        self.reveal_events.push(event);
+        self.record(Change::Reveal);
+    }
+
+    /// 칸에 걸린 효과를 통째로 바꾸고 원래 효과들을 돌려줍니다.
+    pub fn set_square_effects(&mut self, position: AbsolutePosition<D>, effects: Vec<Effect>) -> Vec<Effect> {
+        let zobrist = *self.board.get_zobrist();
+        let old = if effects.is_empty() {
+            self.square_effects.remove(&position)
+        } else {
+            self.square_effects.insert(position, effects)
+        }.unwrap_or_default();
+        let new = self.square_effects.get(&position).map(Vec::as_slice).unwrap_or(&[]);
+        self.state_hash ^= zobrist.square_effects(&position, &old) ^ zobrist.square_effects(&position, new);
+        self.record(Change::SquareEffects { position, old: old.clone() });
+        old
+    }
+
+    /// 난수 상태를 바꾸고 원래 상태를 돌려줍니다.
+    pub fn set_rng(&mut self, rng: GameRng) -> GameRng {
+        let old = std::mem::replace(&mut self.rng, rng);
+        self.record(Change::Rng(old));
+        old
+    }
+
+    /// 자원 값을 바꾸고 원래 값을 돌려줍니다.
+    pub fn set_resource(&mut self, player: PlayerId, name: &str, value: i64) -> i64 {
+        let old = if value == 0 {
+            self.players[player].resources.remove(name)
+        } else {
+            self.players[player].resources.insert(name.to_string(), value)
+        }.unwrap_or(0);
+        let zobrist = *self.board.get_zobrist();
+        self.state_hash ^= zobrist.resource(player, name, old) ^ zobrist.resource(player, name, value);
+        self.record(Change::Resource { player, name: name.to_string(), old });
+        old
+    }
+
+    pub fn add_resource(&mut self, player: PlayerId, name: &str, delta: i64) -> i64 {
+        let value = self.players[player].get_resource(name) + delta;
+        self.set_resource(player, name, value);
+        value
+    }
+
+    /// 손에 든 `name` 기물 수를 바꾸고 원래 수를 돌려줍니다.
+    pub fn set_hand_count(&mut self, player: PlayerId, name: &str, count: usize) -> usize {
+        let old = if count == 0 {
+            self.players[player].hand.remove(name)
+        } else {
+            self.players[player].hand.insert(name.to_string(), count)
+        }.unwrap_or(0);
+        let zobrist = *self.board.get_zobrist();
+        self.state_hash ^= zobrist.hand(player, name, old) ^ zobrist.hand(player, name, count);
+        self.record(Change::Hand { player, name: name.to_string(), old });
+        old
+    }
+
+    /// 게임 전체 카운터 값을 바꾸고 원래 값을 돌려줍니다.
+    pub fn set_counter(&mut self, name: &str, value: i64) -> i64 {
+        let old = if value == 0 {
+            self.counters.remove(name)
+        } else {
+            self.counters.insert(name.to_string(), value)
+        }.unwrap_or(0);
+        let zobrist = *self.board.get_zobrist();
+        self.state_hash ^= zobrist.counter(name, old) ^ zobrist.counter(name, value);
+        self.record(Change::Counter { name: name.to_string(), old });
+        old
+    }
+
+    /// 수를 둡니다.
+    /// 기물 이동, 잡기, 같이 옮겨지는 기물, 프로모션, 앙파상 칸, 캐슬링 권리, 차례를 처리하고
+    /// 그 사이의 모든 변경을 `Undo` 하나로 묶어 스택에 쌓습니다.
+    /// 같이 옮겨지는 기물은 전부 들어올린 다음에 내려놓으므로 자리를 바꾸거나(캐슬링) 줄줄이 밀어도 됩니다.
+    /// 같은 차례에 이어 둘 수 있으면(`Continuation`) 차례와 ply는 그대로 둡니다.
+    pub fn make_move(&mut self, mv: Move<D>) {
+        let outer = self.pending.replace(Vec::new());
+
+        let from = *mv.get_from();
+        let to = *mv.get_to();
+        let mut captured = Vec::new();
+        for position in mv.get_captures() {
+            captured.extend(self.take_piece(position).map(|piece| (*position, piece)));
+            self.remove_castling(position);
+        }
+        let displaced: Vec<_> = mv.get_displacements().iter()
+            .filter_map(|(displaced_from, displaced_to)| {
+                self.remove_castling(displaced_from);
+                self.take_piece(displaced_from).map(|piece| (*displaced_to, piece))
+            })
+            .collect();
+        if let Some(notation) = mv.get_action() {
+            self.record_action_use(&from, notation);
+        }
+        if let Some(piece) = mv.get_drop() {
+            self.pay_for_drop(self.side_to_move, &mv);
+            self.put_piece(to, piece.clone());
+        }
+        let moving = if mv.relocates() { self.take_piece(&from) } else { None };
+        let mut promoted_from = None;
+        if let Some(mut piece) = moving {
+            captured.extend(self.take_piece(&to).map(|piece| (to, piece)));
+            let rules = Arc::clone(&self.rules);
+            if let Some(definition) = rules.get_definition(&piece) {
+                if let Some(owner) = piece.get_owner().player().filter(|_| definition.has_attribute(CASTLE)) {
+                    self.remove_own_castling(owner);
+                }
+                if rules.tracks_moved_state(&piece) && piece.get_state().get(MOVED_STATE).is_none() {
+                    piece.get_state_mut().set(MOVED_STATE, "true");
+                }
+            }
+            if let Some(promotion) = mv.get_promotion() {
+                let base = piece.get_state().get(PROMOTED_STATE).cloned().unwrap_or_else(|| piece.get_name().clone());
+                promoted_from = Some(piece.get_name().clone());
+                piece = promotion.clone();
+                piece.get_state_mut().set(PROMOTED_STATE, base);
+            }
+            self.put_piece(to, piece);
+        }
+        for (displaced_to, piece) in displaced {
+            self.take_piece(&displaced_to);
+            self.put_piece(displaced_to, piece);
+        }
+        let mut events = Vec::new();
+        if !captured.is_empty() {
+            let captor = if mv.relocates() { to } else { from };
+            self.resolve_hidden_captures(&captor, &captured);
+            events = self.run_capture_hooks(&captor, &mut captured);
+            self.settle_captures(self.side_to_move, &captor, &captured);
+        }
+        events.extend(self.run_move_transforms(&mv, &captured, promoted_from.as_deref()));
+        if mv.relocates() {
+            self.remove_castling(&from);
+            self.remove_castling(&to);
+        }
+        self.set_en_passant(mv.get_en_passant().map(|passed| (*passed, to)));
+        self.resolve_chance(&mv);
+        let source = self.side_to_move;
+        for (target, spec) in mv.get_effects() {
+            self.apply_effect(*target, spec, source);
+        }
+        let continuation = self.continuation_after(&mv);
+        let turn_ends = continuation.is_none();
+        self.set_continuation(continuation);
+        if turn_ends {
+            self.set_ply(self.ply + 1);
+            self.set_side_to_move(self.next_player(self.side_to_move));
+            self.expire_effects();
+            events.extend(self.run_timers());
+        }
+
+        let changes = std::mem::replace(&mut self.pending, outer).unwrap_or_default();
+        self.undo_stack.push(Undo { mv, changes, events });
+        self.verify_hash();
+    }
+
+    /// 캐슬링하는 기물이 움직이면 그 플레이어의 캐슬링 권리가 전부 사라집니다.
+    fn remove_own_castling(&mut self, player: PlayerId) {
+        let own: Vec<_> = self.castling.iter()
+            .filter(|position| self.board.get(position).is_some_and(|piece| piece.get_owner() == Owner::Player(player)))
+            .copied()
+            .collect();
+        for position in own {
+            self.remove_castling(&position);
+        }
+    }
+
+    /// 마지막 수를 되돌리고 그 수를 돌려줍니다.
+    pub fn unmake_move(&mut self) -> Option<Move<D>> {
+        let undo = self.undo_stack.pop()?;
+        let outer = self.pending.take();
+        for change in undo.changes.into_iter().rev() {
+            self.revert(change);
+        }
+        self.pending = outer;
+        self.verify_hash();
+        Some(undo.mv)
+    }
+
+    fn revert(&mut self, change: Change<D>) {
+        match change {
+            Change::Square { position, old: Some(piece) } => { self.board.set_piece(position, piece); }
+            Change::Square { position, old: None } => { self.board.remove_piece(&position); }
+            Change::SquareEffects { position, old } => { self.set_square_effects(position, old); }
+            Change::SideToMove(player) => self.set_side_to_move(player),
+            Change::Castling { position, added: true } => { self.remove_castling(&position); }
+            Change::Castling { position, added: false } => { self.add_castling(position); }
+            Change::EnPassant(position) => { self.set_en_passant(position); }
+            Change::Continuation(continuation) => { self.set_continuation(continuation); }
+            Change::Ply(ply) => self.set_ply(ply),
+            Change::Resource { player, name, old } => { self.set_resource(player, &name, old); }
+            Change::Hand { player, name, old } => { self.set_hand_count(player, &name, old); }
+            Change::Counter { name, old } => { self.set_counter(&name, old); }
+            Change::Rng(rng) => { self.set_rng(rng); }
+            Change::Reveal => { self.reveal_events.pop(); }
+        }
+    }
+
+    /// 켜 두면 수를 둘 때마다 키를 처음부터 다시 계산해서 증분 갱신한 키와 비교합니다.
+    /// 느리므로 디버깅할 때만 켜세요.
+    #[inline(always)]
+    pub fn set_hash_check(&mut self, hash_check: bool) {
+        self.hash_check = hash_check;
+    }
+
+    /// 키를 처음부터 다시 계산합니다.
+    pub fn compute_hash(&self) -> u64 {
+        self.board.compute_hash() ^ self.compute_state_hash() ^ self.effect_clock_hash()
+    }
+
+    /// `hash_check`가 켜져 있으면 증분 키와 다시 계산한 키가 같은지 확인합니다.
+    pub fn verify_hash(&self) {
+        if self.hash_check {
+            assert_eq!(self.hash(), self.compute_hash(), "incremental zobrist key diverged at ply {}", self.ply);
+        }
+    }
+
+    fn compute_state_hash(&self) -> u64 {
+        let zobrist = self.board.get_zobrist();
+        let mut hash = zobrist.side_to_move(self.side_to_move) ^ self.ply_key(self.ply);
+        for position in &self.castling {
+            hash ^= zobrist.castling(position);
+        }
+        if let Some((position, _)) = &self.en_passant {
+            hash ^= zobrist.en_passant(position);
+        }
+        if let Some(continuation) = &self.continuation {
+            hash ^= zobrist.continuation(continuation);
+        }
+        for (position, effects) in &self.square_effects {
+            hash ^= zobrist.square_effects(position, effects);
+        }
+        for (name, value) in &self.counters {
+            hash ^= zobrist.counter(name, *value);
+        }
+        for (player, state) in self.players.iter().enumerate() {
+            for (name, value) in &state.resources {
+                hash ^= zobrist.resource(player, name, *value);
+            }
+            for (name, count) in &state.hand {
+                hash ^= zobrist.hand(player, name, *count);
+            }
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen::parse_square, movement::MOVE, presets};
+
+    #[test]
+    fn counters_enter_the_position_key() {
+        let mut game = GameState::from_fen(presets::START_FEN, presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        let hash = game.hash();
+        assert_eq!(game.set_counter("turns", 3), 0);
+        assert_ne!(game.hash(), hash);
+        assert_eq!(game.hash(), game.compute_hash());
+        game.make_move(Move::new(parse_square("e2").unwrap(), parse_square("e4").unwrap(), MOVE));
+        game.unmake_move();
+        assert_eq!(game.set_counter("turns", 0), 3);
+        assert_eq!(game.hash(), hash);
+    }
+}
@@ -0,0 +1,519 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    str::FromStr,
+    sync::Arc,
+};
+use anyhow::{anyhow, bail, Error, Result};
+use crate::{
+    effects::EffectKind,
+    game::GameState,
+    movement::IndependentMove,
+    piece::{Piece, PlayerId},
+    position::AbsolutePosition,
+};
+
+/// ## Guard
+/// 이동 정의에 붙는 조건. 조건이 거짓이면 그 이동으로는 움직이지도, 공격하지도 못합니다.
+/// 기물 파일에는 식으로 적습니다. (`"count(queen) > 0"`, `"turn % 2 == 1 && !terrain(sea)"`)
+/// - `Compare`: 두 값 비교
+/// - `Terrain`: 기물이 선 칸에 그 이름의 칸 효과가 있는지
+/// - `Region`: 기물이 선 칸이 움직이는 플레이어에게 그 이름의 구역인지 (`region(name)`)
+/// - `Script`: 규칙에 등록된 스크립트 함수 호출 (`script(name)`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Guard {
+    Constant(bool),
+    Compare(Term, Comparison, Term),
+    Terrain(String),
+    Region(String),
+    Script(String),
+    Not(Box<Guard>),
+    All(Vec<Guard>),
+    Any(Vec<Guard>),
+}
+
+/// ## Term
+/// 조건식의 값.
+/// - `Ply`: 지금까지 둔 ply 수 (0부터)
+/// - `Turn`: 몇 번째 턴인지 (1부터, 모든 플레이어가 한 수씩 두면 1 늘어남)
+/// - `Count`, `EnemyCount`: 움직이는 플레이어의, 또는 적의 그 이름 기물 수
+/// - `State`: 이 기물의 상태 값. 숫자로 읽히면 숫자, 없으면 빈 글자입니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Number(i64),
+    Text(String),
+    Ply,
+    Turn,
+    Count(String),
+    EnemyCount(String),
+    State(String),
+    Arithmetic(Box<Term>, Arithmetic, Box<Term>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arithmetic {
+    Add,
+    Sub,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// 조건이 ply에 어떻게 기대는지. 같은 배치라도 이 값이 다르면 다른 국면이라 Zobrist 키에 넣어야 합니다.
+/// - `Absolute`: ply 값 그대로 (`ply > 3`, 스크립트)
+/// - `Phase(n)`: ply를 n으로 나눈 나머지만 (`turn % 2 == 1`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlyDependence {
+    Absolute,
+    Phase(usize),
+}
+
+impl PlyDependence {
+    /// 두 조건을 함께 볼 때. 나머지끼리는 최소공배수로 합칩니다.
+    pub fn combine(self, other: Self) -> Self {
+        match (self, other) {
+            (PlyDependence::Phase(left), PlyDependence::Phase(right)) => PlyDependence::Phase(left / gcd(left, right) * right),
+            _ => PlyDependence::Absolute,
+        }
+    }
+
+    /// `ply`에서 키에 넣을 값.
+    #[inline(always)]
+    pub fn key_of(self, ply: usize) -> usize {
+        match self {
+            PlyDependence::Absolute => ply,
+            PlyDependence::Phase(period) => ply % period,
+        }
+    }
+}
+
+fn gcd(left: usize, right: usize) -> usize {
+    if right == 0 { left } else { gcd(right, left % right) }
+}
+
+fn combine(left: Option<PlyDependence>, right: Option<PlyDependence>) -> Option<PlyDependence> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(left.combine(right)),
+        (left, right) => left.or(right),
+    }
+}
+
+impl Guard {
+    /// 이 조건이 ply에 기대는 방식. 기대지 않으면 `None`입니다.
+    /// 스크립트는 무엇을 읽는지 알 수 없으므로 ply 값 그대로에 기댄다고 봅니다.
+    pub fn ply_dependence(&self, player_count: usize) -> Option<PlyDependence> {
+        match self {
+            Guard::Constant(_) | Guard::Terrain(_) | Guard::Region(_) => None,
+            Guard::Script(_) => Some(PlyDependence::Absolute),
+            Guard::Compare(left, _, right) => combine(left.ply_dependence(player_count), right.ply_dependence(player_count)),
+            Guard::Not(guard) => guard.ply_dependence(player_count),
+            Guard::All(guards) | Guard::Any(guards) => guards.iter().fold(None, |dependence, guard| combine(dependence, guard.ply_dependence(player_count))),
+        }
+    }
+}
+
+impl Term {
+    /// 이 값이 ply에 기대는 방식. `ply % n`, `turn % n`처럼 나머지로만 쓰면 주기만 봅니다.
+    fn ply_dependence(&self, player_count: usize) -> Option<PlyDependence> {
+        match self {
+            Term::Ply | Term::Turn => Some(PlyDependence::Absolute),
+            Term::Arithmetic(left, Arithmetic::Rem, right) => match (left.plies_per_step(player_count), right.as_ref()) {
+                (Some(plies), Term::Number(period)) if *period > 0 => Some(PlyDependence::Phase(plies * *period as usize)),
+                _ => combine(left.ply_dependence(player_count), right.ply_dependence(player_count)),
+            },
+            Term::Arithmetic(left, _, right) => combine(left.ply_dependence(player_count), right.ply_dependence(player_count)),
+            Term::Number(_) | Term::Text(_) | Term::Count(_) | Term::EnemyCount(_) | Term::State(_) => None,
+        }
+    }
+
+    /// `ply`나 `turn`에 상수를 더하거나 뺀 값이면 한 번 바뀌는 데 걸리는 ply 수.
+    fn plies_per_step(&self, player_count: usize) -> Option<usize> {
+        match self {
+            Term::Ply => Some(1),
+            Term::Turn => Some(player_count),
+            Term::Arithmetic(left, Arithmetic::Add | Arithmetic::Sub, right) => match (left.as_ref(), right.as_ref()) {
+                (term, Term::Number(_)) | (Term::Number(_), term) => term.plies_per_step(player_count),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering.is_eq(),
+            Comparison::Ne => ordering.is_ne(),
+            Comparison::Lt => ordering.is_lt(),
+            Comparison::Le => ordering.is_le(),
+            Comparison::Gt => ordering.is_gt(),
+            Comparison::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// 조건식을 계산한 값. 글자는 `==`, `!=`로만 비교합니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Number(i64),
+    Text(String),
+}
+
+/// 스크립트 함수. 게임 상태, 기물이 선 칸, 기물을 받아서 그 이동을 쓸 수 있는지 돌려줍니다.
+pub type ScriptFn<const D: usize> = Arc<dyn Fn(&GameState<D>, &AbsolutePosition<D>, &Piece) -> bool + Send + Sync>;
+
+/// ## Scripts
+/// 이름으로 부르는 스크립트 함수 모음. 스크립트 엔진이 기물 파일의 `code`를 읽어 여기에 등록합니다.
+#[derive(Clone, Default)]
+pub struct Scripts<const D: usize> {
+    functions: HashMap<String, ScriptFn<D>>,
+}
+
+impl<const D: usize> Scripts<D> {
+    pub fn insert(&mut self, name: impl Into<String>, function: ScriptFn<D>) {
+        self.functions.insert(name.into(), function);
+    }
+
+    #[inline(always)]
+    pub fn get(&self, name: &str) -> Option<&ScriptFn<D>> {
+        self.functions.get(name)
+    }
+}
+
+impl<const D: usize> Debug for Scripts<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.functions.keys()).finish()
+    }
+}
+
+/// 같은 이름에 같은 함수가 등록되어 있으면 같습니다.
+impl<const D: usize> PartialEq for Scripts<D> {
+    fn eq(&self, other: &Self) -> bool {
+        self.functions.len() == other.functions.len()
+            && self.functions.iter().all(|(name, function)| other.functions.get(name).is_some_and(|other| Arc::ptr_eq(function, other)))
+    }
+}
+
+impl<const D: usize> Eq for Scripts<D> {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Name(String),
+    Text(String),
+    Number(i64),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 14] = ["||", "&&", "==", "!=", "<=", ">=", "!", "<", ">", "+", "-", "%", "(", ")"];
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(first) = rest.chars().next() {
+        if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if first == '\'' {
+            let end = rest[1..].find('\'').ok_or_else(|| anyhow!("unclosed quote in guard {text:?}"))?;
+            tokens.push(Token::Text(rest[1..=end].to_string()));
+            rest = &rest[end + 2..];
+        } else if first.is_ascii_digit() {
+            let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            tokens.push(Token::Number(rest[..end].parse()?));
+            rest = &rest[end..];
+        } else if first.is_alphanumeric() || first == '_' {
+            let end = rest.find(|c: char| !c.is_alphanumeric() && c != '_').unwrap_or(rest.len());
+            tokens.push(Token::Name(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else {
+            bail!("unexpected {first:?} in guard {text:?}");
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// 조건식 파서. 우선순위는 `||` < `&&` < `!` < 비교 < `+ - %`입니다. 괄호는 조건만 묶습니다.
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(found)) if *found == symbol);
+        if found {
+            self.index += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<()> {
+        if self.eat(symbol) { Ok(()) } else { bail!("expected {symbol:?}, found {:?}", self.peek()) }
+    }
+
+    fn any(&mut self) -> Result<Guard> {
+        let mut guards = vec![self.all()?];
+        while self.eat("||") {
+            guards.push(self.all()?);
+        }
+        Ok(if guards.len() == 1 { guards.remove(0) } else { Guard::Any(guards) })
+    }
+
+    fn all(&mut self) -> Result<Guard> {
+        let mut guards = vec![self.unary()?];
+        while self.eat("&&") {
+            guards.push(self.unary()?);
+        }
+        Ok(if guards.len() == 1 { guards.remove(0) } else { Guard::All(guards) })
+    }
+
+    fn unary(&mut self) -> Result<Guard> {
+        if self.eat("!") {
+            return Ok(Guard::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let guard = self.any()?;
+            self.expect(")")?;
+            return Ok(guard);
+        }
+        if let Some(Token::Name(name)) = self.peek().cloned() {
+            match name.as_str() {
+                "true" | "false" => {
+                    self.index += 1;
+                    return Ok(Guard::Constant(name == "true"));
+                }
+                "terrain" => return Ok(Guard::Terrain(self.argument_after_name()?)),
+                "region" => return Ok(Guard::Region(self.argument_after_name()?)),
+                "script" => return Ok(Guard::Script(self.argument_after_name()?)),
+                _ => {}
+            }
+        }
+        let left = self.term()?;
+        let comparison = match self.next() {
+            Some(Token::Symbol("==")) => Comparison::Eq,
+            Some(Token::Symbol("!=")) => Comparison::Ne,
+            Some(Token::Symbol("<")) => Comparison::Lt,
+            Some(Token::Symbol("<=")) => Comparison::Le,
+            Some(Token::Symbol(">")) => Comparison::Gt,
+            Some(Token::Symbol(">=")) => Comparison::Ge,
+            other => bail!("expected a comparison, found {other:?}"),
+        };
+        Ok(Guard::Compare(left, comparison, self.term()?))
+    }
+
+    /// `name(argument)`의 `argument`. 지금 토큰이 `name`입니다.
+    fn argument_after_name(&mut self) -> Result<String> {
+        self.index += 1;
+        self.expect("(")?;
+        let (Some(Token::Name(argument)) | Some(Token::Text(argument))) = self.next() else {
+            bail!("expected a name argument");
+        };
+        self.expect(")")?;
+        Ok(argument)
+    }
+
+    fn term(&mut self) -> Result<Term> {
+        let mut term = self.value()?;
+        loop {
+            let arithmetic = if self.eat("+") {
+                Arithmetic::Add
+            } else if self.eat("-") {
+                Arithmetic::Sub
+            } else if self.eat("%") {
+                Arithmetic::Rem
+            } else {
+                return Ok(term);
+            };
+            term = Term::Arithmetic(Box::new(term), arithmetic, Box::new(self.value()?));
+        }
+    }
+
+    fn value(&mut self) -> Result<Term> {
+        Ok(match self.peek().cloned() {
+            Some(Token::Number(number)) => {
+                self.index += 1;
+                Term::Number(number)
+            }
+            Some(Token::Text(text)) => {
+                self.index += 1;
+                Term::Text(text)
+            }
+            Some(Token::Name(name)) => match name.as_str() {
+                "ply" => {
+                    self.index += 1;
+                    Term::Ply
+                }
+                "turn" => {
+                    self.index += 1;
+                    Term::Turn
+                }
+                "count" => Term::Count(self.argument_after_name()?),
+                "enemies" => Term::EnemyCount(self.argument_after_name()?),
+                "state" => Term::State(self.argument_after_name()?),
+                _ => bail!("unknown name {name:?}; quote text like '{name}'"),
+            },
+            other => bail!("expected a value, found {other:?}"),
+        })
+    }
+}
+
+/// 조건식을 읽습니다. 값은 숫자, 따옴표로 묶은 글자(`'sea'`), `ply`, `turn`, `count(name)`, `enemies(name)`, `state(name)`이고
+/// 조건은 비교, `terrain(name)`, `region(name)`, `script(name)`, `true`, `false`를 `!`, `&&`, `||`, 괄호로 묶은 것입니다.
+impl FromStr for Guard {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut parser = Parser { tokens: tokenize(text)?, index: 0 };
+        let guard = parser.any()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected {token:?} in guard {text:?}");
+        }
+        Ok(guard)
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// `player`가 움직이는 `from`의 `piece`에 대해 `guard`가 참인지. 지역과 기물 수는 `player` 쪽에서 봅니다.
+    pub fn guard_holds(&self, guard: &Guard, from: &AbsolutePosition<D>, piece: &Piece, player: PlayerId) -> bool {
+        match guard {
+            Guard::Constant(value) => *value,
+            Guard::Compare(left, comparison, right) => match (self.term_value(left, piece, player), self.term_value(right, piece, player)) {
+                (Value::Number(left), Value::Number(right)) => comparison.holds(left.cmp(&right)),
+                (left, right) => match comparison {
+                    Comparison::Eq => left == right,
+                    Comparison::Ne => left != right,
+                    _ => false,
+                },
+            },
+            Guard::Terrain(name) => {
+                let kind = EffectKind::from_name(name);
+                self.get_square_effects(from).iter().any(|effect| effect.is_active(self.ply()) && *effect.get_kind() == kind)
+            }
+            Guard::Region(name) => self.in_region(name, player, from),
+            Guard::Script(name) => self.get_rules().get_scripts().get(name).is_some_and(|function| function(self, from, piece)),
+            Guard::Not(guard) => !self.guard_holds(guard, from, piece, player),
+            Guard::All(guards) => guards.iter().all(|guard| self.guard_holds(guard, from, piece, player)),
+            Guard::Any(guards) => guards.iter().any(|guard| self.guard_holds(guard, from, piece, player)),
+        }
+    }
+
+    /// 조건이 없거나 참이면 `step`을 쓸 수 있습니다.
+    #[inline(always)]
+    pub(crate) fn step_allowed(&self, step: &IndependentMove<D>, from: &AbsolutePosition<D>, piece: &Piece, player: PlayerId) -> bool {
+        step.get_guard().is_none_or(|guard| self.guard_holds(guard, from, piece, player))
+    }
+
+    fn term_value(&self, term: &Term, piece: &Piece, player: PlayerId) -> Value {
+        match term {
+            Term::Number(number) => Value::Number(*number),
+            Term::Text(text) => Value::Text(text.clone()),
+            Term::Ply => Value::Number(self.ply() as i64),
+            Term::Turn => Value::Number((self.ply() / self.player_count()) as i64 + 1),
+            Term::Count(name) => Value::Number(self.get_board().pieces()
+                .filter(|(_, other)| other.get_name() == name && self.controls(player, other))
+                .count() as i64),
+            Term::EnemyCount(name) => Value::Number(self.get_board().pieces()
+                .filter(|(_, other)| other.get_name() == name && self.is_hostile(player, other))
+                .count() as i64),
+            Term::State(name) => match piece.get_state().get(name) {
+                Some(value) => value.parse().map_or_else(|_| Value::Text(value.clone()), Value::Number),
+                None => Value::Text(String::new()),
+            },
+            Term::Arithmetic(left, arithmetic, right) => match (self.term_value(left, piece, player), self.term_value(right, piece, player)) {
+                (Value::Number(left), Value::Number(right)) => Value::Number(match arithmetic {
+                    Arithmetic::Add => left + right,
+                    Arithmetic::Sub => left - right,
+                    Arithmetic::Rem => left.checked_rem_euclid(right).unwrap_or(0),
+                }),
+                _ => Value::Text(String::new()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fen::parse_square, presets, rules::Ruleset};
+
+    fn can_move(game: &mut GameState<2>, from: &str) -> bool {
+        game.legal_moves().iter().any(|mv| mv.to_string().starts_with(from))
+    }
+
+    fn play(game: &mut GameState<2>, text: &str) {
+        let mv = game.legal_moves().into_iter().find(|mv| mv.to_string() == text).unwrap();
+        game.make_move(mv);
+    }
+
+    #[test]
+    fn guards_follow_turns_counts_and_scripts() {
+        assert!("turn % 2 == 1 && (count(queen) > 0 || !terrain(sea))".parse::<Guard>().is_ok());
+        assert!("turn %".parse::<Guard>().is_err());
+        assert!("plyy > 3".parse::<Guard>().is_err());
+        assert!("state(mode) == 'fly'".parse::<Guard>().is_ok());
+
+        let mut game = GameState::from_fen("3qk3/8/8/8/8/8/8/3QK2I w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        assert!(can_move(&mut game, "h1"));
+        play(&mut game, "h1h2");
+        play(&mut game, "e8e7");
+        assert!(!can_move(&mut game, "h2"));
+
+        let mut game = GameState::from_fen("3qk3/8/8/8/8/8/8/3QK3 w - - 0 1", presets::standard_board(), Arc::new(presets::tyrant_chess())).unwrap();
+        assert!(can_move(&mut game, "e1"));
+        play(&mut game, "d1d8");
+        assert!(!can_move(&mut game, "e8"));
+        assert!(game.in_check(1));
+
+        let mut rules: Ruleset<2> = Ruleset::new(2);
+        let step = IndependentMove::new(["move"], [1, 0].into()).with_guard("script(sea)".parse().unwrap());
+        for player in 0..2 {
+            rules.define(player, presets::king());
+            rules.define(player, crate::rules::PieceDefinition::new("swimmer", "I", 1).with_independent_move(step.clone()));
+        }
+        let swimmer = parse_square("a1").unwrap();
+        let sea: ScriptFn<2> = Arc::new(move |_, from, _| *from == swimmer);
+        let mut game = GameState::from_fen("4k3/8/8/8/8/8/8/I3K3 w - - 0 1", presets::standard_board(), Arc::new(rules.with_script("sea", sea))).unwrap();
+        play(&mut game, "a1a2");
+        play(&mut game, "e8e7");
+        assert!(!can_move(&mut game, "a2"));
+    }
+
+    #[test]
+    fn ply_guards_enter_the_position_key() {
+        assert_eq!("ply > 3".parse::<Guard>().unwrap().ply_dependence(2), Some(PlyDependence::Absolute));
+        assert_eq!("count(queen) > 0".parse::<Guard>().unwrap().ply_dependence(2), None);
+        assert_eq!(presets::fairy_chess().get_ply_dependence(), Some(PlyDependence::Phase(12)));
+        assert_eq!(presets::standard_chess().get_ply_dependence(), None);
+
+        for (rules, repeats) in [(presets::standard_chess(), true), (presets::fairy_chess(), false)] {
+            let mut game = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1", presets::standard_board(), Arc::new(rules)).unwrap();
+            game.set_hash_check(true);
+            let start = game.hash();
+            for text in ["e1e2", "e8e7", "e2e1", "e7e8"] {
+                play(&mut game, text);
+            }
+            assert_eq!(game.hash() == start, repeats);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt::{self, Display, Formatter};
+use anyhow::{bail, Result};
+use crate::{
+    effects::{EffectDuration, EffectKind, EffectSpec, EffectTarget},
+    file_scan::HookFormat,
+    game::GameState,
+    ownership::Owner,
+    piece::Piece,
+    position::AbsolutePosition,
+    visibility::INSPECTOR_STATE,
+};
+
+/// ## CaptureHook
+/// 기물이 잡힐 때(`on_captured`) 또는 잡을 때(`on_capture`) 일어나는 일.
+/// 어느 쪽에 붙어 있든 그 잡기 하나에 대해 같은 뜻입니다.
+/// - `ReturnVictim`: 잡힌 기물이 `plies`수 전에 있던 칸으로 돌아감. 그 칸이 차 있거나 `plies`수 안에 이미 돌아간 적이 있으면 그대로 잡힘 (회귀자)
+/// - `RemoveCaptor`: 잡은 기물도 사라짐 (암행어사)
+/// - `CaptorEffect`: 잡은 기물에 효과를 검 (앵커)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureHook {
+    ReturnVictim { plies: usize },
+    RemoveCaptor,
+    CaptorEffect(EffectSpec),
+}
+
+impl CaptureHook {
+    /// 기물 파일의 훅. `kind`는 `return_victim`, `remove_captor`, `captor_effect`입니다.
+    pub fn from_format(format: &HookFormat) -> Result<Self> {
+        Ok(match format.get_kind().as_str() {
+            "return_victim" => CaptureHook::ReturnVictim { plies: format.get_plies().unwrap_or(1) },
+            "remove_captor" => CaptureHook::RemoveCaptor,
+            "captor_effect" => {
+                let Some(effect) = format.get_effect() else {
+                    bail!("captor_effect hook needs an effect");
+                };
+                let duration = format.get_effect_turns().map_or(EffectDuration::Permanent, EffectDuration::Turns);
+                CaptureHook::CaptorEffect(EffectSpec::new(EffectKind::from_name(effect), duration))
+            }
+            kind => bail!("unknown capture hook {kind:?}"),
+        })
+    }
+}
+
+/// ## HookEvent
+/// 훅이나 변신 때문에 일어난 일. 수와 같이 `Undo`에 남아서 기보에 적힙니다.
+/// `Transformed`의 `owner`는 주인이 바뀌었을 때만 있습니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookEvent<const D: usize> {
+    Returned { from: AbsolutePosition<D>, to: AbsolutePosition<D> },
+    CaptorRemoved(AbsolutePosition<D>),
+    CaptorAffected { position: AbsolutePosition<D>, kind: EffectKind },
+    Transformed { position: AbsolutePosition<D>, into: String, owner: Option<Owner> },
+    MovesAs { position: AbsolutePosition<D>, name: String },
+}
+
+/// 돌아감은 `d5>c3`, 잡은 기물이 사라짐은 `xd5`, 효과는 `d5=anchor`입니다.
+/// 변신은 `d5~queen`이고 플레이어 1의 기물이 되었으면 `d5~queen/1`, 움직임만 바뀌면 `d5:knight`입니다.
+impl<const D: usize> Display for HookEvent<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HookEvent::Returned { from, to } => write!(f, "{from}>{to}"),
+            HookEvent::CaptorRemoved(position) => write!(f, "x{position}"),
+            HookEvent::CaptorAffected { position, kind } => write!(f, "{position}={}", kind.name()),
+            HookEvent::Transformed { position, into, owner } => {
+                write!(f, "{position}~{into}")?;
+                match owner {
+                    Some(owner) => write!(f, "/{owner}"),
+                    None => Ok(()),
+                }
+            }
+            HookEvent::MovesAs { position, name } => write!(f, "{position}:{name}"),
+        }
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// 잡힌 기물에 걸리는 훅. 기물 정의의 `on_captured`에, 암행어사면 `RemoveCaptor`가 더해집니다.
+    fn captured_hooks(&self, piece: &Piece) -> Vec<CaptureHook> {
+        let mut hooks = self.get_rules().get_definition(piece).map(|definition| definition.get_on_captured().to_vec()).unwrap_or_default();
+        if piece.get_state().get(INSPECTOR_STATE).is_some() {
+            hooks.push(CaptureHook::RemoveCaptor);
+        }
+        hooks
+    }
+
+    /// 지금 `position`에 있는 기물이 `plies`수 전에 있던 칸. 기록이 모자라면 처음 위치입니다.
+    /// 그 사이에 보드에 놓였거나 이미 한 번 돌아갔으면 `None`입니다.
+    pub fn square_before(&self, position: &AbsolutePosition<D>, plies: usize) -> Option<AbsolutePosition<D>> {
+        let mut square = *position;
+        for undo in self.history().iter().rev().take(plies) {
+            let mv = undo.get_move();
+            if undo.get_events().iter().any(|event| matches!(event, HookEvent::Returned { to, .. } if *to == square)) {
+                return None;
+            }
+            if mv.is_drop() && *mv.get_to() == square {
+                return None;
+            }
+            if let Some((from, _)) = mv.get_displacements().iter().find(|(_, to)| *to == square) {
+                square = *from;
+            } else if mv.relocates() && *mv.get_to() == square {
+                square = *mv.get_from();
+            }
+        }
+        Some(square)
+    }
+
+    /// 잡힌 기물들의 훅과 잡은 기물(`captor` 칸)의 `on_capture` 훅을 실행하고, 일어난 일을 돌려줍니다.
+    /// 돌아간 기물은 `captured`에서 빠집니다.
+    pub(crate) fn run_capture_hooks(&mut self, captor: &AbsolutePosition<D>, captured: &mut Vec<(AbsolutePosition<D>, Piece)>) -> Vec<HookEvent<D>> {
+        let mut events = Vec::new();
+        let captor_hooks = self.get_board().get(captor)
+            .and_then(|piece| self.get_rules().get_definition(piece))
+            .map(|definition| definition.get_on_capture().to_vec())
+            .unwrap_or_default();
+        let mut index = 0;
+        while index < captured.len() {
+            let (position, piece) = captured[index].clone();
+            let mut returned = false;
+            for hook in self.captured_hooks(&piece).iter().chain(&captor_hooks) {
+                match hook {
+                    CaptureHook::ReturnVictim { plies } => {
+                        let home = self.square_before(&position, *plies).filter(|home| self.get_board().get(home).is_none());
+                        if let Some(home) = home.filter(|_| !returned) {
+                            self.put_piece(home, piece.clone());
+                            events.push(HookEvent::Returned { from: position, to: home });
+                            returned = true;
+                        }
+                    }
+                    CaptureHook::RemoveCaptor => {
+                        if self.take_piece(captor).is_some() {
+                            events.push(HookEvent::CaptorRemoved(*captor));
+                        }
+                    }
+                    CaptureHook::CaptorEffect(spec) => {
+                        if self.get_board().get(captor).is_some() {
+                            self.apply_effect(EffectTarget::Piece(*captor), spec, self.controller(&piece));
+                            events.push(HookEvent::CaptorAffected { position: *captor, kind: spec.kind.clone() });
+                        }
+                    }
+                }
+            }
+            if returned {
+                captured.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{fen::parse_square, presets};
+
+    fn play(game: &mut GameState<2>, text: &str) {
+        let mv = game.legal_moves().into_iter().find(|mv| mv.to_string() == text).unwrap();
+        game.make_move(mv);
+    }
+
+    #[test]
+    fn returner_goes_back_and_slime_anchors() {
+        let mut game = GameState::from_fen("r3k3/8/8/8/8/8/8/T3K3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_hash_check(true);
+        let hash = game.hash();
+        for text in ["a1a3", "e8d8", "a3a5", "d8e8", "a5a6", "a8a6"] {
+            play(&mut game, text);
+        }
+        let returner = game.get_board().get(&parse_square("a1").unwrap()).unwrap();
+        assert_eq!(returner.get_name(), "returner");
+        assert_eq!(game.get_board().get(&parse_square("a6").unwrap()).unwrap().get_name(), "rook");
+        assert_eq!(game.history().last().unwrap().to_string(), "a8a6;a6>a1");
+        assert_eq!(game.square_before(&parse_square("a1").unwrap(), 5), None);
+        for _ in 0..6 {
+            game.unmake_move();
+        }
+        assert_eq!(game.hash(), hash);
+
+        let mut game = GameState::from_fen("4k3/8/8/8/3o4/8/8/3QK3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_hash_check(true);
+        play(&mut game, "d1d4");
+        assert_eq!(game.history().last().unwrap().to_string(), "d1d4;d4=anchor");
+        play(&mut game, "e8e7");
+        assert!(!game.legal_moves().iter().any(|mv| mv.to_string().starts_with("d4")));
+        play(&mut game, "e1e2");
+        play(&mut game, "e7e8");
+        assert!(game.legal_moves().iter().any(|mv| mv.to_string().starts_with("d4")));
+    }
+}
@@ -0,0 +1,36 @@
+pub mod piece;
+pub mod ownership;
+pub mod position;
+pub mod symmetry;
+pub mod orientation;
+pub mod regions;
+pub mod traits;
+pub mod movement;
+pub mod guards;
+pub mod betza;
+pub mod diagram;
+pub mod file_scan;
+pub mod macros;
+pub mod buf;
+pub mod zobrist;
+pub mod effects;
+pub mod hooks;
+pub mod actions;
+pub mod turns;
+pub mod visibility;
+pub mod fog;
+pub mod random;
+pub mod economy;
+pub mod transform;
+pub mod castling;
+pub mod game;
+pub mod moves;
+pub mod rules;
+pub mod movegen;
+pub mod fen;
+pub mod san;
+pub mod presets;
+pub mod perft;
+pub mod tt;
+pub mod search;
+pub mod smp;
@@ -42,6 +42,8 @@ macro_rules! impl_add_sub_mul {
                     (Self { $field_name }, overflow)
                 }
 
+                /// # Safety
+                /// 축마다 `unchecked_add`을 부릅니다. 어느 축이든 덧셈이 넘치면 정의되지 않은 동작입니다.
                 pub const unsafe fn unchecked_add(self, rhs: Self) -> Self {
                     let mut $field_name = [0; D];
                     let mut i = 0;
@@ -53,6 +55,8 @@ macro_rules! impl_add_sub_mul {
                     (Self { $field_name })
                 }
 
+                /// # Safety
+                /// 축마다 `unchecked_sub`을 부릅니다. 어느 축이든 뺄셈이 넘치면 정의되지 않은 동작입니다.
                 pub const unsafe fn unchecked_sub(self, rhs: Self) -> Self {
                     let mut $field_name = [0; D];
                     let mut i = 0;
@@ -64,6 +68,8 @@ macro_rules! impl_add_sub_mul {
                     Self { $field_name }
                 }
 
+                /// # Safety
+                /// 축마다 `unchecked_mul`을 부릅니다. 어느 축이든 곱셈이 넘치면 정의되지 않은 동작입니다.
                 pub const unsafe fn unchecked_mul(self, rhs: Self) -> Self {
                     let mut $field_name = [0; D];
                     let mut i = 0;
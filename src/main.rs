@@ -1,32 +1,14 @@
-use hydrogen_dioxide;
-// mod piece;
-// mod position;
-// mod traits;
-// mod movement;
-// mod file_scan;
-// mod macros;
-// mod buf;
-
-use std::io::{stdin, BufRead, Write};
-use std::fs::File;
-// use crate::piece::{MainCalculate2D, ParsePlayerInput2D};
+use rust_chess::perft;
 
 fn main() {
-    hydrogen_dioxide::main()
-    /*
-    let mut play_board = MainCalculate2D::default();
-    println!("{}", play_board.board);
-    let read = stdin().lock().lines();
-    let mut reader = read.map(|line| line.unwrap());
-    play_board.calculate_move(0);
-    let (_, buffer) = play_board.save_moves.as_can_moves().unwrap().clone();
-    let moves: Vec<_> = buffer.keys().cloned().collect();
-    let mut file = File::create("C:\\Users\\User\\Desktop\\coding\\Rust\\RustChess\\name.txt").unwrap();
-    file.write(format!("{:?}", moves).as_bytes()).expect("TODO: panic message");
-    println!("{:?}", moves);
-    let parse_player_input = ParsePlayerInput2D::new(moves);
-    let input = reader.next().unwrap();
-    let parse_input = parse_player_input.parse_player_input(input);
-    println!("parse input: {:?}", parse_input);
-    */
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("perft") | Some("divide") => {
+            if let Err(error) = perft::run_command(&args) {
+                eprintln!("{error}");
+                std::process::exit(1);
+            }
+        }
+        _ => hydrogen_dioxide::main()
+    }
 }
@@ -0,0 +1,260 @@
+use crate::{
+    effects::{EffectDuration, EffectKind, EffectSpec, EffectTarget},
+    game::GameState,
+    movement::{IndependentMove, MOVE, CAPTURE, INITIAL, PASSABLE, EN_PASSANT, JUMP, LAME, HOP, GRASSHOP, MOVED_STATE, FREEZE, SHIELD, BLIND},
+    moves::Move,
+    piece::{Piece, PlayerId},
+    position::AbsolutePosition,
+    rules::{PieceDefinition, ROYAL},
+};
+
+/// 한 기물의 수를 만드는 동안 바뀌지 않는 값들.
+struct Mover<'a, const D: usize> {
+    from: &'a AbsolutePosition<D>,
+    piece: &'a Piece,
+    definition: &'a PieceDefinition<D>,
+    player: PlayerId,
+}
+
+impl<const D: usize> GameState<D> {
+    /// 차례인 플레이어의 수를 전부 만듭니다. 자기 royal 기물이 공격받게 되는 수도 들어있습니다.
+    pub fn pseudo_legal_moves(&self) -> Vec<Move<D>> {
+        let player = self.side_to_move();
+        let mut moves = Vec::new();
+        if let Some(continuation) = self.get_continuation() {
+            self.continuation_moves(continuation, &mut moves);
+            return moves;
+        }
+        for (position, piece) in self.get_board().pieces() {
+            if self.controls(player, piece) && !self.is_immobilized(position) {
+                self.piece_moves(position, piece, &mut moves);
+            }
+        }
+        self.castling_moves(player, &mut moves);
+        self.drop_moves(player, &mut moves);
+        moves
+    }
+
+    /// 둘 수 있는 수만 돌려줍니다. 수를 둔 뒤 자기 royal 기물이 공격받으면 뺍니다.
+    /// 안개 게임에서는 체크 규칙이 없으므로 `pseudo_legal_moves`와 같습니다.
+    pub fn legal_moves(&mut self) -> Vec<Move<D>> {
+        if self.is_fog_of_war() {
+            return self.pseudo_legal_moves();
+        }
+        self.pseudo_legal_moves()
+            .into_iter()
+            .filter(|mv| self.is_legal(mv))
+            .collect()
+    }
+
+    /// `pseudo_legal_moves`의 수 `mv`를 둘 수 있는지. 몇 수만 확인할 때 `legal_moves`를 다 만들지 않으려고 씁니다.
+    pub fn is_legal(&mut self, mv: &Move<D>) -> bool {
+        if self.is_fog_of_war() {
+            return true;
+        }
+        let player = self.side_to_move();
+        self.make_move(mv.clone());
+        let legal = !self.in_check(player);
+        self.unmake_move();
+        legal
+    }
+
+    /// `player`의 royal 기물 중 하나라도 다른 플레이어에게 공격받고 있는지.
+    pub fn in_check(&self, player: PlayerId) -> bool {
+        let rules = self.get_rules();
+        self.get_board().pieces()
+            .filter(|(_, piece)| self.owns(player, piece))
+            .filter(|(_, piece)| rules.get_definition(piece).is_some_and(|definition| definition.has_attribute(ROYAL)))
+            .any(|(position, _)| self.is_attacked_by_others(position, player))
+    }
+
+    /// `player`가 royal 기물을 전부 잡혔는지. royal 기물이 없는 규칙이면 false.
+    pub fn lost_royal(&self, player: PlayerId) -> bool {
+        let rules = self.get_rules();
+        let is_royal = |piece: &Piece| rules.get_definition(piece).is_some_and(|definition| definition.has_attribute(ROYAL));
+        rules.definitions(player).any(|definition| definition.has_attribute(ROYAL))
+            && !self.get_board().pieces().any(|(_, piece)| self.owns(player, piece) && is_royal(piece))
+    }
+
+    /// `player`와 다른 팀의 플레이어 중 누구라도 `target`을 공격하는지.
+    /// `target`에 `player`의 방패가 있으면 공격받지 않습니다.
+    pub fn is_attacked_by_others(&self, target: &AbsolutePosition<D>, player: PlayerId) -> bool {
+        if self.is_shielded(target, player) {
+            return false;
+        }
+        let rules = self.get_rules();
+        (0..self.player_count())
+            .filter(|&other| rules.get_team(other) != rules.get_team(player))
+            .any(|other| self.is_attacked(target, other))
+    }
+
+    /// `by`가 움직일 수 있는 기물이 `target`에 적 기물이 있다면 잡을 수 있는지.
+    /// 공유 기물은 누구의 기물로도 공격하고, 중립 기물은 공격하지 않습니다.
+    pub fn is_attacked(&self, target: &AbsolutePosition<D>, by: PlayerId) -> bool {
+        self.get_board().pieces()
+            .filter(|(position, piece)| self.controls(by, piece) && !self.is_immobilized(position))
+            .any(|(position, piece)| self.attacks(position, piece, target, by))
+    }
+
+    /// `by`가 움직이는 `from`의 `piece`가 `target`을 공격하는지. 공유 기물의 조건은 차례인 플레이어가 아니라 `by`로 봅니다.
+    fn attacks(&self, from: &AbsolutePosition<D>, piece: &Piece, target: &AbsolutePosition<D>, by: PlayerId) -> bool {
+        let Some(definition) = self.get_rules().movement_definition(piece) else {
+            return false;
+        };
+        let board = self.get_board();
+        let unmoved = piece.get_state().get(MOVED_STATE).is_none();
+        let usable = |step: &IndependentMove<D>| step.has_type(CAPTURE) && (unmoved || !step.has_type(INITIAL)) && self.step_allowed(step, from, piece, by);
+        for leap in definition.get_independent_moves().iter().filter(|leap| usable(leap)) {
+            if board.offset(from, leap.get_offset()).as_ref() == Some(target) && !self.is_lame_blocked(from, leap) {
+                return true;
+            }
+        }
+        for ride in definition.get_dependent_moves().iter().filter(|ride| usable(ride.get_step())) {
+            let hopper = ride.get_step().has_type(HOP) || ride.get_step().has_type(GRASSHOP);
+            let mut hurdle = false;
+            for step in ride.clone() {
+                let Some(position) = board.offset(from, step.get_offset()) else {
+                    break;
+                };
+                let occupied = board.get(&position).is_some();
+                if hopper && !hurdle {
+                    hurdle = occupied;
+                    continue;
+                }
+                if position == *target {
+                    return true;
+                }
+                if step.has_type(GRASSHOP) || (occupied && !step.has_type(JUMP)) {
+                    break;
+                }
+            }
+        }
+        self.action_attacks(from, piece, definition, target)
+    }
+
+    pub(crate) fn piece_moves(&self, from: &AbsolutePosition<D>, piece: &Piece, moves: &mut Vec<Move<D>>) {
+        let Some(definition) = self.get_rules().movement_definition(piece) else {
+            return;
+        };
+        self.action_moves(from, piece, definition, moves);
+        let board = self.get_board();
+        let player = self.controller(piece);
+        let mover = Mover { from, piece, definition, player };
+        let unmoved = piece.get_state().get(MOVED_STATE).is_none();
+        for leap in definition.get_independent_moves() {
+            if (leap.has_type(INITIAL) && !unmoved) || !self.step_allowed(leap, from, piece, player) {
+                continue;
+            }
+            if self.is_lame_blocked(from, leap) {
+                continue;
+            }
+            if let Some(target) = board.offset(from, leap.get_offset()) {
+                let before = moves.len();
+                self.target_moves(&mover, leap, target, None, moves);
+                for mv in &mut moves[before..] {
+                    *mv = self.with_step_effects(mv.clone(), leap, player, &[]);
+                }
+            }
+        }
+        for ride in definition.get_dependent_moves() {
+            if (ride.get_step().has_type(INITIAL) && !unmoved) || !self.step_allowed(ride.get_step(), from, piece, player) {
+                continue;
+            }
+            let hopper = ride.get_step().has_type(HOP) || ride.get_step().has_type(GRASSHOP);
+            let mut hurdle = false;
+            let mut passed = None;
+            let mut path = Vec::new();
+            for (index, step) in ride.clone().enumerate() {
+                let Some(target) = board.offset(from, step.get_offset()) else {
+                    break;
+                };
+                if hopper && !hurdle {
+                    hurdle = board.get(&target).is_some();
+                    path.push(target);
+                    continue;
+                }
+                if index > 0 || !ride.is_first_covered() {
+                    let before = moves.len();
+                    self.target_moves(&mover, &step, target, passed, moves);
+                    for mv in &mut moves[before..] {
+                        *mv = self.with_step_effects(mv.clone(), &step, player, &path);
+                    }
+                }
+                if step.has_type(GRASSHOP) || (board.get(&target).is_some() && !step.has_type(JUMP)) {
+                    break;
+                }
+                passed = Some(target);
+                path.push(target);
+            }
+        }
+    }
+
+    /// `LAME` 이동인데 가는 길의 칸에 기물이 있는지.
+    fn is_lame_blocked(&self, from: &AbsolutePosition<D>, leap: &IndependentMove<D>) -> bool {
+        let board = self.get_board();
+        leap.has_type(LAME) && leap.lame_path().iter().any(|offset| board.offset(from, offset).is_some_and(|position| board.get(&position).is_some()))
+    }
+
+    /// 지나온 칸들(`path`)에 `FREEZE`, `SHIELD` 효과를, 도착한 칸 주위의 적 기물에 `BLIND` 효과를 겁니다.
+    /// 전부 한 턴 동안 남습니다.
+    fn with_step_effects(&self, mut mv: Move<D>, step: &IndependentMove<D>, player: PlayerId, path: &[AbsolutePosition<D>]) -> Move<D> {
+        if step.has_type(BLIND) {
+            for position in self.get_board().neighbours(mv.get_to()) {
+                if self.get_board().get(&position).is_some_and(|other| self.is_hostile(player, other)) {
+                    mv = mv.with_effect(EffectTarget::Piece(position), EffectSpec::new(EffectKind::Blind, EffectDuration::Turns(1)));
+                }
+            }
+        }
+        for position in path {
+            if step.has_type(FREEZE) && self.get_board().get(position).is_some_and(|other| self.is_hostile(player, other)) {
+                mv = mv.with_effect(EffectTarget::Piece(*position), EffectSpec::new(EffectKind::Freeze, EffectDuration::Turns(1)));
+            }
+            if step.has_type(SHIELD) {
+                mv = mv.with_effect(EffectTarget::Square(*position), EffectSpec::new(EffectKind::Shield, EffectDuration::Turns(1)));
+            }
+        }
+        mv
+    }
+
+    /// 한 칸에 대해 `step`으로 할 수 있는 수를 추가합니다.
+    /// `passed`는 같은 방향으로 오면서 바로 앞에 지나온 칸입니다.
+    fn target_moves(&self, mover: &Mover<D>, step: &IndependentMove<D>, target: AbsolutePosition<D>, passed: Option<AbsolutePosition<D>>, moves: &mut Vec<Move<D>>) {
+        let (from, player) = (mover.from, mover.player);
+        let mv = match self.get_board().get(&target) {
+            None if step.has_type(MOVE) => {
+                let mv = Move::new(*from, target, MOVE);
+                match passed {
+                    Some(passed) if step.has_type(PASSABLE) => mv.with_en_passant(passed),
+                    _ => mv
+                }
+            }
+            None if step.has_type(EN_PASSANT) => match self.get_en_passant() {
+                Some((en_passant, victim)) if *en_passant == target && self.get_board().get(victim).is_some_and(|other| self.can_capture(player, victim, other)) => {
+                    Move::new(*from, target, CAPTURE).with_capture(*victim)
+                }
+                _ => return
+            },
+            Some(other) if step.has_type(CAPTURE) && self.can_capture(player, &target, other) => {
+                Move::new(*from, target, CAPTURE)
+            }
+            _ => return
+        };
+        self.push_with_promotions(mover, mv, moves);
+    }
+
+    /// 프로모션 칸이면 고를 수 있는 기물마다 수를 하나씩 만듭니다.
+    fn push_with_promotions(&self, mover: &Mover<D>, mv: Move<D>, moves: &mut Vec<Move<D>>) {
+        if !self.promotes_at(mover.definition, mover.player, mv.get_to()) {
+            moves.push(mv);
+            return;
+        }
+        let promotions = mover.definition.get_promotions().iter()
+            .filter_map(|name| self.get_rules().create_piece(mover.player, name))
+            .map(|mut promotion| {
+                promotion.set_owner(mover.piece.get_owner());
+                mv.clone().with_promotion(promotion)
+            });
+        moves.extend(promotions);
+    }
+}
+
@@ -1,25 +1,180 @@
 use std::collections::{HashMap, HashSet};
-use std::path::Iter;
-use crate::position::RelativePosition;
+use crate::{guards::Guard, orientation::Orientation, position::RelativePosition};
 
-type MovementType = String;
-type StateType = String;
+pub type MovementType = String;
+pub type StateType = String;
+
+/// 한 번이라도 움직인 기물에 붙는 상태.
+pub const MOVED_STATE: &str = "moved";
+/// 프로모션한 기물에 붙는 상태. 값은 프로모션하기 전 기물 이름입니다. 잡히면 이 기물로 손에 들어갑니다.
+pub const PROMOTED_STATE: &str = "promoted";
+
+/// 빈 칸으로 이동.
+pub const MOVE: &str = "move";
+/// 적 기물이 있는 칸으로 이동하면서 잡기.
+pub const CAPTURE: &str = "capture";
+/// 한 번도 움직이지 않은 기물만 쓸 수 있음. (폰 두 칸 전진)
+pub const INITIAL: &str = "initial";
+/// 지나간 칸이 앙파상 칸이 됨.
+pub const PASSABLE: &str = "passable";
+/// 앙파상 칸으로 잡을 수 있음.
+pub const EN_PASSANT: &str = "en_passant";
+/// 가는 길에 있는 기물을 뛰어넘음.
+pub const JUMP: &str = "jump";
+/// 한 번에 가는 이동이지만 가는 길의 칸이 막히면 못 감. (샹치의 마)
+pub const LAME: &str = "lame";
+/// 같은 방향의 기물 하나를 뛰어넘은 뒤의 칸에만 감. (캐논)
+pub const HOP: &str = "hop";
+/// 같은 방향의 기물 하나를 뛰어넘은 바로 다음 칸에만 감. (그래스호퍼)
+pub const GRASSHOP: &str = "grasshop";
+/// 캐슬링. 기물 정의에는 쓰지 않고 수의 종류로만 씁니다.
+pub const CASTLING: &str = "castling";
+/// 자리를 옮기지 않고 닿는 칸 하나의 기물을 맞힘. (스나이퍼)
+pub const SHOOT: &str = "shoot";
+/// 자리를 옮기지 않고 닿는 칸 전부에 한 번에 씀. (번개)
+pub const AREA: &str = "area";
+/// 자리를 옮기지 않고 떨어진 칸의 기물을 낚아챔. (매)
+pub const CATCH: &str = "catch";
+/// 자리를 옮기지 않고 옆의 아군 기물이 이어서 한 번 더 두게 함. (바드)
+pub const GRANT: &str = "grant";
+/// 다른 기물에 업힘. (매) 아직 수를 만들지 않고, 이동 그림에서만 읽습니다.
+pub const RIDE: &str = "ride";
+/// 옆의 기물을 그 방향으로 밀어냄. (선풍기) 밀린 기물은 수의 `displacements`에 적습니다. `RIDE`처럼 아직 수를 만들지 않고, 기보에서만 읽습니다.
+pub const PUSH: &str = "push";
+/// 이어 두는 중에 차례를 끝냄. 기물 정의에는 쓰지 않고 수의 종류로만 씁니다.
+pub const PASS: &str = "pass";
+/// 뛰어넘은 적 기물을 한 턴 동안 얼림. (스펙터)
+pub const FREEZE: &str = "freeze";
+/// 지나간 칸에 한 턴 동안 방패를 남김. (포르티스)
+pub const SHIELD: &str = "shield";
+/// 도착한 칸 주위 8칸의 적 기물이 한 턴 동안 아무것도 보지 못함. (위스퍼)
+pub const BLIND: &str = "blind";
+/// 보드 밖의 기물을 빈 칸에 놓음. `PASS`처럼 수의 종류로만 씁니다.
+pub const DROP: &str = "drop";
 
 /// ## IndependentMove
-/// IndependentMove
-struct IndependentMove<const D: usize> {
+/// 현재 위치에서 `offest`만큼 떨어진 칸으로 한 번에 가는 이동. (나이트, 킹)
+/// `movement_type`에 들어있는 종류(`MOVE`, `CAPTURE` 등)로만 갈 수 있고, `guard`가 있으면 그 조건이 참일 때만 갈 수 있습니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndependentMove<const D: usize> {
     movement_type: HashSet<MovementType>,
-    offest: RelativePosition<D>
+    offest: RelativePosition<D>,
+    guard: Option<Guard>,
+}
+
+impl<const D: usize> IndependentMove<D> {
+    pub fn new<T: Into<MovementType>>(movement_type: impl IntoIterator<Item = T>, offest: RelativePosition<D>) -> Self {
+        Self { movement_type: movement_type.into_iter().map(Into::into).collect(), offest, guard: None }
+    }
+
+    pub fn with_guard(mut self, guard: Guard) -> Self {
+        self.guard = Some(guard);
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_guard(&self) -> Option<&Guard> {
+        self.guard.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_movement_type(&self) -> &HashSet<MovementType> {
+        &self.movement_type
+    }
+
+    #[inline(always)]
+    pub fn has_type(&self, movement_type: &str) -> bool {
+        self.movement_type.contains(movement_type)
+    }
+
+    #[inline(always)]
+    pub fn get_offset(&self) -> &RelativePosition<D> {
+        &self.offest
+    }
+
+    /// 오프셋을 곧게 나눈 중간 칸들. `LAME` 이동은 이 칸들이 비어 있어야 갑니다.
+    /// 축마다 `offset * k / n`을 0 쪽으로 버린 값이라 나이트 (2, 1)는 (1, 0)을 지납니다.
+    pub fn lame_path(&self) -> Vec<RelativePosition<D>> {
+        let length = self.offest.iter().map(|value| value.unsigned_abs()).max().unwrap_or(0) as isize;
+        (1..length).map(|k| RelativePosition::from(std::array::from_fn(|axis| self.offest[axis] * k / length))).collect()
+    }
+
+    /// `ride`가 첫 칸에서 만드는 수를 이 이동이 똑같이 만드는지. 첫 칸에는 지나온 칸이 없으므로 `PASSABLE`, `JUMP`, `FREEZE`, `SHIELD`는 상관없고,
+    /// 이 이동이 `INITIAL`이면 `ride`도 `INITIAL`이어야 합니다. 뛰어넘는 이동(`HOP`, `GRASSHOP`)은 첫 칸에서 수를 만들지 않습니다.
+    pub fn covers_first_step(&self, ride: &DependentMove<D>) -> bool {
+        fn kinds<const D: usize>(leap: &IndependentMove<D>) -> HashSet<&str> {
+            leap.movement_type.iter().map(String::as_str).filter(|kind| ![INITIAL, PASSABLE, JUMP, FREEZE, SHIELD].contains(kind)).collect()
+        }
+        let step = ride.get_step();
+        ride.max_times > 0 && self.offest == ride.start_offest + step.offest && self.guard == step.guard
+            && !step.has_type(HOP) && !step.has_type(GRASSHOP)
+            && (!self.has_type(INITIAL) || step.has_type(INITIAL))
+            && kinds(self) == kinds(step)
+    }
+
+    /// 플레이어 쪽에서 적은 이동을 보드 방향으로 바꿉니다.
+    pub fn oriented(mut self, orientation: &Orientation<D>) -> Self {
+        self.offest = orientation.orient(&self.offest);
+        self
+    }
 }
 
 /// ## DependentMove
-/// DependentMove
-struct DependentMove<const D: usize> {
+/// 같은 방향으로 여러 번 이어서 가는 이동. (룩, 비숍)
+/// n번째 칸은 `start_offest + offest * n`이고, 앞 칸이 막히면 거기서 멈춥니다.
+/// `Iterator`로 쓰면 칸마다 `IndependentMove`를 하나씩 돌려줍니다.
+/// `first_covered`면 첫 칸의 수는 다른 `IndependentMove`가 이미 만드므로, 첫 칸은 막혔는지만 봅니다. (폰의 두 칸 전진)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependentMove<const D: usize> {
     movement_type: IndependentMove<D>,
     start_offest: RelativePosition<D>,
     state: State,
     max_times: usize,
-    times: usize
+    times: usize,
+    first_covered: bool,
+}
+
+impl<const D: usize> DependentMove<D> {
+    pub fn new(movement_type: IndependentMove<D>, max_times: usize) -> Self {
+        Self { movement_type, start_offest: RelativePosition::from([0; D]), state: State::default(), max_times, times: 0, first_covered: false }
+    }
+
+    /// 이 이동 전체에 조건을 붙입니다. 조건은 칸마다가 아니라 이동을 시작할 때 한 번 봅니다.
+    pub fn with_guard(mut self, guard: Guard) -> Self {
+        self.movement_type.guard = Some(guard);
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_step(&self) -> &IndependentMove<D> {
+        &self.movement_type
+    }
+
+    #[inline(always)]
+    pub fn get_start_offset(&self) -> &RelativePosition<D> {
+        &self.start_offest
+    }
+
+    #[inline(always)]
+    pub fn max_times(&self) -> usize {
+        self.max_times
+    }
+
+    #[inline(always)]
+    pub fn is_first_covered(&self) -> bool {
+        self.first_covered
+    }
+
+    pub fn set_first_covered(&mut self, first_covered: bool) {
+        self.first_covered = first_covered;
+    }
+
+    /// 플레이어 쪽에서 적은 이동을 보드 방향으로 바꿉니다.
+    pub fn oriented(mut self, orientation: &Orientation<D>) -> Self {
+        self.movement_type = self.movement_type.oriented(orientation);
+        self.start_offest = orientation.orient(&self.start_offest);
+        self
+    }
 }
 
 impl<const D: usize> Iterator for DependentMove<D> {
@@ -29,18 +184,42 @@ impl<const D: usize> Iterator for DependentMove<D> {
         if self.times >= self.max_times {
             return None;
         }
+        let start = self.start_offest + self.movement_type.offest * RelativePosition::from([self.times as isize; D]);
         self.times += 1;
-        self.state.state_define(&self.start_offest, &self.movement_type)
+        self.state.state_define(&start, &self.movement_type)
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct State {
     states: HashMap<StateType, String>,
     states_code: HashMap<StateType, String>,
 }
 
 impl State {
+    #[inline(always)]
+    pub fn get(&self, state: &str) -> Option<&String> {
+        self.states.get(state)
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, state: impl Into<StateType>, value: impl Into<String>) -> Option<String> {
+        self.states.insert(state.into(), value.into())
+    }
+
+    #[inline(always)]
+    pub fn remove(&mut self, state: &str) -> Option<String> {
+        self.states.remove(state)
+    }
+
+    /// 상태 값들만 순회합니다. `states_code`는 값이 아니므로 포함하지 않습니다.
+    #[inline(always)]
+    pub fn iter(&self) -> impl Iterator<Item = (&StateType, &String)> {
+        self.states.iter()
+    }
+
+    /// `start_move`에서 `delta_move`만큼 한 칸 더 간 이동을 만듭니다. 조건은 `DependentMove`에서 이미 봤으므로 넘기지 않습니다.
     pub fn state_define<const D: usize>(&self, start_move: &RelativePosition<D>, delta_move: &IndependentMove<D>) -> Option<IndependentMove<D>> {
-        None
+        Some(IndependentMove { movement_type: delta_move.movement_type.clone(), offest: *start_move + delta_move.offest, guard: None })
     }
 }
@@ -0,0 +1,270 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+use crate::{
+    effects::{EffectSpec, EffectTarget},
+    movement::{MovementType, CAPTURE, DROP, PASS},
+    piece::Piece,
+    position::AbsolutePosition,
+};
+
+/// ## Move
+/// 한 수. `from`의 기물을 `to`로 옮기고, 부가 효과들을 같이 적어둡니다.
+/// - `captures`: `to` 말고도 비워지는 칸 (앙파상, 뛰어넘으며 잡기 등)
+/// - `displacements`: 같이 옮겨지는 다른 기물 (push, 캐슬링 상대 등). 적힌 순서대로 옮깁니다.
+/// - `promotion`: 도착 후 바뀔 기물
+/// - `en_passant`: 이 수로 생기는 앙파상 칸
+/// - `effects`: 이 수를 둔 플레이어가 거는 효과
+/// - `action`: 자리를 옮기지 않는 액션이면 그 표기 (`Ls`의 `s`). 이때 `to`는 `from`과 같습니다.
+/// - `targets`: 액션이 닿은 칸들. 없을 수도 있습니다.
+/// - `grant`: 이 수 다음에 같은 차례로 한 번 더 둘 기물의 칸 (바드)
+/// - `roll`: 이 수가 일으키는 무작위 결과를 미리 정해둔 값. 없으면 `make_move`가 게임의 난수로 뽑습니다.
+/// - `drop`: 보드 밖에서 `to`에 놓는 기물. 이때 `from`은 `to`와 같습니다.
+/// - `price`: `drop`을 코인으로 사서 놓으면 그 가격. 0이면 손에 든 기물을 놓습니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move<const D: usize> {
+    from: AbsolutePosition<D>,
+    to: AbsolutePosition<D>,
+    movement_type: MovementType,
+    captures: Vec<AbsolutePosition<D>>,
+    displacements: Vec<(AbsolutePosition<D>, AbsolutePosition<D>)>,
+    promotion: Option<Piece>,
+    en_passant: Option<AbsolutePosition<D>>,
+    effects: Vec<(EffectTarget<D>, EffectSpec)>,
+    action: Option<String>,
+    targets: Vec<AbsolutePosition<D>>,
+    grant: Option<AbsolutePosition<D>>,
+    roll: Option<usize>,
+    drop: Option<Piece>,
+    price: i64,
+}
+
+impl<const D: usize> Move<D> {
+    pub fn new(from: AbsolutePosition<D>, to: AbsolutePosition<D>, movement_type: impl Into<MovementType>) -> Self {
+        Self {
+            from,
+            to,
+            movement_type: movement_type.into(),
+            captures: Vec::new(),
+            displacements: Vec::new(),
+            promotion: None,
+            en_passant: None,
+            effects: Vec::new(),
+            action: None,
+            targets: Vec::new(),
+            grant: None,
+            roll: None,
+            drop: None,
+            price: 0,
+        }
+    }
+
+    /// 이어 두는 중에 차례를 끝내는 수. `at`은 이어 두던 기물의 칸입니다.
+    pub fn pass(at: AbsolutePosition<D>) -> Self {
+        Self::new(at, at, PASS)
+    }
+
+    /// 손에 든 기물을 `at`에 놓는 수.
+    pub fn drop(piece: Piece, at: AbsolutePosition<D>) -> Self {
+        let mut mv = Self::new(at, at, DROP);
+        mv.drop = Some(piece);
+        mv
+    }
+
+    /// 자리를 옮기지 않는 액션.
+    pub fn action(from: AbsolutePosition<D>, movement_type: impl Into<MovementType>, notation: impl Into<String>) -> Self {
+        let mut mv = Self::new(from, from, movement_type);
+        mv.action = Some(notation.into());
+        mv
+    }
+
+    pub fn with_capture(mut self, position: AbsolutePosition<D>) -> Self {
+        self.captures.push(position);
+        self
+    }
+
+    pub fn with_displacement(mut self, from: AbsolutePosition<D>, to: AbsolutePosition<D>) -> Self {
+        self.displacements.push((from, to));
+        self
+    }
+
+    pub fn with_promotion(mut self, piece: Piece) -> Self {
+        self.promotion = Some(piece);
+        self
+    }
+
+    pub fn with_en_passant(mut self, position: AbsolutePosition<D>) -> Self {
+        self.en_passant = Some(position);
+        self
+    }
+
+    pub fn with_effect(mut self, target: EffectTarget<D>, spec: EffectSpec) -> Self {
+        self.effects.push((target, spec));
+        self
+    }
+
+    pub fn with_target(mut self, position: AbsolutePosition<D>) -> Self {
+        self.targets.push(position);
+        self
+    }
+
+    pub fn with_grant(mut self, position: AbsolutePosition<D>) -> Self {
+        self.grant = Some(position);
+        self
+    }
+
+    /// 놓는 기물을 손에서 꺼내지 않고 `price` 코인에 삽니다.
+    pub fn with_price(mut self, price: i64) -> Self {
+        self.price = price;
+        self
+    }
+
+    /// 무작위 결과를 `roll`번째로 정합니다. 탐색이 결과마다 따로 둘 때 씁니다. (`GameState::chance_outcomes`)
+    pub fn with_roll(mut self, roll: usize) -> Self {
+        self.roll = Some(roll);
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_from(&self) -> &AbsolutePosition<D> {
+        &self.from
+    }
+
+    #[inline(always)]
+    pub fn get_to(&self) -> &AbsolutePosition<D> {
+        &self.to
+    }
+
+    #[inline(always)]
+    pub fn get_movement_type(&self) -> &MovementType {
+        &self.movement_type
+    }
+
+    #[inline(always)]
+    pub fn get_captures(&self) -> &[AbsolutePosition<D>] {
+        &self.captures
+    }
+
+    #[inline(always)]
+    pub fn get_displacements(&self) -> &[(AbsolutePosition<D>, AbsolutePosition<D>)] {
+        &self.displacements
+    }
+
+    #[inline(always)]
+    pub fn get_promotion(&self) -> Option<&Piece> {
+        self.promotion.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_en_passant(&self) -> Option<&AbsolutePosition<D>> {
+        self.en_passant.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_effects(&self) -> &[(EffectTarget<D>, EffectSpec)] {
+        &self.effects
+    }
+
+    #[inline(always)]
+    pub fn get_action(&self) -> Option<&String> {
+        self.action.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_targets(&self) -> &[AbsolutePosition<D>] {
+        &self.targets
+    }
+
+    #[inline(always)]
+    pub fn get_grant(&self) -> Option<&AbsolutePosition<D>> {
+        self.grant.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_drop(&self) -> Option<&Piece> {
+        self.drop.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_price(&self) -> i64 {
+        self.price
+    }
+
+    #[inline(always)]
+    pub fn get_roll(&self) -> Option<usize> {
+        self.roll
+    }
+
+    #[inline(always)]
+    pub fn is_action(&self) -> bool {
+        self.action.is_some()
+    }
+
+    #[inline(always)]
+    pub fn is_pass(&self) -> bool {
+        self.movement_type == PASS
+    }
+
+    #[inline(always)]
+    pub fn is_drop(&self) -> bool {
+        self.drop.is_some()
+    }
+
+    /// `from`의 기물을 옮기는 수인지. 액션, 드롭, `PASS`는 옮기지 않습니다.
+    #[inline(always)]
+    pub fn relocates(&self) -> bool {
+        !self.is_action() && !self.is_drop() && !self.is_pass()
+    }
+
+    /// 기물을 잡는 수인지.
+    pub fn is_capture(&self) -> bool {
+        self.movement_type == CAPTURE || !self.captures.is_empty()
+    }
+
+    /// 수를 구분하는 짧은 값. 같은 국면의 수들 사이에서만 구분되면 되므로 트랜스포지션 테이블에 수 대신 넣습니다.
+    /// `roll`은 플레이어가 고르는 것이 아니므로 넣지 않습니다.
+    pub fn fingerprint(&self) -> u32 {
+        let mut hasher = DefaultHasher::new();
+        self.from.hash(&mut hasher);
+        self.to.hash(&mut hasher);
+        self.movement_type.hash(&mut hasher);
+        self.captures.hash(&mut hasher);
+        self.displacements.hash(&mut hasher);
+        self.effects.iter().for_each(|(target, spec)| (target, &spec.kind).hash(&mut hasher));
+        self.promotion.as_ref().map(|piece| piece.get_name()).hash(&mut hasher);
+        self.action.hash(&mut hasher);
+        self.targets.hash(&mut hasher);
+        self.grant.hash(&mut hasher);
+        self.drop.as_ref().map(|piece| piece.get_name()).hash(&mut hasher);
+        self.price.hash(&mut hasher);
+        hasher.finish() as u32
+    }
+}
+
+/// 좌표 표기 (`e2e4`, `e7e8q`). 액션은 기물 칸, 액션 표기, 닿은 칸 순서입니다. (`e2s`, `a1sd4`)
+/// 여러 수로 된 차례는 수마다 따로 적고, 차례를 끝내는 수는 `pass`입니다.
+/// 드롭은 기물 표기와 놓는 칸 사이에 `@`, 사서 놓으면 `$`를 씁니다. (`N@e4`, `Q$d5`)
+impl<const D: usize> Display for Move<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_pass() {
+            return write!(f, "{PASS}");
+        }
+        if let Some(piece) = &self.drop {
+            let mark = if self.price > 0 { '$' } else { '@' };
+            return write!(f, "{}{}{}", piece.get_short_name().to_uppercase(), mark, self.to);
+        }
+        if let Some(action) = &self.action {
+            write!(f, "{}{}", self.from, action)?;
+            if self.targets.len() == 1 {
+                write!(f, "{}", self.targets[0])?;
+            }
+            return Ok(());
+        }
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(promotion) = &self.promotion {
+            write!(f, "{}", promotion.get_short_name().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{
+    piece::PlayerId,
+    position::{AbsolutePosition, Board, RelativePosition},
+};
+
+/// ## Orientation
+/// 플레이어가 보드를 보는 방향. 기물 정의는 자기 쪽에서 본 방향으로 적고(0번 축의 +가 앞),
+/// `Ruleset::define`이 이 방향으로 바꿔서 보드 좌표로 만듭니다.
+/// - `axes`: 플레이어의 `i`번 축이 보드의 `axes[i]`번 축
+/// - `flips`: 플레이어의 `i`번 축이 보드에서는 거꾸로인지
+///
+/// 따로 정하지 않으면 홀수 번째 플레이어는 0번 축(랭크)이 뒤집힌, 마주 앉은 방향입니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Orientation<const D: usize> {
+    axes: [usize; D],
+    flips: [bool; D],
+}
+
+impl<const D: usize> Orientation<D> {
+    pub fn identity() -> Self {
+        Self { axes: std::array::from_fn(|axis| axis), flips: [false; D] }
+    }
+
+    /// `player`의 기본 방향.
+    pub fn default_for(player: PlayerId) -> Self {
+        let orientation = Self::identity();
+        if player % 2 == 1 && D > 0 { orientation.with_flip(0) } else { orientation }
+    }
+
+    /// 0번과 1번 축 평면에서 90도씩 `turns`번 돌린 방향. 4인용 보드에서 네 변에 앉은 플레이어입니다.
+    pub fn quarter_turns(turns: usize) -> Self {
+        (0..turns % 4).fold(Self::identity(), |orientation, _| orientation.with_quarter_turn(0, 1))
+    }
+
+    /// 플레이어의 `axis`번 축을 뒤집습니다.
+    pub fn with_flip(mut self, axis: usize) -> Self {
+        self.flips[axis] = !self.flips[axis];
+        self
+    }
+
+    /// 플레이어의 두 축을 맞바꿉니다.
+    pub fn with_swap(mut self, first: usize, second: usize) -> Self {
+        self.axes.swap(first, second);
+        self.flips.swap(first, second);
+        self
+    }
+
+    /// 플레이어의 앞(0번 축)이 보드의 `axis`번 축이 되게 합니다. (3차원 보드에서 층을 따라 나아가는 기물)
+    pub fn with_forward_axis(self, axis: usize) -> Self {
+        self.with_swap(0, axis)
+    }
+
+    /// 보드의 `from`번 축 + 방향이 `to`번 축 + 방향으로 가게 90도 돌립니다. `to`번 축 + 방향은 `from`번 축 - 방향이 됩니다.
+    pub fn with_quarter_turn(mut self, from: usize, to: usize) -> Self {
+        for axis in 0..D {
+            if self.axes[axis] == from {
+                self.axes[axis] = to;
+            } else if self.axes[axis] == to {
+                self.axes[axis] = from;
+                self.flips[axis] = !self.flips[axis];
+            }
+        }
+        self
+    }
+
+    /// 되돌리는 방향. `inverse().orient(orient(offset))`는 `offset`입니다.
+    pub fn inverse(&self) -> Self {
+        let mut inverse = Self::identity();
+        for axis in 0..D {
+            inverse.axes[self.axes[axis]] = axis;
+            inverse.flips[self.axes[axis]] = self.flips[axis];
+        }
+        inverse
+    }
+
+    #[inline(always)]
+    pub fn is_flipped(&self, axis: usize) -> bool {
+        self.flips[axis]
+    }
+
+    /// 플레이어의 `axis`번 축이 보드의 몇 번 축인지.
+    #[inline(always)]
+    pub fn board_axis(&self, axis: usize) -> usize {
+        self.axes[axis]
+    }
+
+    /// 플레이어 쪽에서 적은 `offset`을 보드 방향으로 바꿉니다.
+    pub fn orient(&self, offset: &RelativePosition<D>) -> RelativePosition<D> {
+        let mut oriented = [0; D];
+        for axis in 0..D {
+            oriented[self.axes[axis]] = if self.flips[axis] { -offset[axis] } else { offset[axis] };
+        }
+        RelativePosition::from(oriented)
+    }
+
+    /// `position`을 이 방향에서 본 좌표. 플레이어의 축 순서로, 축마다 자기 쪽 끝에서 0부터 셉니다.
+    pub fn view(&self, position: &AbsolutePosition<D>, board: &Board<D>) -> [usize; D] {
+        let (min, max) = (board.get_min_position(), board.get_max_position());
+        std::array::from_fn(|axis| {
+            let board_axis = self.axes[axis];
+            if self.flips[axis] { max[board_axis] - position[board_axis] } else { position[board_axis] - min[board_axis] }
+        })
+    }
+}
+
+/// 축마다 칸 수.
+pub fn board_size<const D: usize>(board: &Board<D>) -> [usize; D] {
+    let (min, max) = (board.get_min_position(), board.get_max_position());
+    std::array::from_fn(|axis| max[axis] - min[axis] + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{presets, zobrist::Zobrist};
+
+    #[test]
+    fn orientations_turn_offsets_and_views() {
+        let forward = RelativePosition::from([1, 0]);
+        assert_eq!(Orientation::default_for(1).orient(&forward), RelativePosition::from([-1, 0]));
+        let seats: Vec<_> = (0..4).map(|seat| Orientation::<2>::quarter_turns(seat).orient(&forward)).collect();
+        assert_eq!(seats, [[1, 0], [0, 1], [-1, 0], [0, -1]].map(RelativePosition::from));
+        let left = Orientation::<2>::quarter_turns(1);
+        assert_eq!(left.orient(&RelativePosition::from([0, 1])), RelativePosition::from([-1, 0]));
+        assert_eq!(left.inverse().orient(&left.orient(&RelativePosition::from([2, 1]))), RelativePosition::from([2, 1]));
+
+        let board = presets::standard_board();
+        let corner = AbsolutePosition::from([0, 7]);
+        assert_eq!(left.view(&corner, &board), [7, 7]);
+        assert_eq!(Orientation::default_for(1).view(&corner, &board), [7, 7]);
+
+        let pawn_step = |rules: &crate::rules::Ruleset<2>, player| *rules.get(player, "pawn").unwrap().get_independent_moves()[0].get_offset();
+        let rules = presets::standard_chess();
+        assert_eq!(pawn_step(&rules, 1), RelativePosition::from([-1, 0]));
+        let rules = rules.with_orientation(1, left);
+        assert_eq!((pawn_step(&rules, 0), pawn_step(&rules, 1)), (forward, RelativePosition::from([0, 1])));
+
+        let upward = Orientation::<3>::identity().with_forward_axis(2);
+        assert_eq!(upward.orient(&RelativePosition::from([1, 0, 0])), RelativePosition::from([0, 0, 1]));
+        let cube = Board::new(AbsolutePosition::from([0, 0, 0]), AbsolutePosition::from([3, 3, 3]), Zobrist::default());
+        assert_eq!(upward.view(&AbsolutePosition::from([1, 2, 3]), &cube), [3, 2, 1]);
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt::{self, Display, Formatter};
+use crate::{
+    game::GameState,
+    piece::{Piece, PlayerId},
+    position::AbsolutePosition,
+};
+
+/// ## TeamId
+/// 팀 번호. 따로 정하지 않으면 플레이어마다 자기 번호의 팀입니다. (`Ruleset::with_teams`)
+pub type TeamId = usize;
+
+/// ## Owner
+/// 기물의 주인.
+/// - `Player`: 그 플레이어가 움직이고, 다른 팀 플레이어가 잡습니다.
+/// - `Team`: 그 팀의 플레이어 누구나 움직이고, 다른 팀 플레이어가 잡습니다.
+/// - `Shared`: 차례인 플레이어가 움직이고, 누구나 잡습니다. 누구의 차례에나 움직일 수 있으므로 모두에게 체크를 겁니다. (뉴트레이터)
+/// - `Neutral`: 아무도 움직이지 않고, 누구나 잡습니다. 체크를 걸지 않습니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Owner {
+    Player(PlayerId),
+    Team(TeamId),
+    Shared,
+    Neutral,
+}
+
+impl Owner {
+    /// 한 플레이어의 기물이면 그 플레이어.
+    #[inline(always)]
+    pub fn player(&self) -> Option<PlayerId> {
+        match self {
+            Owner::Player(player) => Some(*player),
+            _ => None
+        }
+    }
+
+    /// Zobrist 키에 넣는 값. 플레이어 기물은 플레이어 번호 그대로입니다.
+    pub fn key(&self) -> u64 {
+        match self {
+            Owner::Player(player) => *player as u64,
+            Owner::Team(team) => 1 << 32 | *team as u64,
+            Owner::Shared => u64::MAX - 1,
+            Owner::Neutral => u64::MAX,
+        }
+    }
+}
+
+/// 수 기록에 쓰는 표기. 플레이어는 번호, 팀은 `t` 뒤에 팀 번호, 나머지는 `shared`, `neutral`입니다.
+impl Display for Owner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Owner::Player(player) => write!(f, "{player}"),
+            Owner::Team(team) => write!(f, "t{team}"),
+            Owner::Shared => write!(f, "shared"),
+            Owner::Neutral => write!(f, "neutral"),
+        }
+    }
+}
+
+impl From<PlayerId> for Owner {
+    fn from(player: PlayerId) -> Self {
+        Owner::Player(player)
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// `piece`가 `player` 자신이나 그 팀의 기물인지. royal 기물은 이 플레이어들의 것만 체크를 받습니다.
+    #[inline(always)]
+    pub fn owns(&self, player: PlayerId, piece: &Piece) -> bool {
+        self.get_rules().owns(player, piece.get_owner())
+    }
+
+    /// `player`가 `piece`를 움직일 수 있는지.
+    #[inline(always)]
+    pub fn controls(&self, player: PlayerId, piece: &Piece) -> bool {
+        self.get_rules().controls(player, piece.get_owner())
+    }
+
+    /// `player`가 `piece`를 잡을 수 있는 편인지.
+    #[inline(always)]
+    pub fn is_hostile(&self, player: PlayerId, piece: &Piece) -> bool {
+        self.get_rules().is_hostile(player, piece.get_owner())
+    }
+
+    /// `piece`를 지금 움직이는 플레이어. 한 플레이어의 기물이 아니면 차례인 플레이어입니다.
+    /// 차례가 아닌 플레이어의 공격을 볼 때(`is_attacked`)는 이 값 대신 공격하는 플레이어를 씁니다.
+    #[inline(always)]
+    pub fn controller(&self, piece: &Piece) -> PlayerId {
+        piece.get_owner().player().unwrap_or(self.side_to_move())
+    }
+
+    /// `player`가 `position`의 `victim`을 잡을 수 있는지. 편이 다르고 `victim` 주인의 방패가 없어야 합니다.
+    pub fn can_capture(&self, player: PlayerId, position: &AbsolutePosition<D>, victim: &Piece) -> bool {
+        self.is_hostile(player, victim) && victim.get_owner().player().is_none_or(|owner| !self.is_shielded(position, owner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{fen::parse_square, presets, regions::Region, rules::PieceDefinition};
+
+    #[test]
+    fn shared_and_neutral_pieces() {
+        let mut game = GameState::from_fen("4k3/8/8/8/4*Z3/8/8/4K?P2 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_hash_check(true);
+        let neutrator = parse_square("e4").unwrap();
+        let pawn = parse_square("f1").unwrap();
+        assert_eq!(game.get_board().get(&neutrator).unwrap().get_owner(), Owner::Shared);
+        assert_eq!(game.get_board().get(&pawn).unwrap().get_owner(), Owner::Neutral);
+        assert!(game.in_check(0) && game.in_check(1));
+
+        let moves: Vec<_> = game.legal_moves().iter().map(ToString::to_string).collect();
+        assert!(!moves.iter().any(|mv| mv.starts_with("f1")));
+        assert!(moves.contains(&"e1f1".to_string()));
+        assert!(moves.contains(&"e4d4".to_string()));
+        assert!(!moves.contains(&"e4e5".to_string()) && !moves.contains(&"e4e8".to_string()));
+
+        let mv = game.legal_moves().into_iter().find(|mv| mv.to_string() == "e4d4").unwrap();
+        game.make_move(mv);
+        let mv = game.legal_moves().into_iter().find(|mv| mv.to_string() == "d4d1").unwrap();
+        game.make_move(mv);
+        assert!(game.in_check(0));
+    }
+
+    #[test]
+    fn team_royals_and_shared_attackers() {
+        let square = |name| parse_square(name).unwrap();
+        let mut game = GameState::from_fen("4k3/8/8/4r3/8/8/8/8 w - - 0 1", presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        let mut king = game.get_rules().create_piece(0, "king").unwrap();
+        king.set_owner(Owner::Team(0));
+        game.put_piece(square("e1"), king);
+        assert!(game.in_check(0) && !game.lost_royal(0));
+
+        let mut rules = presets::standard_chess().with_region("home", Region::Layers { axis: 0, first: 0, last: 3 });
+        for player in 0..2 {
+            rules.define(player, PieceDefinition::new("warden", "W", 5).with_betza("R").unwrap().with_guard("region(home)".parse().unwrap()));
+        }
+        let mut game = GameState::from_fen("7k/8/8/8/8/8/8/K7 w - - 0 1", presets::standard_board(), Arc::new(rules)).unwrap();
+        let mut warden = game.get_rules().create_piece(0, "warden").unwrap();
+        warden.set_owner(Owner::Shared);
+        game.put_piece(square("e4"), warden);
+        assert!(game.is_attacked(&square("e8"), 0));
+        assert!(!game.is_attacked(&square("e1"), 1));
+        game.set_side_to_move(1);
+        assert!(!game.is_attacked(&square("e1"), 1));
+    }
+}
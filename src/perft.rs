@@ -0,0 +1,150 @@
+use std::{sync::Arc, time::Instant};
+use anyhow::{anyhow, bail, Result};
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use crate::{
+    game::GameState,
+    moves::Move,
+    presets,
+};
+
+/// 깊이 `depth`까지 둘 수 있는 수를 전부 두어 보고 끝 국면의 수를 셉니다.
+pub fn perft<const D: usize>(game: &mut GameState<D>, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = game.legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for mv in moves {
+        game.make_move(mv);
+        nodes += perft(game, depth - 1);
+        game.unmake_move();
+    }
+    nodes
+}
+
+/// 첫 수마다 perft를 따로 셉니다. 첫 수들은 rayon으로 나눠서 동시에 셉니다.
+pub fn divide<const D: usize>(game: &GameState<D>, depth: usize) -> Vec<(Move<D>, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let moves = game.clone().legal_moves();
+    moves.into_par_iter()
+        .map(|mv| {
+            let mut game = game.clone();
+            game.make_move(mv.clone());
+            let nodes = perft(&mut game, depth - 1);
+            (mv, nodes)
+        })
+        .collect()
+}
+
+/// `perft <depth> [--fairy] [fen]`, `divide <depth> [--fairy] [fen]`
+pub fn run_command(args: &[String]) -> Result<()> {
+    let [command, depth, rest @ ..] = args else {
+        bail!("usage: {} <depth> [--fairy] [fen]", args.first().map(String::as_str).unwrap_or("perft"));
+    };
+    let depth: usize = depth.parse().map_err(|_| anyhow!("depth must be a number: {depth}"))?;
+    let (fairy, fen) = match rest {
+        [flag, fen @ ..] if flag == "--fairy" => (true, fen.join(" ")),
+        fen => (false, fen.join(" ")),
+    };
+    let rules = if fairy { presets::fairy_chess() } else { presets::standard_chess() };
+    let fen = if fen.is_empty() { presets::START_FEN.to_string() } else { fen };
+    let game = GameState::from_fen(&fen, presets::standard_board(), Arc::new(rules))?;
+
+    let start = Instant::now();
+    let nodes = if command == "divide" {
+        let mut divided = divide(&game, depth);
+        divided.sort_by_key(|(mv, _)| mv.to_string());
+        for (mv, nodes) in &divided {
+            println!("{mv}: {nodes}");
+        }
+        divided.iter().map(|(_, nodes)| nodes).sum()
+    } else if depth == 0 {
+        1
+    } else {
+        divide(&game, depth).iter().map(|(_, nodes)| nodes).sum()
+    };
+    println!("nodes: {nodes} ({:.2?})", start.elapsed());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+
+    fn game(fen: &str, fairy: bool) -> GameState<2> {
+        let rules = if fairy { presets::fairy_chess() } else { presets::standard_chess() };
+        let mut game = GameState::from_fen(fen, presets::standard_board(), Arc::new(rules)).unwrap();
+        game.set_hash_check(true);
+        game
+    }
+
+    fn assert_perft(fen: &str, fairy: bool, expected: &[u64]) {
+        let mut game = game(fen, fairy);
+        let hash = game.hash();
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(perft(&mut game, depth + 1), nodes, "{fen} depth {}", depth + 1);
+        }
+        assert_eq!(game.hash(), hash);
+    }
+
+    #[test]
+    fn initial_position() {
+        assert_perft(presets::START_FEN, false, &[20, 400, 8902]);
+    }
+
+    /// 디버그 빌드에서 1분 넘게 걸리므로 `cargo test -- --ignored`로 따로 돌립니다.
+    #[test]
+    #[ignore]
+    fn initial_position_depth_4() {
+        assert_perft(presets::START_FEN, false, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(KIWIPETE, false, &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(POSITION_3, false, &[14, 191, 2812]);
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(POSITION_4, false, &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(POSITION_5, false, &[44, 1486, 62379]);
+    }
+
+    #[test]
+    fn divide_matches_perft() {
+        let mut game = game(KIWIPETE, false);
+        let divided: u64 = divide(&game, 2).iter().map(|(_, nodes)| nodes).sum();
+        assert_eq!(divided, perft(&mut game, 2));
+    }
+
+    // 아래 변형 국면의 값은 이 엔진이 직접 센 값이고 다른 엔진과 맞춰 본 적이 없습니다.
+    // 정답이 아니라 회귀 검사용이므로, 규칙을 고쳐서 값이 바뀌면 손으로 확인한 뒤 갱신하세요.
+    #[test]
+    fn drone_setup() {
+        assert_perft(presets::DRONE_FEN, true, &[38, 1186, 42738]);
+    }
+
+    #[test]
+    fn returner_setup() {
+        assert_perft(presets::RETURNER_FEN, true, &[28, 784, 24892]);
+    }
+}
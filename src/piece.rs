@@ -1,10 +1,111 @@
-use crate::hydrogen_dioxide;
-use crate::movement::State;
+use std::collections::BTreeMap;
+use crate::{
+    effects::Effect,
+    movement::State,
+    ownership::Owner,
+    visibility::Identity,
+};
 
+/// ## PlayerId
+/// 플레이어 번호. 0번이 백, 1번이 흑입니다.
+pub type PlayerId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Piece {
     piece_name: String,
     piece_short_name: String,
     piece_score: i32,
+    piece_owner: Owner,
     piece_state: State,
+    piece_effects: Vec<Effect>,
+    piece_appearances: BTreeMap<PlayerId, Identity>,
     // piece_code: Interpreter,
-}
\ No newline at end of file
+}
+
+impl Piece {
+    pub fn new(piece_name: impl Into<String>, piece_short_name: impl Into<String>, piece_score: i32, piece_owner: impl Into<Owner>) -> Self {
+        Self {
+            piece_name: piece_name.into(),
+            piece_short_name: piece_short_name.into(),
+            piece_score,
+            piece_owner: piece_owner.into(),
+            piece_state: State::default(),
+            piece_effects: Vec::new(),
+            piece_appearances: BTreeMap::new()
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_name(&self) -> &String {
+        &self.piece_name
+    }
+
+    #[inline(always)]
+    pub fn get_short_name(&self) -> &String {
+        &self.piece_short_name
+    }
+
+    #[inline(always)]
+    pub fn piece_score(&self) -> i32 {
+        self.piece_score
+    }
+
+    #[inline(always)]
+    pub fn get_owner(&self) -> Owner {
+        self.piece_owner
+    }
+
+    #[inline(always)]
+    pub fn set_owner(&mut self, owner: impl Into<Owner>) {
+        self.piece_owner = owner.into();
+    }
+
+    #[inline(always)]
+    pub fn get_state(&self) -> &State {
+        &self.piece_state
+    }
+
+    #[inline(always)]
+    pub fn get_state_mut(&mut self) -> &mut State {
+        &mut self.piece_state
+    }
+
+    #[inline(always)]
+    pub fn get_effects(&self) -> &[Effect] {
+        &self.piece_effects
+    }
+
+    #[inline(always)]
+    pub fn get_effects_mut(&mut self) -> &mut Vec<Effect> {
+        &mut self.piece_effects
+    }
+
+    /// `player`에게 보이는 정체. 따로 정하지 않았으면 진짜 정체입니다.
+    pub fn get_appearance(&self, player: PlayerId) -> Identity {
+        self.piece_appearances.get(&player).cloned().unwrap_or_else(|| Identity::of_piece(self))
+    }
+
+    #[inline(always)]
+    pub fn set_appearance(&mut self, player: PlayerId, identity: Identity) {
+        if identity == Identity::of_piece(self) {
+            self.piece_appearances.remove(&player);
+        } else {
+            self.piece_appearances.insert(player, identity);
+        }
+    }
+
+    #[inline(always)]
+    pub fn reveal_to(&mut self, player: PlayerId) {
+        self.piece_appearances.remove(&player);
+    }
+
+    #[inline(always)]
+    pub fn is_disguised_to(&self, player: PlayerId) -> bool {
+        self.piece_appearances.contains_key(&player)
+    }
+
+    #[inline(always)]
+    pub fn appearances(&self) -> impl Iterator<Item = (&PlayerId, &Identity)> {
+        self.piece_appearances.iter()
+    }
+}
@@ -1,10 +1,14 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
 use crate::{
     traits::Dimension,
     piece::Piece,
+    zobrist::Zobrist,
     impl_add_sub_mul,
     impl_ops_add_sub_mul_assign,
-    impl_from_try_from,
+    impl_convert_from_try_from,
     impl_ops_refs,
     impl_try_from_iterator
 };
@@ -67,6 +71,16 @@ impl<const D: usize> AbsolutePosition<D> {
 
 impl<const D: usize> Dimension<D> for AbsolutePosition<D> {}
 
+/// 2차원이면 `e4`처럼, 아니면 `(3, 4, 0)`처럼 출력합니다.
+impl<const D: usize> Display for AbsolutePosition<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.position.as_slice() {
+            &[rank, file] if file < 26 => write!(f, "{}{}", (b'a' + file as u8) as char, rank + 1),
+            position => write!(f, "({})", position.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
 impl<const D: usize> RelativePosition<D> {
     pub const fn to_absolute(&self, base: &AbsolutePosition<D>) -> Option<AbsolutePosition<D>> {
         base.add_absolute(self)
@@ -81,8 +95,131 @@ impl<const D: usize> Dimension<D> for RelativePosition<D> {}
 
 /// ## Board
 /// Board는 와샌즈합니다(?)
-struct Board<const D: usize> {
+/// min_position부터 max_position까지(양 끝 포함)가 보드 안입니다.
+/// `hash`는 보드 위 기물들의 Zobrist 키로, 기물을 놓거나 뺄 때마다 갱신됩니다.
+#[derive(Debug, Clone)]
+pub struct Board<const D: usize> {
     board: HashMap<AbsolutePosition<D>, Piece>,
     min_position: AbsolutePosition<D>,
-    max_position: AbsolutePosition<D>
+    max_position: AbsolutePosition<D>,
+    zobrist: Zobrist,
+    hash: u64
+}
+
+impl<const D: usize> Board<D> {
+    pub fn new(min_position: AbsolutePosition<D>, max_position: AbsolutePosition<D>, zobrist: Zobrist) -> Self {
+        Self { board: HashMap::new(), min_position, max_position, zobrist, hash: 0 }
+    }
+
+    #[inline(always)]
+    pub fn get_min_position(&self) -> &AbsolutePosition<D> {
+        &self.min_position
+    }
+
+    #[inline(always)]
+    pub fn get_max_position(&self) -> &AbsolutePosition<D> {
+        &self.max_position
+    }
+
+    #[inline(always)]
+    pub fn get_zobrist(&self) -> &Zobrist {
+        &self.zobrist
+    }
+
+    #[inline(always)]
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn contains(&self, position: &AbsolutePosition<D>) -> bool {
+        (0..D).all(|i| self.min_position[i] <= position[i] && position[i] <= self.max_position[i])
+    }
+
+    /// 보드의 모든 칸. 마지막 축이 가장 빨리 바뀌는 순서입니다.
+    pub fn squares(&self) -> impl Iterator<Item = AbsolutePosition<D>> + '_ {
+        let mut next = Some(self.min_position);
+        std::iter::from_fn(move || {
+            let current = next?;
+            let mut position = current;
+            next = (0..D).rev().find_map(|axis| {
+                if position[axis] < self.max_position[axis] {
+                    position[axis] += 1;
+                    Some(position)
+                } else {
+                    position[axis] = self.min_position[axis];
+                    None
+                }
+            });
+            Some(current)
+        })
+    }
+
+    /// `center`를 둘러싼 칸들. (2차원이면 8칸)
+    pub fn neighbours(&self, center: &AbsolutePosition<D>) -> Vec<AbsolutePosition<D>> {
+        (0..3usize.pow(D as u32))
+            .filter_map(|code| {
+                let mut offset = [0isize; D];
+                let mut rest = code;
+                for axis in offset.iter_mut() {
+                    *axis = (rest % 3) as isize - 1;
+                    rest /= 3;
+                }
+                if offset == [0; D] {
+                    return None;
+                }
+                self.offset(center, &RelativePosition::from(offset))
+            })
+            .collect()
+    }
+
+    pub fn empty_squares(&self) -> impl Iterator<Item = AbsolutePosition<D>> + '_ {
+        self.squares().filter(|position| self.get(position).is_none())
+    }
+
+    /// 보드 안이면 base + offset을 돌려줍니다.
+    pub fn offset(&self, base: &AbsolutePosition<D>, offset: &RelativePosition<D>) -> Option<AbsolutePosition<D>> {
+        offset.to_absolute(base).filter(|position| self.contains(position))
+    }
+
+    #[inline(always)]
+    pub fn get(&self, position: &AbsolutePosition<D>) -> Option<&Piece> {
+        self.board.get(position)
+    }
+
+    #[inline(always)]
+    pub fn pieces(&self) -> impl Iterator<Item = (&AbsolutePosition<D>, &Piece)> {
+        self.board.iter()
+    }
+
+    /// 기물을 놓고 원래 있던 기물을 돌려줍니다.
+    pub fn set_piece(&mut self, position: AbsolutePosition<D>, piece: Piece) -> Option<Piece> {
+        self.hash ^= self.zobrist.piece(&piece, &position);
+        let old = self.board.insert(position, piece);
+        if let Some(old) = &old {
+            self.hash ^= self.zobrist.piece(old, &position);
+        }
+        old
+    }
+
+    pub fn remove_piece(&mut self, position: &AbsolutePosition<D>) -> Option<Piece> {
+        let old = self.board.remove(position);
+        if let Some(old) = &old {
+            self.hash ^= self.zobrist.piece(old, position);
+        }
+        old
+    }
+
+    /// 기물의 상태를 바꿀 때는 이 함수를 거쳐야 키가 같이 갱신됩니다.
+    pub fn update_piece<R>(&mut self, position: &AbsolutePosition<D>, f: impl FnOnce(&mut Piece) -> R) -> Option<R> {
+        let piece = self.board.get_mut(position)?;
+        self.hash ^= self.zobrist.piece(piece, position);
+        let result = f(piece);
+        self.hash ^= self.zobrist.piece(piece, position);
+        Some(result)
+    }
+
+    /// 키를 처음부터 다시 계산합니다.
+    pub fn compute_hash(&self) -> u64 {
+        self.board.iter().fold(0, |acc, (position, piece)| acc ^ self.zobrist.piece(piece, position))
+    }
 }
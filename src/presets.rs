@@ -0,0 +1,314 @@
+use crate::{
+    actions::ActionDefinition,
+    castling::Castling,
+    economy::{DropRestriction, Economy, EARNS},
+    effects::{EffectDuration, EffectKind, EffectSpec},
+    guards::Guard,
+    hooks::CaptureHook,
+    movement::{DependentMove, IndependentMove, MOVE, CAPTURE, INITIAL, PASSABLE, EN_PASSANT, JUMP, BLIND, SHOOT, AREA, CATCH, GRANT},
+    position::{AbsolutePosition, Board, RelativePosition},
+    rules::{PieceDefinition, Ruleset, ROYAL, CASTLE},
+    transform::{TransformRule, TransformTrigger, Transformation},
+    turns::{ContinuationCondition, ContinuationRule},
+    visibility::MIMIC,
+    zobrist::Zobrist,
+};
+
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// 나이트 자리에 드론을 둔 시작 국면.
+pub const DRONE_FEN: &str = "rdbqkbdr/pppppppp/8/8/8/8/PPPPPPPP/RDBQKBDR w KQkq - 0 1";
+/// 비숍 자리에 회귀자, 폰 줄 가운데에 졸을 둔 시작 국면.
+pub const RETURNER_FEN: &str = "rntqktnr/ppjppjpp/8/8/8/8/PPJPPJPP/RNTQKTNR w KQkq - 0 1";
+
+const ORTHOGONAL: [[isize; 2]; 4] = [[1, 0], [-1, 0], [0, 1], [0, -1]];
+const DIAGONAL: [[isize; 2]; 4] = [[1, 1], [1, -1], [-1, 1], [-1, -1]];
+const KNIGHT: [[isize; 2]; 8] = [[1, 2], [2, 1], [2, -1], [1, -2], [-1, -2], [-2, -1], [-2, 1], [-1, 2]];
+
+const MOVE_CAPTURE: [&str; 2] = [MOVE, CAPTURE];
+
+pub fn standard_board() -> Board<2> {
+    Board::new(AbsolutePosition::from([0, 0]), AbsolutePosition::from([7, 7]), Zobrist::default())
+}
+
+/// 표준 체스 기물. 기물은 전부 플레이어 0 쪽에서 본 방향으로 적고, `Ruleset::define`이 플레이어마다 돌려 놓습니다.
+pub fn standard_chess() -> Ruleset<2> {
+    let mut rules = Ruleset::new(2);
+    for player in 0..2 {
+        rules.define(player, king());
+        rules.define(player, queen());
+        rules.define(player, rook());
+        rules.define(player, bishop());
+        rules.define(player, knight());
+        rules.define(player, pawn());
+    }
+    rules
+}
+
+/// 체스960. 킹과 룩이 어디서 시작하든 캐슬링하면 표준 체스와 같은 파일에 섭니다.
+pub fn chess960() -> Ruleset<2> {
+    standard_chess().with_castling(Castling::chess960())
+}
+
+/// 장거리 캐슬링 규칙의 표준 체스. 킹과 같은 랭크나 파일의 룩과, 둘 다 움직였어도 캐슬링합니다.
+pub fn long_castling_chess() -> Ruleset<2> {
+    standard_chess().with_castling(Castling::long_range())
+}
+
+/// 표준 체스에 `buf.rs`의 기물 중 움직임만으로 정의되는 기물들을 더한 규칙.
+pub fn fairy_chess() -> Ruleset<2> {
+    let mut rules = standard_chess();
+    for player in 0..2 {
+        rules.define(player, drone());
+        rules.define(player, returner());
+        rules.define(player, jol());
+        rules.define(player, sniper());
+        rules.define(player, archer());
+        rules.define(player, hawk());
+        rules.define(player, lightning());
+        rules.define(player, rabbit());
+        rules.define(player, night_runaway());
+        rules.define(player, bard());
+        rules.define(player, fraud());
+        rules.define(player, voice_phishing());
+        rules.define(player, whisper());
+        rules.define(player, gambler());
+        rules.define(player, gimcy());
+        rules.define(player, neutrator());
+        rules.define(player, slime());
+        rules.define(player, turtle());
+        rules.define(player, traffic_light());
+    }
+    rules.with_economy(Economy::default().with_purchases().with_restriction(DropRestriction::NotOnPromotionRank))
+}
+
+/// 킹 대신 폭군을 쓰는 표준 체스. 폭군의 글자는 킹과 같은 `K`입니다.
+pub fn tyrant_chess() -> Ruleset<2> {
+    let mut rules = Ruleset::new(2);
+    for player in 0..2 {
+        rules.define(player, tyrant());
+        rules.define(player, queen());
+        rules.define(player, rook());
+        rules.define(player, bishop());
+        rules.define(player, knight());
+        rules.define(player, pawn());
+    }
+    rules
+}
+
+/// 반란군 규칙: 폰이 킹으로도 프로모션할 수 있는 표준 체스.
+pub fn rebel_chess() -> Ruleset<2> {
+    standard_chess().with_promotion_rule("pawn", vec![7], &["king", "queen", "rook", "bishop", "knight"])
+}
+
+/// 폰 대신 크리미널을 쓰는 표준 체스. 크리미널의 글자는 폰과 같은 `P`입니다.
+pub fn criminal_chess() -> Ruleset<2> {
+    let mut rules = Ruleset::new(2);
+    for player in 0..2 {
+        rules.define(player, king());
+        rules.define(player, queen());
+        rules.define(player, rook());
+        rules.define(player, bishop());
+        rules.define(player, knight());
+        rules.define(player, criminal());
+    }
+    rules
+}
+
+/// 잡은 기물을 손에 들었다가 빈 칸에 놓을 수 있는 크레이지하우스. 폰은 첫 랭크와 끝 랭크에 놓지 못합니다.
+pub fn crazyhouse() -> Ruleset<2> {
+    let pawn_ranks = DropRestriction::NotOnRanks { name: "pawn".to_string(), ranks: vec![0, 7] };
+    standard_chess().with_economy(Economy::default().with_captures_to_hand().with_restriction(pawn_ranks))
+}
+
+fn leaps<const N: usize>(definition: PieceDefinition<2>, movement_type: &[&str], offsets: [[isize; 2]; N]) -> PieceDefinition<2> {
+    offsets.into_iter().fold(definition, |definition, offset| {
+        definition.with_independent_move(IndependentMove::new(movement_type.iter().copied(), RelativePosition::from(offset)))
+    })
+}
+
+fn rides<const N: usize>(definition: PieceDefinition<2>, movement_type: &[&str], offsets: [[isize; 2]; N]) -> PieceDefinition<2> {
+    offsets.into_iter().fold(definition, |definition, offset| {
+        let step = IndependentMove::new(movement_type.iter().copied(), RelativePosition::from(offset));
+        definition.with_dependent_move(DependentMove::new(step, usize::MAX))
+    })
+}
+
+pub fn king() -> PieceDefinition<2> {
+    let king = PieceDefinition::new("king", "K", 0).with_attribute(ROYAL).with_attribute(CASTLE);
+    leaps(leaps(king, &MOVE_CAPTURE, ORTHOGONAL), &MOVE_CAPTURE, DIAGONAL)
+}
+
+pub fn queen() -> PieceDefinition<2> {
+    rides(rides(PieceDefinition::new("queen", "Q", 9), &MOVE_CAPTURE, ORTHOGONAL), &MOVE_CAPTURE, DIAGONAL)
+}
+
+pub fn rook() -> PieceDefinition<2> {
+    rides(PieceDefinition::new("rook", "R", 5), &MOVE_CAPTURE, ORTHOGONAL)
+}
+
+pub fn bishop() -> PieceDefinition<2> {
+    rides(PieceDefinition::new("bishop", "B", 3), &MOVE_CAPTURE, DIAGONAL)
+}
+
+pub fn knight() -> PieceDefinition<2> {
+    leaps(PieceDefinition::new("knight", "N", 3), &MOVE_CAPTURE, KNIGHT)
+}
+
+pub fn pawn() -> PieceDefinition<2> {
+    let pawn = PieceDefinition::new("pawn", "P", 1)
+        .with_promotion(7, ["queen", "rook", "bishop", "knight"]);
+    let pawn = leaps(pawn, &[MOVE], [[1, 0]]);
+    let pawn = leaps(pawn, &[CAPTURE, EN_PASSANT], [[1, 1], [1, -1]]);
+    let double_step = IndependentMove::new([MOVE, INITIAL, PASSABLE], RelativePosition::from([1, 0]));
+    pawn.with_dependent_move(DependentMove::new(double_step, 2))
+}
+
+/// 드론: 퀸처럼 움직이지만 기물을 뛰어넘습니다.
+pub fn drone() -> PieceDefinition<2> {
+    let movement_type = [MOVE, CAPTURE, JUMP];
+    rides(rides(PieceDefinition::new("drone", "D", 11), &movement_type, ORTHOGONAL), &movement_type, DIAGONAL)
+}
+
+/// 회귀자: 8방향 2칸 이내 + 나이트, 전부 뛰어넘음. 잡히면 5수 전에 있던 칸으로 돌아갑니다.
+pub fn returner() -> PieceDefinition<2> {
+    let movement_type = [MOVE, CAPTURE, JUMP];
+    let returner = PieceDefinition::new("returner", "T", 7).with_on_captured(CaptureHook::ReturnVictim { plies: 5 });
+    let returner = leaps(returner, &movement_type, ORTHOGONAL);
+    let returner = leaps(returner, &movement_type, DIAGONAL);
+    let returner = leaps(returner, &movement_type, ORTHOGONAL.map(|[rank, file]| [rank * 2, file * 2]));
+    let returner = leaps(returner, &movement_type, DIAGONAL.map(|[rank, file]| [rank * 2, file * 2]));
+    leaps(returner, &movement_type, KNIGHT)
+}
+
+/// 졸: 앞, 왼쪽, 오른쪽으로 한 칸 take-move.
+pub fn jol() -> PieceDefinition<2> {
+    leaps(PieceDefinition::new("jol", "J", 1), &MOVE_CAPTURE, [[1, 0], [0, 1], [0, -1]])
+}
+
+/// 스나이퍼: 비숍처럼 움직이고, 게임당 한 번 직선상의 적 하나를 제자리에서 잡습니다.
+pub fn sniper() -> PieceDefinition<2> {
+    let shoot = ActionDefinition::new("snipe", "s", SHOOT)
+        .with_rides(ORTHOGONAL.into_iter().chain(DIAGONAL).map(RelativePosition::from))
+        .capturing()
+        .with_limit(1);
+    rides(PieceDefinition::new("sniper", "S", 4), &MOVE_CAPTURE, DIAGONAL).with_action(shoot)
+}
+
+/// 아처: 주위 3x3으로 이동만 하고, 주위 5x5의 적은 제자리에서 쏴서 잡습니다.
+pub fn archer() -> PieceDefinition<2> {
+    let area = (-2..=2).flat_map(|rank| (-2..=2).map(move |file| [rank, file])).filter(|&offset| offset != [0, 0]);
+    let shoot = ActionDefinition::new("shoot", "a", SHOOT).with_leaps(area.map(RelativePosition::from)).capturing();
+    leaps(leaps(PieceDefinition::new("archer", "A", 2), &[MOVE], ORTHOGONAL), &[MOVE], DIAGONAL).with_action(shoot)
+}
+
+/// 매: 대각선 4방향을 뺀 2칸 떨어진 칸의 적을 catch합니다. 스스로 움직이지는 못합니다.
+pub fn hawk() -> PieceDefinition<2> {
+    let ring = (-2..=2isize).flat_map(|rank| (-2..=2isize).map(move |file| [rank, file]))
+        .filter(|[rank, file]| rank.abs().max(file.abs()) == 2 && rank.abs() != file.abs());
+    let catch = ActionDefinition::new("catch", "c", CATCH).with_leaps(ring.map(RelativePosition::from)).capturing();
+    PieceDefinition::new("hawk", "H", 3).with_action(catch)
+}
+
+/// 번개: 대각선으로 move만 하거나, 아군을 포함한 주위 8칸을 한 턴 동안 기절시킵니다. (`Ls`)
+pub fn lightning() -> PieceDefinition<2> {
+    let stun = ActionDefinition::new("stun", "s", AREA)
+        .with_leaps(ORTHOGONAL.into_iter().chain(DIAGONAL).map(RelativePosition::from))
+        .with_effect(EffectSpec::new(EffectKind::Stun, EffectDuration::Turns(1)));
+    rides(PieceDefinition::new("lightning", "L", 3), &[MOVE], DIAGONAL).with_action(stun)
+}
+
+/// 토끼: 나이트처럼 움직이고, 한 차례에 두 번 움직입니다.
+pub fn rabbit() -> PieceDefinition<2> {
+    leaps(PieceDefinition::new("rabbit", "Y", 4), &MOVE_CAPTURE, KNIGHT)
+        .with_continuation(ContinuationRule::new(ContinuationCondition::Always, 2))
+}
+
+/// 나이트 런어웨이: 나이트처럼 움직이고, 잡으면 한 번 더 움직입니다. 한 차례에 최대 8번.
+pub fn night_runaway() -> PieceDefinition<2> {
+    leaps(PieceDefinition::new("night runaway", "U", 4), &MOVE_CAPTURE, KNIGHT)
+        .with_continuation(ContinuationRule::new(ContinuationCondition::AfterCapture, 8))
+}
+
+/// 바드: 킹처럼 move만 하고, 옆의 아군 하나가 같은 차례에 한 번 더 움직이게 합니다. 적은 잡지 못합니다.
+pub fn bard() -> PieceDefinition<2> {
+    let grant = ActionDefinition::new("inspire", "g", GRANT).with_leaps(ORTHOGONAL.into_iter().chain(DIAGONAL).map(RelativePosition::from));
+    leaps(leaps(PieceDefinition::new("bard", "V", 2), &[MOVE], ORTHOGONAL), &[MOVE], DIAGONAL).with_action(grant)
+}
+
+/// 사기꾼: 퀸처럼 움직이지만 상대에게는 킹으로 보입니다.
+pub fn fraud() -> PieceDefinition<2> {
+    rides(rides(PieceDefinition::new("fraud", "W", 9), &MOVE_CAPTURE, ORTHOGONAL), &MOVE_CAPTURE, DIAGONAL).with_disguise("king")
+}
+
+/// 보이스피싱: 폰처럼 움직이고, 기물을 잡으면 상대에게는 잡은 기물로 보입니다.
+pub fn voice_phishing() -> PieceDefinition<2> {
+    let phishing = PieceDefinition::new("voice phishing", "M", 1).with_attribute(MIMIC);
+    let phishing = leaps(phishing, &[MOVE], [[1, 0]]);
+    leaps(phishing, &[CAPTURE], [[1, 1], [1, -1]])
+}
+
+/// 위스퍼: 나이트처럼 움직이고, 도착한 칸 주위 8칸의 적 기물이 다음 턴 동안 아무것도 보지 못합니다.
+pub fn whisper() -> PieceDefinition<2> {
+    leaps(PieceDefinition::new("whisper", "E", 3), &[MOVE, CAPTURE, BLIND], KNIGHT)
+}
+
+/// 갬블러: 수를 둘 때마다 다음 수에 나이트, 비숍, 룩 중 무엇처럼 움직일지 무작위로 정해집니다.
+pub fn gambler() -> PieceDefinition<2> {
+    PieceDefinition::new("gambler", "G", 4).with_gambles(["knight", "bishop", "rook"])
+}
+
+/// 김씨: 킹처럼 움직이고, 잡을 때마다 코인을 받습니다. 코인으로는 기물을 사서 빈 칸에 놓습니다.
+pub fn gimcy() -> PieceDefinition<2> {
+    let gimcy = PieceDefinition::new("gimcy", "C", 3).with_attribute(EARNS);
+    leaps(leaps(gimcy, &MOVE_CAPTURE, ORTHOGONAL), &MOVE_CAPTURE, DIAGONAL)
+}
+
+/// 뉴트레이터: 아마존(퀸 + 나이트)처럼 움직이는 공유 기물. 백 차례에는 백이, 흑 차례에는 흑이 움직입니다.
+/// 주인은 기물을 놓을 때 정합니다. (FEN의 `*Z`)
+pub fn neutrator() -> PieceDefinition<2> {
+    let neutrator = PieceDefinition::new("neutrator", "Z", 12);
+    let neutrator = rides(rides(neutrator, &MOVE_CAPTURE, ORTHOGONAL), &MOVE_CAPTURE, DIAGONAL);
+    leaps(neutrator, &MOVE_CAPTURE, KNIGHT)
+}
+
+/// 슬라임: 8방향으로 move만 하고, 자신을 잡은 기물을 한 턴 동안 묶어둡니다. (anchor)
+pub fn slime() -> PieceDefinition<2> {
+    let slime = PieceDefinition::new("slime", "O", 1)
+        .with_on_captured(CaptureHook::CaptorEffect(EffectSpec::new(EffectKind::Anchor, EffectDuration::Turns(1))));
+    leaps(leaps(slime, &[MOVE], ORTHOGONAL), &[MOVE], DIAGONAL)
+}
+
+/// 폭군: 킹 대신 쓰는 royal 기물. 퀸 + 나이트처럼 움직이지만 자기 퀸이 살아 있을 때만 움직입니다.
+pub fn tyrant() -> PieceDefinition<2> {
+    let tyrant = PieceDefinition::new("tyrant", "K", 0).with_attribute(ROYAL).with_attribute(CASTLE);
+    let tyrant = rides(rides(tyrant, &MOVE_CAPTURE, ORTHOGONAL), &MOVE_CAPTURE, DIAGONAL);
+    let tyrant = leaps(tyrant, &MOVE_CAPTURE, KNIGHT);
+    tyrant.with_guard("count(queen) > 0".parse().expect("valid guard"))
+}
+
+/// 거북이: 킹처럼 움직이지만 홀수 번째 턴에만 움직입니다.
+pub fn turtle() -> PieceDefinition<2> {
+    let turtle = leaps(leaps(PieceDefinition::new("turtle", "I", 2), &MOVE_CAPTURE, ORTHOGONAL), &MOVE_CAPTURE, DIAGONAL);
+    turtle.with_guard("turn % 2 == 1".parse().expect("valid guard"))
+}
+
+/// 신호등: 가로로 룩처럼 move&take하고, 초록불일 때만 세로로 한 칸 move합니다.
+/// 첫 턴에 초록불이고 턴마다 초록, 노랑, 빨강 순서로 바뀝니다. 노란불과 빨간불의 barrier는 아직 없습니다.
+pub fn traffic_light() -> PieceDefinition<2> {
+    let green = "turn % 3 == 1".parse::<Guard>().expect("valid guard");
+    let traffic_light = rides(PieceDefinition::new("traffic light", "X", 4), &MOVE_CAPTURE, [[0, 1], [0, -1]]);
+    [[1, 0], [-1, 0]].into_iter().fold(traffic_light, |definition, offset| {
+        definition.with_independent_move(IndependentMove::new([MOVE], RelativePosition::from(offset)).with_guard(green.clone()))
+    })
+}
+
+/// 크리미널: 폰처럼 앞으로 가지만 적을 잡지 못하고, 프로모션하면 상대의 기물이 됩니다.
+pub fn criminal() -> PieceDefinition<2> {
+    let criminal = PieceDefinition::new("criminal", "P", 1)
+        .with_promotion(7, ["queen", "rook", "bishop", "knight"])
+        .with_transform(TransformRule::new(TransformTrigger::Enter(vec![7]), Transformation::Defect));
+    let criminal = leaps(criminal, &[MOVE], [[1, 0]]);
+    let double_step = IndependentMove::new([MOVE, INITIAL, PASSABLE], RelativePosition::from([1, 0]));
+    criminal.with_dependent_move(DependentMove::new(double_step, 2))
+}
@@ -0,0 +1,198 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use crate::{
+    effects::{EffectDuration, EffectKind, EffectSpec, EffectTarget},
+    game::{Change, GameState},
+    moves::Move,
+    piece::PlayerId,
+    position::AbsolutePosition,
+    zobrist::splitmix64,
+};
+
+/// 갬블러가 다음 수에 흉내 낼 기물의 이름.
+pub const GAMBLE_STATE: &str = "gamble";
+
+/// ## GameRng
+/// 게임이 들고 있는 seed 있는 난수 생성기 (splitmix64).
+/// 규칙과 스크립트의 무작위는 전부 `GameState`를 거쳐 이 생성기에서 뽑으므로,
+/// 같은 상태에서 같은 순서로 뽑으면 항상 같은 값이 나옵니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GameRng {
+    seed: u64,
+    state: u64,
+}
+
+impl GameRng {
+    pub const fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    #[inline(always)]
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    #[inline(always)]
+    pub fn get_state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = splitmix64(self.state);
+        self.state
+    }
+
+    /// `0..n` 중 하나. 나머지 연산 대신 곱셈을 써서 치우치지 않게 합니다.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "cannot pick from nothing");
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// ## GameRecord
+/// 기보. 첫 수를 두기 전의 난수 상태와 둔 수들, 마지막 난수 상태를 저장합니다.
+/// 수는 둔 그대로(`roll` 없이) 저장하므로 `replay`하면 무작위 결과까지 그대로 다시 나옵니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord<const D: usize> {
+    pub start: GameRng,
+    pub moves: Vec<Move<D>>,
+    pub end: GameRng,
+}
+
+impl<const D: usize> GameState<D> {
+    /// 무작위 결과 하나를 `0..sides` 중에서 뽑습니다. 뽑기 전 상태를 기록하므로 수를 되돌리면 같이 돌아갑니다.
+    /// 난수 상태는 키에 넣지 않습니다. 탐색은 무작위 결과를 전부 따로 보므로(`chance_outcomes`) 국면의 값과 상관이 없습니다.
+    pub fn roll(&mut self, sides: usize) -> usize {
+        let mut rng = *self.get_rng();
+        let value = rng.below(sides);
+        self.set_rng(rng);
+        value
+    }
+
+    /// `candidates`에서 겹치지 않게 `count`개를 무작위로 고릅니다.
+    pub fn choose_squares(&mut self, mut candidates: Vec<AbsolutePosition<D>>, count: usize) -> Vec<AbsolutePosition<D>> {
+        let count = count.min(candidates.len());
+        for index in 0..count {
+            let pick = index + self.roll(candidates.len() - index);
+            candidates.swap(index, pick);
+        }
+        candidates.truncate(count);
+        candidates
+    }
+
+    /// 빈 칸 `count`개에 `kind` 효과를 영원히 겁니다. (바다거북의 바다 칸)
+    pub fn scatter_square_effect(&mut self, kind: EffectKind, count: usize, source: PlayerId) -> Vec<AbsolutePosition<D>> {
+        let empty: Vec<_> = self.get_board().empty_squares().collect();
+        let chosen = self.choose_squares(empty, count);
+        let spec = EffectSpec::new(kind, EffectDuration::Permanent);
+        for position in &chosen {
+            self.apply_effect(EffectTarget::Square(*position), &spec, source);
+        }
+        chosen
+    }
+
+    /// `mv`가 일으키는 무작위 결과의 수. 1이면 무작위가 없습니다. 수를 두기 전에 부릅니다.
+    /// 탐색은 결과가 여럿이면 `mv.with_roll(i)`를 전부 두어 보고 평균을 냅니다.
+    pub fn chance_outcomes(&self, mv: &Move<D>) -> usize {
+        let Some(piece) = mv.get_promotion().or_else(|| self.get_board().get(mv.get_from())).filter(|_| mv.relocates()) else {
+            return 1;
+        };
+        self.get_rules().get_definition(piece).map_or(1, |definition| definition.get_gambles().len().max(1))
+    }
+
+    /// 수를 둔 뒤의 무작위 결과를 정합니다. 갬블러는 다음에 흉내 낼 기물을 새로 뽑습니다.
+    pub(crate) fn resolve_chance(&mut self, mv: &Move<D>) {
+        if !mv.relocates() {
+            return;
+        }
+        let Some(gambles) = self.get_board().get(mv.get_to())
+            .and_then(|piece| self.get_rules().get_definition(piece))
+            .map(|definition| definition.get_gambles().to_vec())
+            .filter(|gambles| !gambles.is_empty()) else {
+            return;
+        };
+        let roll = match mv.get_roll() {
+            Some(roll) => roll % gambles.len(),
+            None => self.roll(gambles.len()),
+        };
+        self.update_piece(mv.get_to(), |piece| piece.get_state_mut().set(GAMBLE_STATE, gambles[roll].clone()));
+    }
+
+    /// 지금까지의 기보.
+    pub fn game_record(&self) -> GameRecord<D> {
+        let start = self.history().iter()
+            .flat_map(|undo| undo.get_changes())
+            .find_map(|change| match change {
+                Change::Rng(rng) => Some(*rng),
+                _ => None
+            })
+            .unwrap_or(*self.get_rng());
+        GameRecord { start, moves: self.history().iter().map(|undo| undo.get_move().clone()).collect(), end: *self.get_rng() }
+    }
+
+    /// 기보를 다시 둡니다. 이 게임은 기보의 첫 국면과 같은 국면, 같은 난수 상태여야 합니다.
+    /// 둘 수 없는 수가 있거나 끝난 난수 상태가 다르면 에러입니다.
+    pub fn replay(&mut self, record: &GameRecord<D>) -> Result<()> {
+        if *self.get_rng() != record.start {
+            bail!("random state {:?} does not match the record start {:?}", self.get_rng(), record.start);
+        }
+        for mv in &record.moves {
+            if !self.legal_moves().contains(mv) {
+                bail!("illegal move {mv} in record");
+            }
+            self.make_move(mv.clone());
+        }
+        if *self.get_rng() != record.end {
+            bail!("replay ended with random state {:?} instead of {:?}", self.get_rng(), record.end);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{presets, search::{SearchLimits, Searcher}};
+
+    #[test]
+    fn gambler_rolls_replay_bit_for_bit() {
+        let fen = "4k3/8/8/8/8/8/8/G3K3 w - - 0 1";
+        let new_game = || {
+            let mut game = GameState::from_fen(fen, presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+            game.set_seed(42);
+            game.scatter_square_effect(EffectKind::Custom("sea".to_string()), 40, 0);
+            game
+        };
+        let mut game = new_game();
+        game.set_hash_check(true);
+        assert_eq!(game.square_effects().count(), 40);
+        for _ in 0..12 {
+            let moves = game.legal_moves();
+            let gambler = moves.iter().find(|mv| game.get_board().get(mv.get_from()).unwrap().get_name() == "gambler");
+            let mv = gambler.unwrap_or(&moves[0]).clone();
+            game.make_move(mv);
+        }
+        let record = game.game_record();
+        assert_ne!(record.start, record.end);
+
+        let mut replayed = new_game();
+        replayed.replay(&record).unwrap();
+        assert_eq!(replayed.hash(), game.hash());
+        assert_eq!(*replayed.get_rng(), *game.get_rng());
+
+        let mut other = GameState::from_fen(fen, presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        other.set_seed(7);
+        assert!(other.replay(&record).is_err());
+
+        let start = game.hash();
+        let rng = *game.get_rng();
+        let mv = game.legal_moves().into_iter().find(|mv| game.chance_outcomes(mv) > 1).unwrap();
+        assert_eq!(game.chance_outcomes(&mv), 3);
+        game.make_move(mv);
+        game.unmake_move();
+        Searcher::new(1).search(&mut game, SearchLimits { depth: Some(2), ..SearchLimits::default() });
+        assert_eq!(game.hash(), start);
+        assert_eq!(*game.get_rng(), rng);
+    }
+}
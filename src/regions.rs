@@ -0,0 +1,116 @@
+use crate::{
+    game::GameState,
+    orientation::board_size,
+    piece::PlayerId,
+    position::AbsolutePosition,
+    rules::PieceDefinition,
+};
+
+/// ## Region
+/// 이름 붙은 칸 모음. 좌표는 전부 그 플레이어가 보는 방향(`Orientation`)에서 자기 쪽 끝부터 0으로 셉니다.
+/// - `Layers`: 축 `axis`의 `first..=last`번째 줄 (자기 진영, 강 건너편)
+/// - `FarLayers`: 축 `axis`의 상대 쪽 끝 `count`줄 (쇼기의 프로모션 구역, 3차원의 마지막 두 층)
+/// - `Box`: 축마다 `(first, last)` 범위인 상자. 빠진 축은 전부 들어갑니다. (궁성)
+/// - `Union`, `Intersection`: 여러 구역의 합집합, 교집합
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Region {
+    Layers { axis: usize, first: usize, last: usize },
+    FarLayers { axis: usize, count: usize },
+    Box(Vec<(usize, usize)>),
+    Union(Vec<Region>),
+    Intersection(Vec<Region>),
+}
+
+impl Region {
+    /// 보는 방향으로 바꾼 좌표 `view`가 이 구역에 들어가는지. `size`는 축마다 칸 수입니다.
+    pub fn contains(&self, view: &[usize], size: &[usize]) -> bool {
+        match self {
+            Region::Layers { axis, first, last } => view.get(*axis).is_some_and(|v| (first..=last).contains(&v)),
+            Region::FarLayers { axis, count } => view.get(*axis).is_some_and(|v| v + count >= size[*axis]),
+            Region::Box(ranges) => ranges.iter().zip(view).all(|((first, last), v)| (first..=last).contains(&v)),
+            Region::Union(regions) => regions.iter().any(|region| region.contains(view, size)),
+            Region::Intersection(regions) => regions.iter().all(|region| region.contains(view, size)),
+        }
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// `player`의 `definition` 기물이 `position`에 도착하면 프로모션하는지. 프로모션 랭크나 프로모션 구역이면 됩니다.
+    /// 프로모션 랭크는 `player`의 방향에서 자기 쪽 끝부터 셉니다.
+    pub fn promotes_at(&self, definition: &PieceDefinition<D>, player: PlayerId, position: &AbsolutePosition<D>) -> bool {
+        definition.promotes_on(self.rank_of(player, position))
+            || (!definition.get_promotions().is_empty() && definition.get_promotion_region().is_some_and(|region| self.in_region(region, player, position)))
+    }
+
+    /// `player`가 보는 `position`의 랭크. 자기 쪽 끝이 0입니다.
+    pub fn rank_of(&self, player: PlayerId, position: &AbsolutePosition<D>) -> usize {
+        self.get_rules().get_orientation(player).view(position, self.get_board())[0]
+    }
+
+    /// `position`이 `player`에게 `name` 구역인지. 없는 구역이면 false.
+    pub fn in_region(&self, name: &str, player: PlayerId, position: &AbsolutePosition<D>) -> bool {
+        let rules = self.get_rules();
+        let Some(region) = rules.get_region(name) else {
+            return false;
+        };
+        let board = self.get_board();
+        region.contains(&rules.get_orientation(player).view(position, board), &board_size(board))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{
+        economy::{DropRestriction, Economy},
+        fen::parse_square,
+        movement::{IndependentMove, CAPTURE, MOVE},
+        position::{Board, RelativePosition},
+        presets,
+        rules::{PieceDefinition, Ruleset},
+        symmetry::Symmetry,
+        zobrist::Zobrist,
+    };
+
+    #[test]
+    fn regions_follow_each_side() {
+        let palace = Region::Box(vec![(0, 2), (3, 5)]);
+        let river = Region::Layers { axis: 0, first: 5, last: 9 };
+        let rules = presets::standard_chess()
+            .with_region("palace", palace)
+            .with_region("across_river", river)
+            .with_region("zone", Region::FarLayers { axis: 0, count: 3 });
+        let game = GameState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1", presets::standard_board(), Arc::new(rules)).unwrap();
+        let square = |name| parse_square(name).unwrap();
+        assert!(game.in_region("palace", 0, &square("e2")) && !game.in_region("palace", 1, &square("e2")));
+        assert!(game.in_region("palace", 1, &square("d8")));
+        assert!(game.in_region("zone", 0, &square("a6")) && game.in_region("zone", 1, &square("a3")));
+        assert!(!game.in_region("zone", 0, &square("a5")) && !game.in_region("unknown", 0, &square("a8")));
+
+        let mut rules = Ruleset::new(2).with_region("zone", Region::FarLayers { axis: 0, count: 2 });
+        let king = [[1, 0, 0], [1, 1, 0], [1, 1, 1]].into_iter()
+            .flat_map(|offset| RelativePosition::from(offset).symmetries(&Symmetry::all()))
+            .fold(PieceDefinition::new("king", "K", 0), |king, offset| {
+                king.with_independent_move(IndependentMove::new([MOVE, CAPTURE], offset))
+            })
+            .with_promotion_region("zone", ["knight"]);
+        for player in 0..2 {
+            rules.define(player, king.clone());
+            rules.define(player, PieceDefinition::new("knight", "N", 3));
+        }
+        let rules = rules.with_economy(Economy::default().with_restriction(DropRestriction::NotInRegion { name: None, region: "zone".to_string() }));
+        let board = Board::new(AbsolutePosition::from([0, 0, 0]), AbsolutePosition::from([3, 3, 3]), Zobrist::default());
+        let mut game = GameState::new(board, Arc::new(rules));
+        let corner = AbsolutePosition::from([3, 0, 0]);
+        let definition = game.get_rules().get(0, "king").unwrap().clone();
+        assert!(game.promotes_at(&definition, 0, &corner) && game.promotes_at(&definition, 0, &AbsolutePosition::from([2, 3, 3])));
+        assert!(!game.promotes_at(&definition, 0, &AbsolutePosition::from([1, 0, 0])));
+        assert!(game.promotes_at(&definition, 1, &AbsolutePosition::from([1, 0, 0])));
+        assert!(!game.can_drop(0, &definition, &corner) && game.can_drop(1, &definition, &corner));
+        let king = game.get_rules().create_piece(0, "king").unwrap();
+        assert!(game.guard_holds(&"region(zone)".parse().unwrap(), &corner, &king, 0));
+        game.put_piece(AbsolutePosition::from([2, 0, 0]), king);
+        assert!(game.pseudo_legal_moves().iter().any(|mv| mv.get_promotion().is_some()));
+    }
+}
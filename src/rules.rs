@@ -0,0 +1,563 @@
+use std::collections::{HashMap, HashSet};
+use anyhow::{anyhow, Result};
+use crate::{
+    actions::ActionDefinition,
+    castling::Castling,
+    economy::Economy,
+    file_scan::{FileFormat, MoveFormat},
+    guards::{Guard, PlyDependence, ScriptFn, Scripts},
+    hooks::CaptureHook,
+    movement::{DependentMove, IndependentMove, INITIAL},
+    piece::{Piece, PlayerId},
+    position::RelativePosition,
+    orientation::Orientation,
+    ownership::{Owner, TeamId},
+    regions::Region,
+    symmetry::symmetries,
+    random::GAMBLE_STATE,
+    transform::{TransformRule, TransformTrigger, MOVES_AS_STATE},
+    turns::ContinuationRule,
+    visibility::Identity,
+};
+
+/// 잡히면 지는 기물. 이 기물이 공격받는 수는 둘 수 없습니다.
+pub const ROYAL: &str = "royal";
+/// 캐슬링하는 쪽 기물 (킹).
+pub const CASTLE: &str = "castle";
+
+/// ## PieceDefinition
+/// 기물 한 종류의 정의. 보드 위의 `Piece`는 이름으로 이 정의를 찾아 씁니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PieceDefinition<const D: usize> {
+    name: String,
+    short_name: String,
+    point: i32,
+    attributes: HashSet<String>,
+    independent_moves: Vec<IndependentMove<D>>,
+    dependent_moves: Vec<DependentMove<D>>,
+    promotions: Vec<String>,
+    promotion_zone: Vec<usize>,
+    promotion_region: Option<String>,
+    actions: Vec<ActionDefinition<D>>,
+    continuation: Option<ContinuationRule>,
+    disguise: Option<String>,
+    gambles: Vec<String>,
+    on_captured: Vec<CaptureHook>,
+    on_capture: Vec<CaptureHook>,
+    transforms: Vec<TransformRule>,
+}
+
+impl<const D: usize> PieceDefinition<D> {
+    pub fn new(name: impl Into<String>, short_name: impl Into<String>, point: i32) -> Self {
+        Self {
+            name: name.into(),
+            short_name: short_name.into(),
+            point,
+            attributes: HashSet::new(),
+            independent_moves: Vec::new(),
+            dependent_moves: Vec::new(),
+            promotions: Vec::new(),
+            promotion_zone: Vec::new(),
+            promotion_region: None,
+            actions: Vec::new(),
+            continuation: None,
+            disguise: None,
+            gambles: Vec::new(),
+            on_captured: Vec::new(),
+            on_capture: Vec::new(),
+            transforms: Vec::new(),
+        }
+    }
+
+    /// 기물 파일에서 정의를 읽습니다. `max_times`가 없는 이동은 `IndependentMove`가 됩니다.
+    pub fn from_file_format(file: &FileFormat) -> Result<Self> {
+        let mut definition = Self::new(file.get_name(), file.get_short_name(), *file.get_point());
+        for attribute in file.get_attributes() {
+            definition = definition.with_attribute(attribute);
+        }
+        for format in file.get_moves() {
+            definition = definition.with_move_format(format)?;
+        }
+        if let Some(betza) = file.get_betza() {
+            definition = definition.with_betza(betza)?;
+        }
+        for format in file.get_actions() {
+            definition = definition.with_action(ActionDefinition::from_format(format)?);
+        }
+        if let Some(format) = file.get_continuation() {
+            definition = definition.with_continuation(ContinuationRule::from_format(format)?);
+        }
+        definition.disguise = file.get_disguise().cloned();
+        definition.gambles = file.get_gambles().clone();
+        definition.on_captured = file.get_on_captured().iter().map(CaptureHook::from_format).collect::<Result<_>>()?;
+        definition.on_capture = file.get_on_capture().iter().map(CaptureHook::from_format).collect::<Result<_>>()?;
+        definition.promotions = file.get_promotions().clone();
+        definition.promotion_zone = file.get_promotion_zone().iter().copied().chain(file.get_promotion_rank()).collect();
+        definition.promotion_region = file.get_promotion_region().cloned();
+        definition.transforms = file.get_transforms().iter().map(TransformRule::from_format).collect::<Result<_>>()?;
+        Ok(definition)
+    }
+
+    /// 이동 하나를 더합니다. 대칭이 있으면 펼친 방향마다 하나씩 더합니다.
+    fn with_move_format(mut self, format: &MoveFormat) -> Result<Self> {
+        let offset = RelativePosition::try_from(format.get_offset().clone())
+            .map_err(|offset| anyhow!("{}: offset {:?} is not {}-dimensional", self.name, offset, D))?;
+        let offsets = match format.get_symmetries() {
+            names if names.is_empty() => vec![offset],
+            names => offset.symmetries(&symmetries(names).map_err(|error| anyhow!("{}: {error}", self.name))?),
+        };
+        let guard = match format.get_guard() {
+            Some(guard) => Some(guard.parse::<Guard>().map_err(|error| anyhow!("{}: {error}", self.name))?),
+            None => None,
+        };
+        for offset in offsets {
+            let mut step = IndependentMove::new(format.get_movement_type().iter().cloned(), offset);
+            if let Some(guard) = &guard {
+                step = step.with_guard(guard.clone());
+            }
+            self = match format.get_max_times() {
+                Some(max_times) => self.with_dependent_move(DependentMove::new(step, max_times)),
+                None => self.with_independent_move(step),
+            };
+        }
+        Ok(self)
+    }
+
+    pub fn with_attribute(mut self, attribute: impl Into<String>) -> Self {
+        self.attributes.insert(attribute.into());
+        self
+    }
+
+    pub fn with_independent_move(mut self, independent_move: IndependentMove<D>) -> Self {
+        self.independent_moves.push(independent_move);
+        self
+    }
+
+    pub fn with_dependent_move(mut self, dependent_move: DependentMove<D>) -> Self {
+        self.dependent_moves.push(dependent_move);
+        self
+    }
+
+    /// 플레이어 쪽에서 적은 이동과 액션을 보드 방향으로 바꿉니다. `Ruleset::define`이 부릅니다.
+    pub fn oriented(mut self, orientation: &Orientation<D>) -> Self {
+        self.independent_moves = std::mem::take(&mut self.independent_moves).into_iter().map(|leap| leap.oriented(orientation)).collect();
+        self.dependent_moves = std::mem::take(&mut self.dependent_moves).into_iter().map(|ride| ride.oriented(orientation)).collect();
+        self.actions = std::mem::take(&mut self.actions).into_iter().map(|action| action.oriented(orientation)).collect();
+        self
+    }
+
+    /// 첫 칸이 `IndependentMove`와 겹치는 `DependentMove`를 표시해서 같은 수를 두 번 만들지 않게 합니다. `Ruleset::define`이 부릅니다.
+    fn with_overlaps_marked(mut self) -> Self {
+        for ride in &mut self.dependent_moves {
+            let covered = self.independent_moves.iter().any(|leap| leap.covers_first_step(ride));
+            ride.set_first_covered(covered);
+        }
+        self
+    }
+
+    /// 지금까지 더한 모든 이동에 같은 조건을 붙입니다. 액션에는 붙지 않습니다.
+    pub fn with_guard(mut self, guard: Guard) -> Self {
+        self.independent_moves = std::mem::take(&mut self.independent_moves).into_iter().map(|leap| leap.with_guard(guard.clone())).collect();
+        self.dependent_moves = std::mem::take(&mut self.dependent_moves).into_iter().map(|ride| ride.with_guard(guard.clone())).collect();
+        self
+    }
+
+    pub fn with_action(mut self, action: ActionDefinition<D>) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub fn with_continuation(mut self, continuation: ContinuationRule) -> Self {
+        self.continuation = Some(continuation);
+        self
+    }
+
+    /// 다른 플레이어에게는 `name` 기물로 보입니다. (사기꾼)
+    pub fn with_disguise(mut self, name: impl Into<String>) -> Self {
+        self.disguise = Some(name.into());
+        self
+    }
+
+    /// 수를 둘 때마다 `names` 중 하나를 무작위로 골라 다음 수는 그 기물처럼 움직입니다. (갬블러)
+    pub fn with_gambles(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.gambles = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 이 기물이 잡힐 때 실행할 훅을 더합니다.
+    pub fn with_on_captured(mut self, hook: CaptureHook) -> Self {
+        self.on_captured.push(hook);
+        self
+    }
+
+    /// 이 기물이 잡을 때 실행할 훅을 더합니다.
+    pub fn with_on_capture(mut self, hook: CaptureHook) -> Self {
+        self.on_capture.push(hook);
+        self
+    }
+
+    /// `rank`에 도착하면 `promotions` 중 하나로 프로모션합니다. 랭크는 자기 쪽 끝에서 0부터 셉니다.
+    pub fn with_promotion(self, rank: usize, promotions: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.with_promotion_zone([rank], promotions)
+    }
+
+    /// `ranks` 중 하나에 도착하면 `promotions` 중 하나로 프로모션합니다. (쇼기의 세 랭크)
+    pub fn with_promotion_zone(mut self, ranks: impl IntoIterator<Item = usize>, promotions: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.promotion_zone = ranks.into_iter().collect();
+        self.promotions = promotions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// `region` 구역에 도착하면 `promotions` 중 하나로 프로모션합니다.
+    pub fn with_promotion_region(mut self, region: impl Into<String>, promotions: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.promotion_region = Some(region.into());
+        self.promotions = promotions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 변신 규칙을 더합니다.
+    pub fn with_transform(mut self, rule: TransformRule) -> Self {
+        self.transforms.push(rule);
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    #[inline(always)]
+    pub fn get_short_name(&self) -> &String {
+        &self.short_name
+    }
+
+    #[inline(always)]
+    pub fn get_point(&self) -> i32 {
+        self.point
+    }
+
+    #[inline(always)]
+    pub fn has_attribute(&self, attribute: &str) -> bool {
+        self.attributes.contains(attribute)
+    }
+
+    /// `Timer` 변신 규칙이 있는지. 이런 기물에만 `TIMER_STATE`를 붙입니다.
+    pub fn has_timer(&self) -> bool {
+        self.transforms.iter().any(|rule| matches!(rule.trigger, TransformTrigger::Timer(_)))
+    }
+
+    /// 움직였는지가 규칙에 영향을 주는 기물인지. 이런 기물에만 `MOVED_STATE`를 붙입니다.
+    /// 다른 기물에까지 붙이면 같은 국면이 다른 키를 가지게 되어 반복 판정이 틀어집니다.
+    pub fn uses_moved_state(&self) -> bool {
+        self.has_attribute(CASTLE)
+            || self.independent_moves.iter().any(|independent_move| independent_move.has_type(INITIAL))
+            || self.dependent_moves.iter().any(|dependent_move| dependent_move.get_step().has_type(INITIAL))
+    }
+
+    #[inline(always)]
+    pub fn get_independent_moves(&self) -> &[IndependentMove<D>] {
+        &self.independent_moves
+    }
+
+    #[inline(always)]
+    pub fn get_dependent_moves(&self) -> &[DependentMove<D>] {
+        &self.dependent_moves
+    }
+
+    #[inline(always)]
+    pub fn get_actions(&self) -> &[ActionDefinition<D>] {
+        &self.actions
+    }
+
+    /// 표기로 액션을 찾습니다.
+    pub fn get_action(&self, notation: &str) -> Option<&ActionDefinition<D>> {
+        self.actions.iter().find(|action| action.get_notation() == notation)
+    }
+
+    #[inline(always)]
+    pub fn get_continuation(&self) -> Option<&ContinuationRule> {
+        self.continuation.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_disguise(&self) -> Option<&String> {
+        self.disguise.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_gambles(&self) -> &[String] {
+        &self.gambles
+    }
+
+    #[inline(always)]
+    pub fn get_on_captured(&self) -> &[CaptureHook] {
+        &self.on_captured
+    }
+
+    #[inline(always)]
+    pub fn get_on_capture(&self) -> &[CaptureHook] {
+        &self.on_capture
+    }
+
+    #[inline(always)]
+    pub fn get_promotions(&self) -> &[String] {
+        &self.promotions
+    }
+
+    #[inline(always)]
+    pub fn get_promotion_zone(&self) -> &[usize] {
+        &self.promotion_zone
+    }
+
+    /// 자기 쪽 끝에서 센 `rank`에 도착하면 프로모션하는지. 프로모션 구역(`promotion_region`)은 `GameState::promotes_at`이 봅니다.
+    #[inline(always)]
+    pub fn promotes_on(&self, rank: usize) -> bool {
+        !self.promotions.is_empty() && self.promotion_zone.contains(&rank)
+    }
+
+    #[inline(always)]
+    pub fn get_promotion_region(&self) -> Option<&String> {
+        self.promotion_region.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_transforms(&self) -> &[TransformRule] {
+        &self.transforms
+    }
+
+    pub fn create_piece(&self, owner: PlayerId) -> Piece {
+        Piece::new(&self.name, &self.short_name, self.point, owner)
+    }
+}
+
+/// ## Ruleset
+/// 플레이어별 기물 정의 모음.
+/// 기물은 자기 쪽에서 본 방향으로 정의하고, 플레이어마다 그 플레이어의 방향(`Orientation`)으로 바꿔 따로 둡니다. (폰의 앞 방향)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ruleset<const D: usize> {
+    pieces: Vec<HashMap<String, PieceDefinition<D>>>,
+    teams: Vec<TeamId>,
+    economy: Economy,
+    scripts: Scripts<D>,
+    timers: bool,
+    ply_dependence: Option<PlyDependence>,
+    orientations: Vec<Orientation<D>>,
+    regions: HashMap<String, Region>,
+    castling: Castling,
+}
+
+impl<const D: usize> Ruleset<D> {
+    pub fn new(player_count: usize) -> Self {
+        Self {
+            pieces: vec![HashMap::new(); player_count],
+            teams: (0..player_count).collect(),
+            economy: Economy::default(),
+            scripts: Scripts::default(),
+            timers: false,
+            ply_dependence: None,
+            orientations: (0..player_count).map(Orientation::default_for).collect(),
+            regions: HashMap::new(),
+            castling: Castling::default(),
+        }
+    }
+
+    /// 플레이어마다 팀을 정합니다. 같은 팀끼리는 서로 잡지 않고 체크도 걸지 않습니다.
+    pub fn with_teams(mut self, teams: Vec<TeamId>) -> Self {
+        assert_eq!(teams.len(), self.pieces.len(), "every player needs a team");
+        self.teams = teams;
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_team(&self, player: PlayerId) -> TeamId {
+        self.teams[player]
+    }
+
+    /// `owner`의 기물이 `player` 자신이나 그 팀의 것인지. 공유 기물과 중립 기물은 누구의 것도 아닙니다.
+    pub fn owns(&self, player: PlayerId, owner: Owner) -> bool {
+        match owner {
+            Owner::Player(owner) => owner == player,
+            Owner::Team(team) => self.get_team(player) == team,
+            Owner::Shared | Owner::Neutral => false,
+        }
+    }
+
+    /// `player`가 `owner`의 기물을 움직일 수 있는지.
+    pub fn controls(&self, player: PlayerId, owner: Owner) -> bool {
+        match owner {
+            Owner::Player(owner) => owner == player,
+            Owner::Team(team) => self.get_team(player) == team,
+            Owner::Shared => true,
+            Owner::Neutral => false,
+        }
+    }
+
+    /// `player`가 `owner`의 기물을 잡을 수 있는 편인지.
+    pub fn is_hostile(&self, player: PlayerId, owner: Owner) -> bool {
+        match owner {
+            Owner::Player(owner) => self.get_team(owner) != self.get_team(player),
+            Owner::Team(team) => self.get_team(player) != team,
+            Owner::Shared | Owner::Neutral => true,
+        }
+    }
+
+    /// `owner`의 기물이 쓰는 정의의 플레이어. 팀 기물은 그 팀의 첫 플레이어,
+    /// 공유 기물과 중립 기물은 플레이어 0의 정의를 씁니다.
+    pub fn definition_player(&self, owner: Owner) -> PlayerId {
+        match owner {
+            Owner::Player(player) => player,
+            Owner::Team(team) => self.teams.iter().position(|&other| other == team).unwrap_or(0),
+            Owner::Shared | Owner::Neutral => 0,
+        }
+    }
+
+    pub fn with_economy(mut self, economy: Economy) -> Self {
+        self.economy = economy;
+        self
+    }
+
+    /// 기물을 손에 들고 놓는 규칙.
+    #[inline(always)]
+    pub fn get_economy(&self) -> &Economy {
+        &self.economy
+    }
+
+    pub fn with_castling(mut self, castling: Castling) -> Self {
+        self.castling = castling;
+        self
+    }
+
+    /// 캐슬링 규칙. 따로 정하지 않으면 표준 체스의 캐슬링입니다.
+    #[inline(always)]
+    pub fn get_castling(&self) -> &Castling {
+        &self.castling
+    }
+
+    /// 플레이어가 보드를 보는 방향을 정합니다. 이미 정의한 기물의 이동도 새 방향으로 바꾸고, 구역과 프로모션 랭크도 이 방향으로 셉니다.
+    pub fn with_orientation(mut self, player: PlayerId, orientation: Orientation<D>) -> Self {
+        let back = self.orientations[player].inverse();
+        for definition in self.pieces[player].values_mut() {
+            *definition = definition.clone().oriented(&back).oriented(&orientation);
+        }
+        self.orientations[player] = orientation;
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_orientation(&self, player: PlayerId) -> &Orientation<D> {
+        &self.orientations[player]
+    }
+
+    /// 이름 붙은 구역을 더합니다. 이동 조건, 프로모션, 드롭 제한이 이름으로 씁니다.
+    pub fn with_region(mut self, name: impl Into<String>, region: Region) -> Self {
+        self.regions.insert(name.into(), region);
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_region(&self, name: &str) -> Option<&Region> {
+        self.regions.get(name)
+    }
+
+    /// 이동 조건의 `script(name)`이 부르는 함수를 등록합니다.
+    pub fn with_script(mut self, name: impl Into<String>, function: ScriptFn<D>) -> Self {
+        self.scripts.insert(name, function);
+        self
+    }
+
+    #[inline(always)]
+    pub fn get_scripts(&self) -> &Scripts<D> {
+        &self.scripts
+    }
+
+    #[inline(always)]
+    pub fn player_count(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// `player`의 기물을 정의합니다. `definition`은 자기 쪽에서 본 방향으로 적고, 여기서 `player`의 방향으로 바꿉니다.
+    pub fn define(&mut self, player: PlayerId, definition: PieceDefinition<D>) {
+        self.timers |= definition.has_timer();
+        let player_count = self.player_count();
+        self.ply_dependence = definition.independent_moves.iter()
+            .chain(definition.dependent_moves.iter().map(DependentMove::get_step))
+            .filter_map(|step| step.get_guard()?.ply_dependence(player_count))
+            .fold(self.ply_dependence, |dependence, other| Some(dependence.map_or(other, |dependence| dependence.combine(other))));
+        let definition = definition.oriented(&self.orientations[player]).with_overlaps_marked();
+        self.pieces[player].insert(definition.get_name().clone(), definition);
+    }
+
+    /// 이 규칙의 프로모션을 바꿉니다. `zone`은 자기 쪽 끝에서 센 프로모션 랭크라서 모든 플레이어에게 같습니다.
+    pub fn with_promotion_rule(mut self, name: &str, zone: Vec<usize>, promotions: &[&str]) -> Self {
+        for pieces in &mut self.pieces {
+            if let Some(definition) = pieces.remove(name) {
+                pieces.insert(name.to_string(), definition.with_promotion_zone(zone.clone(), promotions.iter().copied()));
+            }
+        }
+        self
+    }
+
+    /// 이동 조건이 ply에 기대는 방식. Zobrist 키에 ply를 얼마나 넣을지 정합니다.
+    #[inline(always)]
+    pub fn get_ply_dependence(&self) -> Option<PlyDependence> {
+        self.ply_dependence
+    }
+
+    /// `Timer` 변신 규칙이 있는 기물이 있는지. 없으면 차례마다 기물을 훑지 않습니다.
+    #[inline(always)]
+    pub fn has_timers(&self) -> bool {
+        self.timers
+    }
+
+    #[inline(always)]
+    pub fn get(&self, player: PlayerId, name: &str) -> Option<&PieceDefinition<D>> {
+        self.pieces.get(player)?.get(name)
+    }
+
+    /// 보드 위 기물의 정의.
+    #[inline(always)]
+    pub fn get_definition(&self, piece: &Piece) -> Option<&PieceDefinition<D>> {
+        self.get(self.definition_player(piece.get_owner()), piece.get_name())
+    }
+
+    /// 변장한 기물이 다른 플레이어에게 보이는 정의.
+    pub fn disguise_definition(&self, piece: &Piece) -> Option<&PieceDefinition<D>> {
+        let disguise = self.get_definition(piece)?.get_disguise()?;
+        self.get(self.definition_player(piece.get_owner()), disguise)
+    }
+
+    /// 이 기물에 `MOVED_STATE`를 붙여야 하는지. 변장한 정체가 쓰는 경우에도 붙여야
+    /// 보이는 보드에서 진짜 기물과 구별되지 않습니다.
+    pub fn tracks_moved_state(&self, piece: &Piece) -> bool {
+        self.get_definition(piece).is_some_and(PieceDefinition::uses_moved_state)
+            || self.disguise_definition(piece).is_some_and(PieceDefinition::uses_moved_state)
+    }
+
+    /// 기물이 지금 움직이는 방식의 정의. `MOVES_AS_STATE`가 있으면 거기 적힌 기물처럼 움직입니다.
+    /// 갬블러는 `GAMBLE_STATE`에 적힌 기물처럼, 아직 뽑은 적이 없으면 첫 번째 기물처럼 움직입니다.
+    pub fn movement_definition(&self, piece: &Piece) -> Option<&PieceDefinition<D>> {
+        let definition = self.get_definition(piece)?;
+        let state = piece.get_state();
+        let gamble = state.get(MOVES_AS_STATE).or(state.get(GAMBLE_STATE)).or(definition.gambles.first());
+        match gamble {
+            Some(name) => self.get(self.definition_player(piece.get_owner()), name),
+            None => Some(definition),
+        }
+    }
+
+    pub fn definitions(&self, player: PlayerId) -> impl Iterator<Item = &PieceDefinition<D>> {
+        self.pieces[player].values()
+    }
+
+    /// 기물을 만듭니다. 변장하는 기물이면 다른 플레이어에게 보이는 정체도 정해둡니다.
+    pub fn create_piece(&self, player: PlayerId, name: &str) -> Option<Piece> {
+        let definition = self.get(player, name)?;
+        let mut piece = definition.create_piece(player);
+        if let Some(disguise) = definition.get_disguise().and_then(|disguise| self.get(player, disguise)) {
+            for other in (0..self.player_count()).filter(|&other| other != player) {
+                piece.set_appearance(other, Identity::of_definition(disguise));
+            }
+        }
+        Some(piece)
+    }
+}
@@ -0,0 +1,436 @@
+use anyhow::{anyhow, bail, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::{
+    buf::PLAYER_INPUT_RE,
+    fen::parse_square,
+    game::GameState,
+    movement::{CASTLING, PASS, PUSH, RIDE},
+    moves::Move,
+    position::AbsolutePosition,
+};
+
+lazy_static! {
+    /// 손에 든 기물이나 산 기물을 놓는 수. (`N@e4`, `Q$d5`)
+    static ref DROP_RE: Regex = Regex::new(r"^(?P<name>[A-Z]+)(?P<mark>[@$])(?P<to>[a-z]\d+)$").unwrap();
+    /// 자리를 옮기지 않는 액션. (`Ls`, `Ssd4`, `Le2s`)
+    static ref ACTION_RE: Regex = Regex::new(r"^(?P<name>[A-Z]*)(?P<from>[a-z]\d+)?(?P<action>[a-z])(?P<target>[a-z]\d+)?$").unwrap();
+    /// 옆의 기물을 밀어내는 수. 같은 줄로 밀면 도착 행만, 아니면 도착 칸을 적습니다. (`Fpe3-7`, `Fpd4-h4`, 구분하려면 `Fe2pe3-7`)
+    static ref PUSH_RE: Regex = Regex::new(r"^(?P<name>[A-Z]+)(?P<from>[a-z]\d+)?p(?P<pushed>[a-z]\d+)-(?P<end_file>[a-z])?(?P<end_rank>\d+)$").unwrap();
+    /// 다른 기물에 업히는 수. (`Hrf3`, 구분하려면 `Hg1rf3`)
+    static ref RIDE_RE: Regex = Regex::new(r"^(?P<name>[A-Z]+)(?P<from>[a-z]\d+)?r(?P<to>[a-z]\d+)$").unwrap();
+}
+
+/// 기보 한 수를 읽은 모양. 한 글자가 여러 뜻일 수 있으므로(`Nbd2`는 `b` 액션으로도 보통 수로도 읽힘) 읽을 수 있는 모양을 전부 봅니다.
+/// push(`Fpe3-7`)는 `PUSH` 수의 `displacements`, ride(`Hrf3`)는 `RIDE` 수의 도착 칸과 맞춰 봅니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MoveText {
+    Pass,
+    Castle { long: bool },
+    Drop { name: String, to: AbsolutePosition<2>, bought: bool },
+    Action { name: String, from: Option<AbsolutePosition<2>>, action: String, target: Option<AbsolutePosition<2>> },
+    /// `file`이 없으면 밀린 기물의 줄 그대로입니다.
+    Push { name: String, from: Option<AbsolutePosition<2>>, pushed: AbsolutePosition<2>, file: Option<usize>, rank: usize },
+    Ride { name: String, from: Option<AbsolutePosition<2>>, to: AbsolutePosition<2> },
+    Standard { name: String, file: Option<usize>, rank: Option<usize>, takes: bool, to: AbsolutePosition<2>, promotion: Option<String> },
+}
+
+/// `text`를 읽을 수 있는 모양들. 체크 표시(`+`, `#`)와 평가 표시(`!`, `?`)는 뺍니다.
+fn move_texts(text: &str) -> Vec<MoveText> {
+    let text = text.trim().trim_end_matches(['+', '#', '!', '?']);
+    match text {
+        "pass" => return vec![MoveText::Pass],
+        "O-O" | "0-0" => return vec![MoveText::Castle { long: false }],
+        "O-O-O" | "0-0-0" => return vec![MoveText::Castle { long: true }],
+        _ => {}
+    }
+    let square = |captures: &regex::Captures, name: &str| captures.name(name).and_then(|square| parse_square(square.as_str()).ok());
+    let mut texts = Vec::new();
+    if let Some(captures) = DROP_RE.captures(text) {
+        if let Some(to) = square(&captures, "to") {
+            texts.push(MoveText::Drop { name: captures["name"].to_string(), to, bought: &captures["mark"] == "$" });
+        }
+    }
+    if let Some(captures) = ACTION_RE.captures(text) {
+        let (name, from, target) = (captures["name"].to_string(), square(&captures, "from"), square(&captures, "target"));
+        if captures.name("from").is_none_or(|_| from.is_some()) && captures.name("target").is_none_or(|_| target.is_some()) {
+            texts.push(MoveText::Action { name, from, action: captures["action"].to_string(), target });
+        }
+    }
+    if let Some(captures) = PUSH_RE.captures(text) {
+        let file = captures.name("end_file").map(|file| (file.as_str().as_bytes()[0] - b'a') as usize);
+        let rank = captures["end_rank"].parse::<usize>().ok().and_then(|rank| rank.checked_sub(1));
+        let from = square(&captures, "from");
+        if let (Some(pushed), Some(rank), true) = (square(&captures, "pushed"), rank, captures.name("from").is_none_or(|_| from.is_some())) {
+            texts.push(MoveText::Push { name: captures["name"].to_string(), from, pushed, file, rank });
+        }
+    }
+    if let Some(captures) = RIDE_RE.captures(text) {
+        let from = square(&captures, "from");
+        if let (Some(to), true) = (square(&captures, "to"), captures.name("from").is_none_or(|_| from.is_some())) {
+            texts.push(MoveText::Ride { name: captures["name"].to_string(), from, to });
+        }
+    }
+    texts.extend(standard_text(text));
+    texts
+}
+
+/// `PLAYER_INPUT_RE`로 읽는 보통 수. (`e4`, `Nbd7`, `R1a3`, `exd5`, `e8=Q`, 긴 기보의 `Ng1-f3`)
+/// 정규식의 `name`이 앞의 소문자까지 가져가므로, 대문자는 기물, 그 뒤 소문자는 출발 줄, 끝의 `x`는 잡기로 다시 나눕니다.
+fn standard_text(text: &str) -> Option<MoveText> {
+    let text = match text.split_once('-') {
+        Some((from, to)) if to.starts_with(|letter: char| letter.is_ascii_lowercase()) => format!("{from}{to}"),
+        _ => text.to_string(),
+    };
+    let captures = PLAYER_INPUT_RE.captures(&text).filter(|captures| captures.get(0).is_some_and(|whole| whole.start() == 0))?;
+    let letters = format!("{}{}", &captures["name"], &captures["start_col"]);
+    let name: String = letters.chars().take_while(char::is_ascii_uppercase).collect();
+    let mut rest = &letters[name.len()..];
+    let mut takes = !captures["takes"].is_empty();
+    if let Some(before) = rest.strip_suffix(['x', 'X']).filter(|_| !takes) {
+        (rest, takes) = (before, true);
+    }
+    let file = match rest.as_bytes() {
+        [] => None,
+        [file @ b'a'..=b'z'] => Some((file - b'a') as usize),
+        _ => return None,
+    };
+    let rank = match &captures["start_row"] {
+        "" => None,
+        rank => Some(rank.parse::<usize>().ok()?.checked_sub(1)?),
+    };
+    let to = parse_square(&format!("{}{}", &captures["end_col"], &captures["end_row"])).ok()?;
+    let other = captures["other"].trim_matches(['=', '/', '(', ')', ' ']);
+    let promotion = match other {
+        "" | "e.p." | "ep" => None,
+        other if other.chars().all(|letter| letter.is_ascii_alphabetic()) => Some(other.to_uppercase()),
+        _ => return None,
+    };
+    Some(MoveText::Standard { name, file, rank, takes, to, promotion })
+}
+
+/// 캐슬링이 긴 쪽(`O-O-O`)인지. 킹이 가는 방향이 아니라 파트너(룩)가 킹의 어느 쪽에 있었는지로 정합니다.
+/// 체스960에서는 킹이 b1에서 c1로 가도 긴 쪽일 수 있습니다. 파트너가 킹보다 좌표가 작은 쪽이면 긴 쪽이고,
+/// 파일을 따라 하는 캐슬링이면 랭크를 비교합니다.
+fn castles_long<const D: usize>(mv: &Move<D>) -> Option<bool> {
+    let (partner, _) = mv.get_displacements().first()?;
+    let king = mv.get_from();
+    let axis = (0..D).rev().find(|&axis| partner[axis] != king[axis])?;
+    Some(partner[axis] < king[axis])
+}
+
+impl GameState<2> {
+    /// 기보 한 수(`Nbd7`, `exd5`, `e8=Q`, `O-O`, `N@e4`)를 읽어 지금 둘 수 있는 수에서 찾습니다.
+    /// 기물 글자는 기물 파일의 `short_name`이고, 글자가 없으면 `P`입니다. 출발 칸은 줄이나 행 하나만 적어도 됩니다.
+    /// 자리를 옮기지 않는 액션(`Ls`, 칸을 고르면 `Ssd4`), 옆의 기물을 밀어내는 수(`Fpe3-7`), 업히는 수(`Hrf3`)도 읽습니다.
+    /// 맞는 수가 여럿이거나, 맞는 수가 자기 royal 기물을 공격받게 두면 그 수들을 적은 오류입니다.
+    /// 2차원 보드에서만 읽습니다. 다른 차원의 `move_text`는 칸을 `(0, 1, 2)`처럼 적으므로 읽을 수 없습니다.
+    pub fn parse_move_text(&mut self, text: &str) -> Result<Move<2>> {
+        let moves = self.pseudo_legal_moves();
+        self.parse_move_text_among(text, &moves)
+    }
+
+    /// `parse_move_text`와 같지만 지금 국면의 수 목록(`pseudo_legal_moves`나 `legal_moves`)에서 찾습니다.
+    /// `legal_moves`를 넘기면 둘 수 없는 수라는 오류 대신 맞는 수가 없다는 오류가 납니다.
+    pub fn parse_move_text_among(&mut self, text: &str, moves: &[Move<2>]) -> Result<Move<2>> {
+        let texts = move_texts(text);
+        if texts.is_empty() {
+            bail!("cannot read move {text:?}");
+        }
+        let candidates: Vec<_> = moves.iter()
+            .filter(|mv| texts.iter().any(|pattern| self.matches_text(pattern, mv)))
+            .cloned()
+            .collect();
+        let (mut legal, illegal): (Vec<_>, Vec<_>) = candidates.into_iter().partition(|mv| self.is_legal(mv));
+        let list = |moves: &[Move<2>]| moves.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
+        match legal.len() {
+            1 => Ok(legal.remove(0)),
+            0 if illegal.is_empty() => Err(anyhow!("no move matches {text:?}")),
+            0 => Err(anyhow!("illegal move {text:?}: {} would leave a royal piece attacked", list(&illegal))),
+            _ => Err(anyhow!("ambiguous move {text:?}: {}", list(&legal))),
+        }
+    }
+
+    /// `mv`가 읽은 모양에 맞는지.
+    fn matches_text(&self, text: &MoveText, mv: &Move<2>) -> bool {
+        let mover = self.get_board().get(mv.get_from()).map(|piece| piece.get_short_name().to_uppercase());
+        let named = |name: &str| mover.as_deref() == Some(if name.is_empty() { "P" } else { name });
+        let from_matches = |from: &Option<AbsolutePosition<2>>| from.is_none_or(|from| from == *mv.get_from());
+        match text {
+            MoveText::Pass => mv.is_pass(),
+            MoveText::Castle { long } => mv.get_movement_type() == CASTLING && castles_long(mv) == Some(*long),
+            MoveText::Drop { name, to, bought } => {
+                mv.get_drop().is_some_and(|piece| piece.get_short_name().to_uppercase() == *name) && mv.get_to() == to && (mv.get_price() > 0) == *bought
+            }
+            MoveText::Action { name, from, action, target } => {
+                mv.get_action() == Some(action) && (name.is_empty() || named(name)) && from_matches(from)
+                    && target.is_none_or(|target| mv.get_targets() == [target])
+            }
+            MoveText::Push { name, from, pushed, file, rank } => {
+                let end = AbsolutePosition::from([*rank, file.unwrap_or(pushed[1])]);
+                mv.get_movement_type() == PUSH && named(name) && from_matches(from) && mv.get_displacements() == [(*pushed, end)]
+            }
+            MoveText::Ride { name, from, to } => mv.get_movement_type() == RIDE && named(name) && from_matches(from) && mv.get_to() == to,
+            MoveText::Standard { name, file, rank, takes, to, promotion } => {
+                mv.relocates() && named(name) && mv.get_to() == to
+                    && file.is_none_or(|file| mv.get_from()[1] == file)
+                    && rank.is_none_or(|rank| mv.get_from()[0] == rank)
+                    && (!takes || mv.is_capture())
+                    && promotion.as_ref().is_none_or(|promotion| mv.get_promotion().is_some_and(|piece| piece.get_short_name().to_uppercase() == *promotion))
+            }
+        }
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// `mv`의 기보(SAN). `parse_move_text`가 다시 읽으면 같은 수가 됩니다.
+    /// 기물 글자는 `short_name` 전체이고 `P`는 적지 않습니다. 출발 칸은 같은 칸으로 가는 같은 기물이 있을 때만,
+    /// 줄, 행, 칸 순서로 구분되는 만큼만 적습니다. 2차원이 아니면 줄과 행 대신 칸을 적습니다.
+    /// 밀어내는 수는 `Fpe3-7`, 업히는 수는 `Hrf3`처럼 적고, 같은 기물이 같은 수를 둘 수 있을 때만 출발 칸을 넣습니다.
+    /// 수를 둔 뒤 다른 플레이어가 체크면 `+`, 체크메이트면 `#`를 붙입니다.
+    /// 다시 읽는 `parse_move_text`는 `GameState<2>`에만 있습니다. 칸을 `e4`처럼 적는 것이 2차원뿐이라서, 다른 차원의 기보는 적기만 합니다.
+    pub fn move_text(&mut self, mv: &Move<D>) -> String {
+        let legal = self.legal_moves();
+        self.move_text_among(mv, &legal)
+    }
+
+    /// `move_text`와 같지만 지금 국면의 `legal_moves`를 받아서 씁니다. 한 국면의 수를 전부 적을 때 목록을 한 번만 만듭니다.
+    pub fn move_text_among(&mut self, mv: &Move<D>, legal: &[Move<D>]) -> String {
+        let text = self.move_body(mv, false, legal);
+        text + self.check_suffix(mv)
+    }
+
+    /// `mv`의 긴 기보(LAN). 보통 수는 출발 칸을 늘 적고 `-`나 `x`로 잇습니다. (`Ng1-f3`, `e5xd6`) 나머지는 SAN과 같습니다.
+    pub fn long_move_text(&mut self, mv: &Move<D>) -> String {
+        let legal = self.legal_moves();
+        self.long_move_text_among(mv, &legal)
+    }
+
+    /// `long_move_text`와 같지만 지금 국면의 `legal_moves`를 받아서 씁니다.
+    pub fn long_move_text_among(&mut self, mv: &Move<D>, legal: &[Move<D>]) -> String {
+        let text = self.move_body(mv, true, legal);
+        text + self.check_suffix(mv)
+    }
+
+    fn move_body(&self, mv: &Move<D>, long: bool, legal: &[Move<D>]) -> String {
+        let board = self.get_board();
+        let name = |position: &AbsolutePosition<D>| board.get(position).map_or(String::new(), |piece| piece.get_short_name().to_uppercase());
+        let mover = name(mv.get_from());
+        if mv.is_pass() {
+            return PASS.to_string();
+        }
+        if mv.is_drop() {
+            return mv.to_string();
+        }
+        if let Some(action) = mv.get_action() {
+            let target = match mv.get_targets() {
+                [target] => target.to_string(),
+                _ => String::new(),
+            };
+            let shared = legal.iter().any(|other| {
+                other.get_action() == Some(action) && other.get_from() != mv.get_from() && name(other.get_from()) == mover
+                    && (target.is_empty() || other.get_targets() == mv.get_targets())
+            });
+            let from = if shared || long { mv.get_from().to_string() } else { String::new() };
+            return format!("{mover}{from}{action}{target}");
+        }
+        if let (PUSH, [(pushed, end)]) = (mv.get_movement_type().as_str(), mv.get_displacements()) {
+            let shared = legal.iter().any(|other| {
+                other.get_movement_type() == PUSH && other.get_from() != mv.get_from() && name(other.get_from()) == mover
+                    && other.get_displacements() == mv.get_displacements()
+            });
+            let from = if shared || long { mv.get_from().to_string() } else { String::new() };
+            let end = if D == 2 && pushed[1] == end[1] { (end[0] + 1).to_string() } else { end.to_string() };
+            return format!("{mover}{from}p{pushed}-{end}");
+        }
+        if mv.get_movement_type() == RIDE {
+            let shared = legal.iter().any(|other| {
+                other.get_movement_type() == RIDE && other.get_from() != mv.get_from() && name(other.get_from()) == mover && other.get_to() == mv.get_to()
+            });
+            let from = if shared || long { mv.get_from().to_string() } else { String::new() };
+            return format!("{mover}{from}r{}", mv.get_to());
+        }
+        if mv.get_movement_type() == CASTLING {
+            let side = castles_long(mv);
+            let same_side = legal.iter().filter(|other| other.get_movement_type() == CASTLING && castles_long(other) == side).count();
+            return match side {
+                Some(long) if same_side == 1 => if long { "O-O-O" } else { "O-O" }.to_string(),
+                _ => format!("{mover}{}{}", mv.get_from(), mv.get_to()),
+            };
+        }
+        let piece = if mover == "P" { String::new() } else { mover.clone() };
+        let from = if long {
+            mv.get_from().to_string()
+        } else {
+            let rivals: Vec<_> = legal.iter()
+                .filter(|other| other.relocates() && other.get_to() == mv.get_to() && other.get_from() != mv.get_from() && name(other.get_from()) == mover)
+                .collect();
+            let from = mv.get_from();
+            let file = (D == 2 && from[1] < 26).then(|| ((b'a' + from[1] as u8) as char).to_string());
+            match file {
+                Some(file) if (piece.is_empty() && mv.is_capture()) || (!rivals.is_empty() && rivals.iter().all(|other| other.get_from()[1] != from[1])) => file,
+                Some(_) if !rivals.is_empty() && rivals.iter().all(|other| other.get_from()[0] != from[0]) => (from[0] + 1).to_string(),
+                _ if rivals.is_empty() => String::new(),
+                _ => from.to_string(),
+            }
+        };
+        let separator = match (mv.is_capture(), long) {
+            (true, _) => "x",
+            (false, true) => "-",
+            (false, false) => "",
+        };
+        let promotion = mv.get_promotion().map_or(String::new(), |piece| format!("={}", piece.get_short_name().to_uppercase()));
+        format!("{piece}{from}{separator}{}{promotion}", mv.get_to())
+    }
+
+    /// 수를 둔 뒤 차례인 플레이어가 둘 수 없고 체크면 `#`, 다른 플레이어 누구라도 체크면 `+`.
+    fn check_suffix(&mut self, mv: &Move<D>) -> &'static str {
+        let mover = self.side_to_move();
+        self.make_move(mv.clone());
+        let checked = (0..self.player_count()).any(|player| player != mover && self.in_check(player));
+        let mated = checked && self.in_check(self.side_to_move()) && self.legal_moves().is_empty();
+        self.unmake_move();
+        match (checked, mated) {
+            (_, true) => "#",
+            (true, false) => "+",
+            _ => "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{presets, rules::PieceDefinition};
+
+    #[test]
+    fn move_text_resolves_disambiguation_and_fairy_notation() {
+        let game = |fen: &str, rules| GameState::from_fen(fen, presets::standard_board(), Arc::new(rules)).unwrap();
+        let mut start = game("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", presets::standard_chess());
+        assert_eq!(start.parse_move_text("e4").unwrap().to_string(), "e2e4");
+        assert_eq!(start.parse_move_text("Nf3!").unwrap().to_string(), "g1f3");
+        assert!(start.parse_move_text("e5").unwrap_err().to_string().contains("no move matches"));
+        assert!(start.parse_move_text("--").unwrap_err().to_string().contains("cannot read"));
+
+        let mut knights = game("4k3/8/8/3p4/4P3/5N2/8/1N2K3 w - - 0 1", presets::standard_chess());
+        let error = knights.parse_move_text("Nd2").unwrap_err().to_string();
+        assert!(error.contains("ambiguous") && error.contains("b1d2") && error.contains("f3d2"));
+        assert_eq!(knights.parse_move_text("Nbd2").unwrap().to_string(), "b1d2");
+        assert_eq!(knights.parse_move_text("N3d2").unwrap().to_string(), "f3d2");
+        assert_eq!(knights.parse_move_text("Nf3xd4").unwrap_err().to_string(), "no move matches \"Nf3xd4\"");
+        assert_eq!(knights.parse_move_text("exd5+").unwrap().to_string(), "e4d5");
+
+        let mut pinned = game("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1", presets::standard_chess());
+        let error = pinned.parse_move_text("Nc3").unwrap_err().to_string();
+        assert!(error.contains("illegal") && error.contains("e2c3"));
+
+        let mut promotion = game("8/4P3/8/8/8/8/8/k3K3 w - - 0 1", presets::standard_chess());
+        assert!(promotion.parse_move_text("e8").unwrap_err().to_string().contains("e7e8q"));
+        assert_eq!(promotion.parse_move_text("e8=N").unwrap().to_string(), "e7e8n");
+        let mut castling = game("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", presets::standard_chess());
+        assert_eq!(castling.parse_move_text("O-O").unwrap().to_string(), "e1g1");
+        assert_eq!(castling.parse_move_text("0-0-0").unwrap().to_string(), "e1c1");
+
+        let mut fairy = game("4k3/8/8/8/3q4/4p3/4L3/S3K3 w - - 0 1", presets::fairy_chess());
+        assert_eq!(fairy.parse_move_text("Ls").unwrap().to_string(), "e2s");
+        assert_eq!(fairy.parse_move_text("Ssd4").unwrap().to_string(), "a1sd4");
+        let mut rules = presets::fairy_chess();
+        for player in 0..2 {
+            rules.define(player, PieceDefinition::new("fan", "F", 3).with_betza("W").unwrap());
+        }
+        let mut pushes = game("4k3/8/8/8/3p4/4p3/4F3/4K1H1 w - - 0 1", rules);
+        let square = |text| parse_square(text).unwrap();
+        let up = Move::new(square("e2"), square("e2"), PUSH).with_displacement(square("e3"), square("e7"));
+        let short = Move::new(square("e2"), square("e2"), PUSH).with_displacement(square("e3"), square("e5"));
+        let side = Move::new(square("e3"), square("e3"), PUSH).with_displacement(square("d4"), square("a4"));
+        let ride = Move::new(square("g1"), square("e2"), RIDE);
+        let moves = [up.clone(), short.clone(), side, ride.clone()];
+        assert_eq!(pushes.parse_move_text_among("Fpe3-7", &moves).unwrap(), up);
+        assert_eq!(pushes.parse_move_text_among("Fpe3-5", &moves).unwrap(), short);
+        assert!(pushes.parse_move_text_among("Fpd4-a4", &moves).unwrap_err().to_string().contains("no move matches"));
+        assert_eq!(pushes.parse_move_text_among("Hre2", &moves).unwrap(), ride);
+        assert!(pushes.parse_move_text_among("Hre3", &moves).unwrap_err().to_string().contains("no move matches"));
+        assert_eq!(moves.iter().map(|mv| pushes.move_text_among(mv, &moves)).collect::<Vec<_>>(), ["Fpe3-7", "Fpe3-5", "Ppd4-a4", "Hre2"]);
+        assert_eq!(pushes.long_move_text_among(&up, &moves), "Fe2pe3-7");
+        assert_eq!(pushes.parse_move_text_among("Fe2pe3-7", &moves).unwrap(), up);
+        assert_eq!(pushes.parse_move_text_among("Hg1re2", &moves).unwrap(), ride);
+
+        let mut chess960 = game("4k3/8/8/8/8/8/8/RK5R w AH - 0 1", presets::chess960());
+        let queenside = chess960.parse_move_text("O-O-O").unwrap();
+        assert_eq!((queenside.get_to(), chess960.move_text(&queenside)), (&parse_square("c1").unwrap(), "O-O-O".to_string()));
+        let kingside = chess960.parse_move_text("O-O").unwrap();
+        assert_eq!((kingside.get_to(), chess960.move_text(&kingside)), (&parse_square("g1").unwrap(), "O-O".to_string()));
+        let mut long_range = game("4k3/8/8/4R3/8/8/8/R3K3 w - - 0 1", presets::long_castling_chess());
+        let file = long_range.legal_moves().into_iter().find(|mv| mv.get_movement_type() == CASTLING && mv.get_to()[1] == 4).unwrap();
+        assert_eq!(long_range.move_text(&file), "O-O");
+        assert_eq!(long_range.parse_move_text("O-O-O").unwrap().get_to(), &parse_square("c1").unwrap());
+    }
+
+    /// 깊이 `depth`까지의 모든 수가 SAN과 LAN 둘 다 같은 수로 다시 읽히는지.
+    fn assert_round_trips(game: &mut GameState<2>, depth: usize) {
+        if depth == 0 {
+            return;
+        }
+        let legal = game.legal_moves();
+        for mv in legal.iter().cloned() {
+            for text in [game.move_text_among(&mv, &legal), game.long_move_text_among(&mv, &legal)] {
+                assert_eq!(game.parse_move_text_among(&text, &legal).unwrap_or_else(|error| panic!("{text}: {error}")), mv, "{text}");
+            }
+            game.make_move(mv);
+            assert_round_trips(game, depth - 1);
+            game.unmake_move();
+        }
+    }
+
+    #[test]
+    fn move_text_round_trips_over_perft() {
+        let game = |fen: &str, rules| GameState::from_fen(fen, presets::standard_board(), Arc::new(rules)).unwrap();
+        assert_round_trips(&mut game("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", presets::standard_chess()), 3);
+        assert_round_trips(&mut game("4k3/8/8/8/3q4/4p3/4L3/S3K3 w - - 0 1", presets::fairy_chess()), 3);
+        assert_round_trips(&mut game("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1", presets::standard_chess()), 3);
+
+        let mut knights = game("4k3/8/8/3p4/4P3/5N2/8/1N2K3 w - - 0 1", presets::standard_chess());
+        let texts: Vec<_> = knights.legal_moves().iter().map(|mv| knights.move_text(mv)).collect();
+        assert!(["Nbd2", "Nfd2", "exd5", "Ng5", "Kd2"].iter().all(|text| texts.contains(&text.to_string())));
+        let mut rooks = game("4k3/8/R7/8/8/8/8/R3K3 w - - 0 1", presets::standard_chess());
+        let mv = rooks.parse_move_text("R1a3").unwrap();
+        assert_eq!((rooks.move_text(&mv), rooks.long_move_text(&mv)), ("R1a3".to_string(), "Ra1-a3".to_string()));
+        let mut mate = game("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1", presets::standard_chess());
+        let texts: Vec<_> = mate.legal_moves().iter().map(|mv| mate.move_text(mv)).collect();
+        assert!(texts.contains(&"Qxf7#".to_string()) && texts.contains(&"Bxf7+".to_string()));
+        let mut promotion = game("8/4P3/8/8/8/8/8/k3K3 w - - 0 1", presets::standard_chess());
+        let texts: Vec<_> = promotion.legal_moves().iter().map(|mv| promotion.move_text(mv)).collect();
+        assert!(texts.contains(&"e8=Q".to_string()) && texts.contains(&"e8=N".to_string()));
+        let mut fairy = game("4k3/8/8/8/3q4/4p3/4L3/S3K3 w - - 0 1", presets::fairy_chess());
+        let texts: Vec<_> = fairy.legal_moves().iter().map(|mv| fairy.move_text(mv)).collect();
+        assert!(texts.contains(&"Ls".to_string()) && texts.contains(&"Ssd4".to_string()));
+
+        let mut rules = presets::standard_chess();
+        for player in 0..2 {
+            rules.define(player, PieceDefinition::new("amazon", "Am", 12).with_betza("QN").unwrap());
+        }
+        let mut amazons = GameState::new(presets::standard_board(), Arc::new(rules));
+        for (square, player, name) in [("e1", 0, "king"), ("e8", 1, "king"), ("b1", 0, "amazon"), ("f1", 0, "amazon")] {
+            let piece = amazons.get_rules().create_piece(player, name).unwrap();
+            amazons.put_piece(parse_square(square).unwrap(), piece);
+        }
+        let mv = amazons.parse_move_text("AMbd2").unwrap();
+        assert_eq!(amazons.move_text(&mv), "AMbd2");
+        assert_round_trips(&mut amazons, 1);
+    }
+
+    #[test]
+    fn move_text_round_trips_over_kiwipete() {
+        let mut kiwipete = GameState::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        assert_round_trips(&mut kiwipete, 3);
+    }
+
+    #[test]
+    fn move_text_round_trips_over_drops() {
+        let mut crazyhouse = GameState::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 0 1", presets::standard_board(), Arc::new(presets::crazyhouse())).unwrap();
+        crazyhouse.set_hand_count(0, "knight", 1);
+        crazyhouse.set_hand_count(1, "pawn", 1);
+        assert_round_trips(&mut crazyhouse, 3);
+    }
+}
@@ -0,0 +1,462 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use crate::{
+    game::GameState,
+    moves::Move,
+    ownership::Owner,
+    piece::{Piece, PlayerId},
+    position::AbsolutePosition,
+    tt::{Bound, TableEntry, TranspositionTable},
+};
+
+pub const INFINITY: i32 = 1_000_000;
+/// 메이트 점수. 메이트까지 남은 수만큼 빼서 빠른 메이트를 더 좋게 봅니다.
+pub const MATE: i32 = 100_000;
+const MAX_PLY: usize = 128;
+const ASPIRATION_WINDOW: i32 = 50;
+/// 이 노드 수마다 시간을 확인합니다.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// ## SearchLimits
+/// 탐색을 멈출 조건. 아무것도 정하지 않으면 `MAX_PLY` 깊이까지 찾습니다.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<usize>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+/// ## SearchResult
+/// 마지막으로 끝까지 찾은 깊이의 결과.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult<const D: usize> {
+    pub best_move: Option<Move<D>>,
+    pub score: i32,
+    pub depth: usize,
+    pub nodes: u64,
+    pub pv: Vec<Move<D>>,
+}
+
+/// ## SearchShared
+/// 한 번의 탐색에서 모든 탐색 스레드가 같이 보는 값. 멈춤 신호와 전체 노드 수.
+#[derive(Debug, Default)]
+pub struct SearchShared {
+    stop: AtomicBool,
+    nodes: AtomicU64,
+}
+
+impl SearchShared {
+    #[inline(always)]
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    pub fn nodes(&self) -> u64 {
+        self.nodes.load(Ordering::Relaxed)
+    }
+}
+
+/// ## Searcher
+/// negamax 알파베타 탐색기.
+/// 반복 심화 + aspiration window, Zobrist 키 트랜스포지션 테이블, 킬러/히스토리 수 정렬, 잡는 수만 보는 정지 탐색을 합니다.
+/// 기물 점수는 `Piece::piece_score`(기물 파일의 `point`)를 씁니다.
+/// 트랜스포지션 테이블은 `Arc`로 들고 있어서 여러 탐색기가 같이 쓸 수 있습니다. (`smp::LazySmp`)
+pub struct Searcher<const D: usize> {
+    tt: Arc<TranspositionTable>,
+    shared: Arc<SearchShared>,
+    thread_id: usize,
+    killers: Vec<[Option<Move<D>>; 2]>,
+    history: HashMap<(AbsolutePosition<D>, AbsolutePosition<D>), i32>,
+    pv: Vec<Vec<Move<D>>>,
+    path: Vec<u64>,
+    nodes: u64,
+    limits: SearchLimits,
+    start: Instant,
+}
+
+impl<const D: usize> Searcher<D> {
+    pub fn new(tt_megabytes: usize) -> Self {
+        Self::with_table(Arc::new(TranspositionTable::new(tt_megabytes)))
+    }
+
+    pub fn with_table(tt: Arc<TranspositionTable>) -> Self {
+        Self {
+            tt,
+            shared: Arc::new(SearchShared::default()),
+            thread_id: 0,
+            killers: vec![[None, None]; MAX_PLY],
+            history: HashMap::new(),
+            pv: vec![Vec::new(); MAX_PLY + 1],
+            path: Vec::new(),
+            nodes: 0,
+            limits: SearchLimits::default(),
+            start: Instant::now(),
+        }
+    }
+
+    #[inline(always)]
+    pub fn get_tt(&self) -> &TranspositionTable {
+        &self.tt
+    }
+
+    /// 반복 심화로 찾습니다. 시간이나 노드 수가 다 되면 마지막으로 끝낸 깊이의 결과를 돌려줍니다.
+    pub fn search(&mut self, game: &mut GameState<D>, limits: SearchLimits) -> SearchResult<D> {
+        self.search_shared(game, limits, Arc::new(SearchShared::default()), 0)
+    }
+
+    /// 다른 탐색 스레드와 `shared`를 같이 쓰면서 찾습니다.
+    /// 노드 수 제한은 모든 스레드의 노드를 합쳐서 봅니다.
+    /// 홀수 번 스레드는 한 단계 더 깊이 찾아서 스레드끼리 같은 일을 덜 하게 합니다.
+    pub fn search_shared(&mut self, game: &mut GameState<D>, limits: SearchLimits, shared: Arc<SearchShared>, thread_id: usize) -> SearchResult<D> {
+        self.limits = limits;
+        self.start = Instant::now();
+        self.shared = shared;
+        self.thread_id = thread_id;
+        self.nodes = 0;
+        self.killers.iter_mut().for_each(|killers| *killers = [None, None]);
+        self.history.clear();
+
+        let max_depth = limits.depth.unwrap_or(MAX_PLY).clamp(1, MAX_PLY);
+        let mut result = SearchResult { best_move: None, score: 0, depth: 0, nodes: 0, pv: Vec::new() };
+        for depth in (1..=max_depth).map(|depth| (depth + thread_id % 2).min(max_depth)) {
+            if depth <= result.depth {
+                continue;
+            }
+            let score = self.aspiration(game, depth, result.score);
+            if self.stopped() && result.best_move.is_some() {
+                break;
+            }
+            result = SearchResult {
+                best_move: self.pv[0].first().cloned(),
+                score,
+                depth,
+                nodes: self.nodes,
+                pv: self.pv[0].clone(),
+            };
+            if self.stopped() || score.abs() >= MATE - MAX_PLY as i32 {
+                break;
+            }
+        }
+        result.nodes = self.shared.nodes();
+        result
+    }
+
+    #[inline(always)]
+    fn stopped(&self) -> bool {
+        self.shared.is_stopped()
+    }
+
+    fn count_node(&mut self) {
+        self.nodes += 1;
+        self.shared.nodes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 지난 깊이의 점수 주변 좁은 창으로 먼저 찾고, 벗어나면 그쪽을 열어서 다시 찾습니다.
+    fn aspiration(&mut self, game: &mut GameState<D>, depth: usize, previous: i32) -> i32 {
+        let (mut alpha, mut beta) = if depth >= 3 {
+            (previous - ASPIRATION_WINDOW, previous + ASPIRATION_WINDOW)
+        } else {
+            (-INFINITY, INFINITY)
+        };
+        loop {
+            let score = self.negamax(game, depth, 0, alpha, beta);
+            if self.stopped() {
+                return score;
+            }
+            if score <= alpha {
+                alpha = -INFINITY;
+            } else if score >= beta {
+                beta = INFINITY;
+            } else {
+                return score;
+            }
+        }
+    }
+
+    fn should_stop(&self) -> bool {
+        if self.stopped() {
+            return true;
+        }
+        let out_of_nodes = self.limits.nodes.is_some_and(|nodes| self.shared.nodes() >= nodes);
+        let out_of_time = self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) && self.limits.time.is_some_and(|time| self.start.elapsed() >= time);
+        if out_of_nodes || out_of_time {
+            self.shared.stop();
+        }
+        self.stopped()
+    }
+
+    fn negamax(&mut self, game: &mut GameState<D>, depth: usize, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv[ply].clear();
+        if ply > 0 && self.should_stop() {
+            return 0;
+        }
+        let key = game.hash();
+        if ply > 0 && self.path.contains(&key) {
+            return 0;
+        }
+        // 안개 게임에서는 royal 기물을 실제로 잡을 수 있습니다.
+        if game.lost_royal(game.side_to_move()) {
+            return -MATE + ply as i32;
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(game, ply, alpha, beta);
+        }
+        self.count_node();
+
+        let entry = self.tt.probe(key);
+        if let Some(entry) = entry.filter(|entry| ply > 0 && entry.depth as usize >= depth) {
+            let score = score_from_tt(entry.score, ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if cutoff {
+                return score;
+            }
+        }
+
+        let player = game.side_to_move();
+        let mut moves = game.legal_moves();
+        if moves.is_empty() {
+            return if game.in_check(player) { -MATE + ply as i32 } else { 0 };
+        }
+        self.order_moves(game, &mut moves, entry, ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        self.path.push(key);
+        for mv in moves {
+            let score = self.chance_average(game, &mv, player, alpha, beta, |searcher, game, alpha, beta| {
+                searcher.negamax(game, depth - 1, ply + 1, alpha, beta)
+            });
+            if self.stopped() {
+                break;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv.clone());
+            }
+            if score > alpha {
+                alpha = score;
+                let mut pv = vec![mv.clone()];
+                pv.extend(self.pv[ply + 1].iter().cloned());
+                self.pv[ply] = pv;
+            }
+            if alpha >= beta {
+                if !mv.is_capture() {
+                    self.store_killer(ply, mv.clone());
+                    *self.history.entry((*mv.get_from(), *mv.get_to())).or_insert(0) += (depth * depth) as i32;
+                }
+                break;
+            }
+        }
+        self.path.pop();
+        if self.stopped() {
+            return 0;
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt.store(key, TableEntry {
+            score: score_to_tt(best_score, ply),
+            depth: depth.min(u8::MAX as usize) as u8,
+            bound,
+            best_move: best_move.map(|mv| mv.fingerprint()),
+        });
+        best_score
+    }
+
+    /// 잡는 수만 끝까지 따라가서 조용한 국면의 점수를 봅니다.
+    fn quiescence(&mut self, game: &mut GameState<D>, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.count_node();
+        if self.should_stop() {
+            return 0;
+        }
+        if game.lost_royal(game.side_to_move()) {
+            return -MATE + ply as i32;
+        }
+        let stand_pat = evaluate(game);
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let player = game.side_to_move();
+        let mut captures: Vec<_> = game.legal_moves().into_iter().filter(Move::is_capture).collect();
+        captures.sort_by_cached_key(|mv| -capture_score(game, mv));
+        for mv in captures {
+            let score = self.chance_average(game, &mv, player, alpha, beta, |searcher, game, alpha, beta| {
+                searcher.quiescence(game, ply + 1, alpha, beta)
+            });
+            if self.stopped() {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// `mv`를 둔 뒤의 점수를 `player` 쪽에서 봅니다. 같은 플레이어가 이어 두면 부호를 바꾸지 않습니다.
+    /// 무작위 결과가 여럿인 수는 찬스 노드로 보고, 결과마다 창을 다 열고 찾아서 평균을 냅니다.
+    fn chance_average(
+        &mut self,
+        game: &mut GameState<D>,
+        mv: &Move<D>,
+        player: PlayerId,
+        alpha: i32,
+        beta: i32,
+        mut child: impl FnMut(&mut Self, &mut GameState<D>, i32, i32) -> i32,
+    ) -> i32 {
+        let outcomes = game.chance_outcomes(mv);
+        let (alpha, beta) = if outcomes > 1 { (-INFINITY, INFINITY) } else { (alpha, beta) };
+        let mut total = 0i64;
+        for roll in 0..outcomes {
+            game.make_move(if outcomes > 1 { mv.clone().with_roll(roll) } else { mv.clone() });
+            total += if game.side_to_move() == player {
+                child(self, game, alpha, beta)
+            } else {
+                -child(self, game, -beta, -alpha)
+            } as i64;
+            game.unmake_move();
+        }
+        (total / outcomes as i64) as i32
+    }
+
+    /// 트랜스포지션 테이블의 수, 잡는 수(MVV-LVA), 킬러, 히스토리 순서.
+    fn order_moves(&self, game: &GameState<D>, moves: &mut [Move<D>], entry: Option<TableEntry>, ply: usize) {
+        moves.sort_by_cached_key(|mv| {
+            let score = if entry.is_some_and(|entry| entry.is_move(mv.fingerprint())) {
+                10_000_000
+            } else if mv.is_capture() {
+                1_000_000 + capture_score(game, mv)
+            } else if self.killers[ply].contains(&Some(mv.clone())) {
+                900_000
+            } else {
+                self.history.get(&(*mv.get_from(), *mv.get_to())).copied().unwrap_or(0)
+            };
+            -score
+        });
+    }
+
+    fn store_killer(&mut self, ply: usize, mv: Move<D>) {
+        let killers = &mut self.killers[ply];
+        if killers[0].as_ref() != Some(&mv) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(mv);
+        }
+    }
+}
+
+/// 차례인 플레이어 기준 기물 점수 차이. 공유 기물과 중립 기물은 누구의 것도 아니므로 세지 않습니다.
+pub fn evaluate<const D: usize>(game: &GameState<D>) -> i32 {
+    let player = game.side_to_move();
+    game.get_board().pieces()
+        .map(|(_, piece)| match piece.get_owner() {
+            Owner::Shared | Owner::Neutral => 0,
+            _ if game.is_hostile(player, piece) => -piece.piece_score(),
+            _ => piece.piece_score(),
+        })
+        .sum::<i32>() * 100
+}
+
+/// 잡히는 기물이 비쌀수록, 잡는 기물이 쌀수록 높은 점수.
+fn capture_score<const D: usize>(game: &GameState<D>, mv: &Move<D>) -> i32 {
+    let board = game.get_board();
+    let attacker = board.get(mv.get_from());
+    let victims: i32 = std::iter::once(mv.get_to())
+        .chain(mv.get_captures())
+        .filter_map(|position| board.get(position))
+        .filter(|piece| game.is_hostile(game.side_to_move(), piece))
+        .map(Piece::piece_score)
+        .sum();
+    victims * 100 - attacker.map(Piece::piece_score).unwrap_or(0)
+}
+
+/// 메이트 점수는 지금 노드 기준으로 바꿔서 저장합니다.
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score <= -MATE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::presets;
+
+    fn search(fen: &str, depth: usize) -> SearchResult<2> {
+        let mut game = GameState::from_fen(fen, presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        let hash = game.hash();
+        let result = Searcher::new(1).search(&mut game, SearchLimits { depth: Some(depth), ..SearchLimits::default() });
+        assert_eq!(game.hash(), hash);
+        result
+    }
+
+    #[test]
+    fn finds_back_rank_mate() {
+        let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3);
+        assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+        assert_eq!(result.score, MATE - 1);
+    }
+
+    #[test]
+    fn takes_hanging_queen() {
+        let result = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2);
+        assert_eq!(result.best_move.as_ref().unwrap().to_string(), "d2d5");
+        assert_eq!(result.pv.first(), result.best_move.as_ref());
+    }
+
+    #[test]
+    fn stops_at_node_limit() {
+        let mut game = GameState::from_fen(presets::START_FEN, presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        let result = Searcher::new(1).search(&mut game, SearchLimits { nodes: Some(2_000), ..SearchLimits::default() });
+        assert!(result.best_move.is_some());
+        assert!(result.nodes < 4_000);
+    }
+
+    #[test]
+    fn rabbit_chains_two_captures() {
+        let mut game = GameState::from_fen("4k3/8/8/8/3r4/1q6/8/Y3K3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        let result = Searcher::new(1).search(&mut game, SearchLimits { depth: Some(3), ..SearchLimits::default() });
+        let pv: Vec<_> = result.pv.iter().map(ToString::to_string).collect();
+        assert_eq!(pv[..2], ["a1b3", "b3d4"]);
+        assert!(result.score >= 300);
+    }
+}
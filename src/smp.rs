@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+use anyhow::Result;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use crate::{
+    game::GameState,
+    search::{SearchLimits, SearchResult, SearchShared, Searcher},
+    tt::TranspositionTable,
+};
+
+/// ## LazySmp
+/// 여러 스레드가 같은 국면을 각자 찾으면서 트랜스포지션 테이블만 같이 쓰는 병렬 탐색.
+/// 0번 스레드가 끝나면 나머지도 멈추고, 가장 깊이 끝낸 스레드의 결과를 씁니다. (같으면 0번)
+pub struct LazySmp {
+    tt: Arc<TranspositionTable>,
+    pool: ThreadPool,
+    threads: usize,
+}
+
+impl LazySmp {
+    pub fn new(tt_megabytes: usize, threads: usize) -> Result<Self> {
+        let threads = threads.max(1);
+        Ok(Self {
+            tt: Arc::new(TranspositionTable::new(tt_megabytes)),
+            pool: ThreadPoolBuilder::new().num_threads(threads).thread_name(|id| format!("search-{id}")).build()?,
+            threads,
+        })
+    }
+
+    /// 코어 수만큼 스레드를 씁니다.
+    pub fn with_available_threads(tt_megabytes: usize) -> Result<Self> {
+        let threads = std::thread::available_parallelism().map(usize::from).unwrap_or(1);
+        Self::new(tt_megabytes, threads)
+    }
+
+    #[inline(always)]
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+
+    #[inline(always)]
+    pub fn get_tt(&self) -> &Arc<TranspositionTable> {
+        &self.tt
+    }
+
+    pub fn search<const D: usize>(&self, game: &GameState<D>, limits: SearchLimits) -> SearchResult<D> {
+        let shared = Arc::new(SearchShared::default());
+        let helpers = Mutex::new(Vec::new());
+        let main = self.pool.install(|| {
+            rayon::scope(|scope| {
+                for thread_id in 1..self.threads {
+                    let (shared, helpers, tt) = (Arc::clone(&shared), &helpers, Arc::clone(&self.tt));
+                    let mut game = game.clone();
+                    scope.spawn(move |_| {
+                        let result = Searcher::with_table(tt).search_shared(&mut game, limits, shared, thread_id);
+                        helpers.lock().unwrap().push(result);
+                    });
+                }
+                let result = Searcher::with_table(Arc::clone(&self.tt)).search_shared(&mut game.clone(), limits, Arc::clone(&shared), 0);
+                shared.stop();
+                result
+            })
+        });
+        let nodes = shared.nodes();
+        let mut best = helpers.into_inner().unwrap().into_iter()
+            .filter(|result| result.best_move.is_some())
+            .fold(main, |best, result| if result.depth > best.depth { result } else { best });
+        best.nodes = nodes;
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::presets;
+
+    #[test]
+    fn threads_agree_on_mate() {
+        let game = GameState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", presets::standard_board(), Arc::new(presets::standard_chess())).unwrap();
+        let smp = LazySmp::new(1, 4).unwrap();
+        let result = smp.search(&game, SearchLimits { depth: Some(3), ..SearchLimits::default() });
+        assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+        assert!(smp.get_tt().probe(game.hash()).is_some());
+    }
+}
@@ -0,0 +1,197 @@
+use std::str::FromStr;
+use anyhow::{anyhow, bail, Error, Result};
+use crate::position::RelativePosition;
+
+/// ## Symmetry
+/// 오프셋 하나를 여러 오프셋으로 펼치는 대칭. 이동 정의에 `(1, 2)`와 `all`만 적으면 나이트의 8칸이 됩니다.
+/// - `Permute`: 이 축들의 값을 모든 순서로 바꿔 놓음
+/// - `Flip`: 이 축들의 부호를 모든 조합으로 뒤집음
+/// - `Rotate`: 두 축이 이루는 평면에서 90도씩 돌림
+/// - `Forward`: `axis` 축으로 앞(+)인 것만 남김. `sideways`면 옆(0)도 남깁니다.
+///
+/// 축을 비워 두면 모든 축입니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Symmetry {
+    Permute(Vec<usize>),
+    Flip(Vec<usize>),
+    Rotate(usize, usize),
+    Forward { axis: usize, sideways: bool },
+}
+
+impl Symmetry {
+    /// 모든 축 순서 바꾸기와 모든 부호 뒤집기. D차원 나이트, 킹, 룩을 만듭니다.
+    pub fn all() -> Vec<Self> {
+        vec![Symmetry::Permute(Vec::new()), Symmetry::Flip(Vec::new())]
+    }
+}
+
+/// 기물 파일의 대칭 이름. `all`, `permute`, `flip`, `rotate`, `forward`, `sideways`이고
+/// 축은 `flip(1)`, `rotate(0, 2)`처럼 괄호에 적습니다. `all`은 `permute`와 `flip` 둘을 뜻하므로 `symmetries`로 읽습니다.
+impl FromStr for Symmetry {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let (name, axes) = match text.split_once('(') {
+            Some((name, rest)) => {
+                let axes = rest.strip_suffix(')').ok_or_else(|| anyhow!("unclosed symmetry {text:?}"))?;
+                let axes = axes.split(',').filter(|axis| !axis.trim().is_empty())
+                    .map(|axis| axis.trim().parse().map_err(|_| anyhow!("bad axis {axis:?} in {text:?}")))
+                    .collect::<Result<Vec<usize>>>()?;
+                (name.trim(), axes)
+            }
+            None => (text, Vec::new()),
+        };
+        let axis = || axes.first().copied().unwrap_or(0);
+        Ok(match name {
+            "permute" => Symmetry::Permute(axes),
+            "flip" => Symmetry::Flip(axes),
+            "rotate" => match axes[..] {
+                [] => Symmetry::Rotate(0, 1),
+                [first, second] => Symmetry::Rotate(first, second),
+                _ => bail!("rotate needs two axes: {text:?}"),
+            },
+            "forward" => Symmetry::Forward { axis: axis(), sideways: false },
+            "sideways" => Symmetry::Forward { axis: axis(), sideways: true },
+            _ => bail!("unknown symmetry {text:?}"),
+        })
+    }
+}
+
+/// 대칭 이름들을 읽습니다. `all`은 `permute`와 `flip`으로 펼칩니다.
+pub fn symmetries(names: &[String]) -> Result<Vec<Symmetry>> {
+    let mut symmetries = Vec::new();
+    for name in names {
+        if name.trim() == "all" {
+            symmetries.extend(Symmetry::all());
+        } else {
+            symmetries.push(name.parse()?);
+        }
+    }
+    Ok(symmetries)
+}
+
+/// 축 목록. 비어 있으면 모든 축입니다.
+fn axes_or_all<const D: usize>(axes: &[usize]) -> Vec<usize> {
+    if axes.is_empty() { (0..D).collect() } else { axes.iter().copied().filter(|&axis| axis < D).collect() }
+}
+
+/// 처음 나온 순서를 지키면서 겹치는 오프셋을 뺍니다.
+fn push_unique<const D: usize>(offsets: &mut Vec<RelativePosition<D>>, offset: RelativePosition<D>) {
+    if !offsets.contains(&offset) {
+        offsets.push(offset);
+    }
+}
+
+impl<const D: usize> RelativePosition<D> {
+    /// `axes` 축들의 값을 모든 순서로 바꿔 놓은 오프셋들. 자기 자신이 처음입니다.
+    pub fn permuted(&self, axes: &[usize]) -> Vec<Self> {
+        let axes = axes_or_all::<D>(axes);
+        let mut offsets = vec![*self];
+        let mut checked = 0;
+        while checked < offsets.len() {
+            let offset = offsets[checked];
+            for (index, &first) in axes.iter().enumerate() {
+                for &second in &axes[index + 1..] {
+                    let mut swapped = offset;
+                    swapped.swap(first, second);
+                    push_unique(&mut offsets, swapped);
+                }
+            }
+            checked += 1;
+        }
+        offsets
+    }
+
+    /// `axes` 축들의 부호를 모든 조합으로 뒤집은 오프셋들. 0인 축은 뒤집어도 같으므로 한 번만 나옵니다.
+    pub fn flipped(&self, axes: &[usize]) -> Vec<Self> {
+        axes_or_all::<D>(axes).into_iter().fold(vec![*self], |offsets, axis| {
+            let mut flipped = Vec::new();
+            for offset in offsets {
+                let mut negated = offset;
+                negated[axis] = -negated[axis];
+                push_unique(&mut flipped, offset);
+                push_unique(&mut flipped, negated);
+            }
+            flipped
+        })
+    }
+
+    /// `first`와 `second` 축이 이루는 평면에서 90도씩 네 번 돌린 오프셋들.
+    pub fn rotated(&self, first: usize, second: usize) -> Vec<Self> {
+        let mut offsets = vec![*self];
+        if first >= D || second >= D || first == second {
+            return offsets;
+        }
+        let mut offset = *self;
+        for _ in 0..3 {
+            (offset[first], offset[second]) = (-offset[second], offset[first]);
+            push_unique(&mut offsets, offset);
+        }
+        offsets
+    }
+
+    /// `axis` 축으로 앞인지. `sideways`면 그 축으로 움직이지 않는 것도 앞으로 칩니다.
+    #[inline(always)]
+    pub fn is_forward(&self, axis: usize, sideways: bool) -> bool {
+        self[axis] > 0 || (sideways && self[axis] == 0)
+    }
+
+    /// 대칭들을 차례로 적용해 펼친 오프셋들. 겹치는 것과 `(0, 0, ..)`은 뺍니다.
+    pub fn symmetries(&self, symmetries: &[Symmetry]) -> Vec<Self> {
+        let offsets = symmetries.iter().fold(vec![*self], |offsets, symmetry| {
+            let mut expanded = Vec::new();
+            for offset in offsets {
+                let images = match symmetry {
+                    Symmetry::Permute(axes) => offset.permuted(axes),
+                    Symmetry::Flip(axes) => offset.flipped(axes),
+                    Symmetry::Rotate(first, second) => offset.rotated(*first, *second),
+                    Symmetry::Forward { axis, sideways } => {
+                        if *axis < D && offset.is_forward(*axis, *sideways) { vec![offset] } else { Vec::new() }
+                    }
+                };
+                for image in images {
+                    push_unique(&mut expanded, image);
+                }
+            }
+            expanded
+        });
+        offsets.into_iter().filter(|offset| offset.iter().any(|&value| value != 0)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{file_scan::FileFormat, rules::PieceDefinition};
+
+    #[test]
+    fn symmetries_expand_knights_and_kings() {
+        let knight = RelativePosition::from([1, 2]).symmetries(&Symmetry::all());
+        assert_eq!(knight.len(), 8);
+        assert!(knight.contains(&RelativePosition::from([-2, 1])));
+        let knight = RelativePosition::from([0, 1, 2]).symmetries(&Symmetry::all());
+        assert_eq!(knight.len(), 24);
+        let king: Vec<_> = [[1, 0, 0], [1, 1, 0], [1, 1, 1]].into_iter()
+            .flat_map(|offset| RelativePosition::from(offset).symmetries(&Symmetry::all()))
+            .collect();
+        assert_eq!(king.len(), 26);
+
+        let wazir = RelativePosition::from([1, 0]).symmetries(&["rotate".parse().unwrap()]);
+        assert_eq!(wazir, [[1, 0], [0, 1], [-1, 0], [0, -1]].map(RelativePosition::from));
+        let flat = RelativePosition::from([1, 2, 0]).symmetries(&[Symmetry::Rotate(0, 1)]);
+        assert!(flat.iter().all(|offset| offset[2] == 0) && flat.len() == 4);
+
+        let names = ["all".to_string(), "forward".to_string()];
+        let forward_knight = RelativePosition::from([1, 2]).symmetries(&symmetries(&names).unwrap());
+        assert_eq!(forward_knight.len(), 4);
+        let sideways = RelativePosition::from([1, 0]).symmetries(&symmetries(&["all".to_string(), "sideways(0)".to_string()]).unwrap());
+        assert_eq!(sideways, [[1, 0], [0, 1], [0, -1]].map(RelativePosition::from));
+        assert!("spin".parse::<Symmetry>().is_err() && "rotate(0)".parse::<Symmetry>().is_err());
+
+        let json = r#"{"name": "knight", "short_name": "N", "point": 3, "code": "",
+            "moves": [{"movement_type": ["move", "capture"], "offset": [1, 2], "symmetries": ["all"]}]}"#;
+        let knight = PieceDefinition::<2>::from_file_format(&json.parse::<FileFormat>().unwrap()).unwrap();
+        assert_eq!(knight.get_independent_moves().len(), 8);
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::{anyhow, bail, Result};
+use crate::{
+    file_scan::TransformFormat,
+    game::GameState,
+    hooks::HookEvent,
+    movement::MOVED_STATE,
+    moves::Move,
+    ownership::Owner,
+    piece::Piece,
+    position::AbsolutePosition,
+};
+
+/// 이 기물이 지금 어떤 기물처럼 움직이는지. `Transformation::MoveAs`가 적습니다.
+pub const MOVES_AS_STATE: &str = "moves_as";
+/// 기물이 보드에 처음 놓이거나(`put_piece`, 드롭) 마지막으로 변신한 ply.
+pub const TIMER_STATE: &str = "timer";
+
+/// ## TransformTrigger
+/// 변신이 일어나는 때.
+/// - `Move`: 이 기물이 자리를 옮길 때마다
+/// - `Capture`: 이 기물이 적 기물을 잡았을 때
+/// - `Enter`: 이 기물이 이 랭크들 중 하나에 도착했을 때. 랭크는 자기 쪽 끝에서 셉니다.
+/// - `EnterRegion`: 이 기물이 자기에게 그 이름의 구역인 칸에 도착했을 때
+/// - `Timer`: 놓이거나 마지막으로 변신하고 이만큼 ply가 지났을 때. 차례가 넘어갈 때 봅니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransformTrigger {
+    Move,
+    Capture,
+    Enter(Vec<usize>),
+    EnterRegion(String),
+    Timer(usize),
+}
+
+/// ## Transformation
+/// 변신해서 바뀌는 것.
+/// - `Become`: 같은 주인의 다른 기물이 됨
+/// - `BecomeCaptured`: 방금 잡은 기물과 같은 종류가 됨
+/// - `Defect`: 다음 플레이어의 기물이 됨 (크리미널)
+/// - `MoveAs`: 종류는 그대로 두고 그 기물처럼 움직임
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transformation {
+    Become(String),
+    BecomeCaptured,
+    Defect,
+    MoveAs(String),
+}
+
+/// ## TransformRule
+/// 기물 정의에 붙는 변신 규칙 하나.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransformRule {
+    pub trigger: TransformTrigger,
+    pub result: Transformation,
+}
+
+impl TransformRule {
+    pub fn new(trigger: TransformTrigger, result: Transformation) -> Self {
+        Self { trigger, result }
+    }
+}
+
+/// 기물 파일의 변신 규칙. `trigger`는 `move`, `capture`, `enter`(`ranks` 또는 `region`), `timer`(`plies`)이고
+/// `result`는 `become`(`name`), `become_captured`, `defect`, `move_as`(`name`)입니다.
+impl TransformRule {
+    pub fn from_format(format: &TransformFormat) -> Result<Self> {
+        let trigger = match format.get_trigger().as_str() {
+            "move" => TransformTrigger::Move,
+            "capture" => TransformTrigger::Capture,
+            "enter" => match format.get_region() {
+                Some(region) => TransformTrigger::EnterRegion(region.clone()),
+                None => TransformTrigger::Enter(format.get_ranks().clone()),
+            },
+            "timer" => TransformTrigger::Timer(format.get_plies().ok_or_else(|| anyhow!("timer transform needs plies"))?),
+            trigger => bail!("unknown transform trigger {trigger:?}"),
+        };
+        let name = || format.get_name().cloned().ok_or_else(|| anyhow!("{} transform needs a name", format.get_result()));
+        let result = match format.get_result().as_str() {
+            "become" => Transformation::Become(name()?),
+            "become_captured" => Transformation::BecomeCaptured,
+            "defect" => Transformation::Defect,
+            "move_as" => Transformation::MoveAs(name()?),
+            result => bail!("unknown transform result {result:?}"),
+        };
+        Ok(Self::new(trigger, result))
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// 수를 둔 기물의 `Move`, `Capture`, `Enter`, `EnterRegion` 변신을 처리합니다.
+    /// 프로모션한 기물은 프로모션하기 전 기물(`promoted_from`)의 규칙을 따릅니다. (크리미널)
+    pub(crate) fn run_move_transforms(&mut self, mv: &Move<D>, captured: &[(AbsolutePosition<D>, Piece)], promoted_from: Option<&str>) -> Vec<HookEvent<D>> {
+        if mv.is_drop() || mv.is_pass() {
+            return Vec::new();
+        }
+        let position = if mv.relocates() { *mv.get_to() } else { *mv.get_from() };
+        let Some(piece) = self.get_board().get(&position) else {
+            return Vec::new();
+        };
+        let rules = self.get_rules().clone();
+        let definition = match promoted_from {
+            Some(name) => rules.get(rules.definition_player(piece.get_owner()), name),
+            None => rules.get_definition(piece),
+        };
+        let Some(definition) = definition.filter(|definition| !definition.get_transforms().is_empty()) else {
+            return Vec::new();
+        };
+        let player = self.controller(piece);
+        let victim = captured.iter().map(|(_, piece)| piece).rfind(|victim| self.is_hostile(player, victim)).cloned();
+        let mut events = Vec::new();
+        for rule in definition.get_transforms() {
+            let fires = match &rule.trigger {
+                TransformTrigger::Move => mv.relocates(),
+                TransformTrigger::Capture => victim.is_some(),
+                TransformTrigger::Enter(ranks) => mv.relocates() && ranks.contains(&self.rank_of(player, &position)),
+                TransformTrigger::EnterRegion(region) => mv.relocates() && self.in_region(region, player, &position),
+                TransformTrigger::Timer(_) => false,
+            };
+            if fires {
+                events.extend(self.transform(&position, &rule.result, victim.as_ref()));
+            }
+        }
+        events
+    }
+
+    /// 시간이 다 된 기물들의 `Timer` 변신을 처리합니다. `make_move`가 차례를 넘길 때마다 부릅니다.
+    /// 보드는 순서가 없으므로 칸 순서대로 처리해서 기보가 항상 같게 적히게 합니다.
+    pub(crate) fn run_timers(&mut self) -> Vec<HookEvent<D>> {
+        let rules = self.get_rules().clone();
+        if !rules.has_timers() {
+            return Vec::new();
+        }
+        let ply = self.ply();
+        let mut due: Vec<_> = self.get_board().pieces()
+            .filter_map(|(position, piece)| {
+                let start = piece.get_state().get(TIMER_STATE).and_then(|start| start.parse().ok()).unwrap_or(0);
+                let definition = rules.get_definition(piece)?;
+                let rule = definition.get_transforms().iter().find(|rule| matches!(rule.trigger, TransformTrigger::Timer(plies) if ply >= start + plies))?;
+                Some((*position, rule.result.clone()))
+            })
+            .collect();
+        due.sort_unstable_by_key(|(position, _)| *position);
+        due.into_iter().filter_map(|(position, result)| self.transform(&position, &result, None)).collect()
+    }
+
+    /// `position`의 기물을 변신시킵니다. 새 기물은 움직인 적이 있는지를 이어받고 타이머는 지금부터 셉니다.
+    /// 움직임만 바꿀 때(`MoveAs`)도 타이머를 다시 셉니다. 그러지 않으면 차례마다 다시 변신합니다.
+    fn transform(&mut self, position: &AbsolutePosition<D>, result: &Transformation, victim: Option<&Piece>) -> Option<HookEvent<D>> {
+        let piece = self.get_board().get(position)?.clone();
+        let (name, owner) = match result {
+            Transformation::Become(name) => (name.clone(), piece.get_owner()),
+            Transformation::BecomeCaptured => (victim?.get_name().clone(), piece.get_owner()),
+            Transformation::Defect => {
+                let player = piece.get_owner().player()?;
+                (piece.get_name().clone(), Owner::Player(self.next_player(player)))
+            }
+            Transformation::MoveAs(name) => {
+                let ply = self.ply().to_string();
+                self.update_piece(position, |piece| {
+                    piece.get_state_mut().set(MOVES_AS_STATE, name.as_str());
+                    piece.get_state_mut().set(TIMER_STATE, ply);
+                });
+                return Some(HookEvent::MovesAs { position: *position, name: name.clone() });
+            }
+        };
+        let rules = self.get_rules().clone();
+        let mut changed = rules.create_piece(rules.definition_player(owner), &name)?;
+        changed.set_owner(owner);
+        if piece.get_state().get(MOVED_STATE).is_some() {
+            changed.get_state_mut().set(MOVED_STATE, "true");
+        }
+        changed.get_state_mut().set(TIMER_STATE, self.ply().to_string());
+        self.put_piece(*position, changed);
+        let owner = (owner != piece.get_owner()).then_some(owner);
+        Some(HookEvent::Transformed { position: *position, into: name, owner })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{
+        fen::parse_square,
+        movement::{IndependentMove, CAPTURE, MOVE},
+        position::RelativePosition,
+        presets,
+        rules::{PieceDefinition, Ruleset},
+    };
+
+    fn play(game: &mut GameState<2>, text: &str) {
+        let mv = game.legal_moves().into_iter().find(|mv| mv.to_string() == text).unwrap();
+        game.make_move(mv);
+    }
+
+    #[test]
+    fn pieces_promote_defect_hatch_and_copy() {
+        let mut game = GameState::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", presets::standard_board(), Arc::new(presets::rebel_chess())).unwrap();
+        assert!(game.legal_moves().iter().any(|mv| mv.to_string() == "a7a8k"));
+
+        let mut game = GameState::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", presets::standard_board(), Arc::new(presets::criminal_chess())).unwrap();
+        game.set_hash_check(true);
+        let hash = game.hash();
+        play(&mut game, "a7a8q");
+        assert_eq!(game.history().last().unwrap().to_string(), "a7a8q;a8~queen/1");
+        assert_eq!(game.get_board().get(&parse_square("a8").unwrap()).unwrap().get_owner(), Owner::Player(1));
+        game.unmake_move();
+        assert_eq!(game.hash(), hash);
+
+        let mut rules = Ruleset::new(2);
+        let egg = PieceDefinition::new("egg", "E", 1).with_transform(TransformRule::new(TransformTrigger::Timer(2), Transformation::Become("knight".to_string())));
+        let copycat = [[1, 0], [-1, 0], [0, 1], [0, -1], [1, 1], [1, -1], [-1, 1], [-1, -1]].into_iter()
+            .fold(PieceDefinition::new("copycat", "C", 2), |copycat, offset| {
+                copycat.with_independent_move(IndependentMove::new([MOVE, CAPTURE], RelativePosition::from(offset)))
+            })
+            .with_transform(TransformRule::new(TransformTrigger::Capture, Transformation::BecomeCaptured));
+        for player in 0..2 {
+            rules.define(player, presets::king());
+            rules.define(player, presets::knight());
+            rules.define(player, presets::rook());
+            rules.define(player, egg.clone());
+            rules.define(player, copycat.clone());
+        }
+        let mut game = GameState::from_fen("4k3/8/8/8/8/8/1r6/CE2K3 w - - 0 1", presets::standard_board(), Arc::new(rules)).unwrap();
+        game.set_hash_check(true);
+        play(&mut game, "a1b2");
+        assert_eq!(game.history().last().unwrap().to_string(), "a1b2;b2~rook");
+        play(&mut game, "e8e7");
+        assert_eq!(game.history().last().unwrap().to_string(), "e8e7;b1~knight");
+        assert_eq!(game.get_board().get(&parse_square("b1").unwrap()).unwrap().get_name(), "knight");
+        game.unmake_move();
+        assert_eq!(game.get_board().get(&parse_square("b1").unwrap()).unwrap().get_name(), "egg");
+    }
+
+    #[test]
+    fn timers_count_from_placement() {
+        let mut rules = Ruleset::new(2);
+        let egg = PieceDefinition::new("egg", "E", 1).with_transform(TransformRule::new(TransformTrigger::Timer(2), Transformation::Become("knight".to_string())));
+        let chameleon = PieceDefinition::new("chameleon", "M", 1).with_transform(TransformRule::new(TransformTrigger::Timer(2), Transformation::MoveAs("knight".to_string())));
+        for player in 0..2 {
+            rules.define(player, presets::king());
+            rules.define(player, presets::knight());
+            rules.define(player, egg.clone());
+            rules.define(player, chameleon.clone());
+        }
+        let mut game = GameState::from_fen("4k3/8/8/8/8/8/8/M3K3 w - - 0 1", presets::standard_board(), Arc::new(rules)).unwrap();
+        game.set_hash_check(true);
+        game.set_hand_count(0, "egg", 1);
+        play(&mut game, "e1e2");
+        assert_eq!(game.history().last().unwrap().to_string(), "e1e2");
+        play(&mut game, "e8e7");
+        assert_eq!(game.history().last().unwrap().to_string(), "e8e7;a1:knight");
+        play(&mut game, "E@c3");
+        assert_eq!(game.history().last().unwrap().to_string(), "E@c3");
+        play(&mut game, "e7e8");
+        assert_eq!(game.history().last().unwrap().to_string(), "e7e8;a1:knight;c3~knight");
+
+        assert_eq!(Owner::Team(1).to_string(), "t1");
+        assert_eq!(Owner::Neutral.to_string(), "neutral");
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// ## Bound
+/// 저장한 점수가 정확한 값인지, 아래/위 한계인지.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+/// ## TableEntry
+/// 트랜스포지션 테이블에서 꺼낸 값.
+/// 수는 `Move::fingerprint`의 아래 21비트만 저장하고, 꺼낼 때 둘 수 있는 수 중에서 다시 찾습니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableEntry {
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    pub best_move: Option<u32>,
+}
+
+const MOVE_BITS: u32 = 21;
+const MOVE_MASK: u64 = (1 << MOVE_BITS) - 1;
+
+impl TableEntry {
+    /// 점수 32비트 | 깊이 8비트 | bound 2비트 | 수 있음 1비트 | 수 21비트
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+        let best_move = match self.best_move {
+            Some(fingerprint) => (1 << MOVE_BITS) | (fingerprint as u64 & MOVE_MASK),
+            None => 0,
+        };
+        (self.score as u32 as u64) | ((self.depth as u64) << 32) | (bound << 40) | (best_move << 42)
+    }
+
+    fn unpack(data: u64) -> Option<Self> {
+        let bound = match (data >> 40) & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        let best_move = data >> 42;
+        Some(Self {
+            score: data as u32 as i32,
+            depth: (data >> 32) as u8,
+            bound,
+            best_move: (best_move >> MOVE_BITS & 1 == 1).then_some((best_move & MOVE_MASK) as u32),
+        })
+    }
+
+    /// 저장된 수와 같은 수인지. 저장할 때 아래 비트만 남기므로 아래 비트만 비교합니다.
+    pub fn is_move(&self, fingerprint: u32) -> bool {
+        self.best_move == Some((fingerprint as u64 & MOVE_MASK) as u32)
+    }
+}
+
+/// ## TranspositionTable
+/// Zobrist 키로 찾는 탐색 결과 캐시. 칸 하나에 (키 ^ 값, 값) 두 `u64`를 둡니다.
+/// 락 없이 여러 스레드가 같이 씁니다. 두 값이 다른 스레드의 쓰기와 섞여서 찢어지면
+/// 키 ^ 값이 키와 맞지 않게 되므로 그냥 없는 칸으로 봅니다.
+#[derive(Debug)]
+pub struct TranspositionTable {
+    entries: Vec<[AtomicU64; 2]>,
+    mask: usize,
+}
+
+impl TranspositionTable {
+    /// 대략 `megabytes` MB 크기의 테이블. 칸 수는 2의 거듭제곱으로 맞춥니다.
+    pub fn new(megabytes: usize) -> Self {
+        let wanted = (megabytes.max(1) << 20) / std::mem::size_of::<[AtomicU64; 2]>();
+        let size = (wanted + 1).next_power_of_two() >> 1;
+        Self { entries: (0..size).map(|_| [AtomicU64::new(0), AtomicU64::new(0)]).collect(), mask: size - 1 }
+    }
+
+    pub fn clear(&self) {
+        for [check, data] in &self.entries {
+            check.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TableEntry> {
+        let [check, data] = &self.entries[key as usize & self.mask];
+        let data = data.load(Ordering::Relaxed);
+        if check.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        TableEntry::unpack(data)
+    }
+
+    /// 다른 국면이거나 더 깊게 찾은 결과면 덮어씁니다.
+    pub fn store(&self, key: u64, entry: TableEntry) {
+        let [check, data] = &self.entries[key as usize & self.mask];
+        let old_data = data.load(Ordering::Relaxed);
+        let same = check.load(Ordering::Relaxed) ^ old_data == key;
+        let replace = !same || TableEntry::unpack(old_data).is_none_or(|old| entry.depth >= old.depth || entry.bound == Bound::Exact);
+        if replace {
+            let packed = entry.pack();
+            check.store(key ^ packed, Ordering::Relaxed);
+            data.store(packed, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{bail, Result};
+use crate::{
+    file_scan::ContinuationFormat,
+    game::GameState,
+    moves::Move,
+    position::AbsolutePosition,
+};
+
+/// ## ContinuationCondition
+/// 한 수를 둔 기물이 같은 차례에 또 둘 수 있는 조건.
+/// - `Always`: 항상 (토끼의 두 번 이동)
+/// - `AfterCapture`: 방금 수에서 기물을 잡았을 때만 (나이트 런어웨이)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContinuationCondition {
+    Always,
+    AfterCapture,
+}
+
+impl ContinuationCondition {
+    #[inline(always)]
+    pub fn holds<const D: usize>(&self, mv: &Move<D>) -> bool {
+        match self {
+            ContinuationCondition::Always => true,
+            ContinuationCondition::AfterCapture => mv.is_capture(),
+        }
+    }
+}
+
+/// ## ContinuationRule
+/// 기물 정의에 붙는 이어 두기 규칙. 한 차례에 이 기물로 최대 `max_legs`번까지 둡니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContinuationRule {
+    condition: ContinuationCondition,
+    max_legs: usize,
+}
+
+impl ContinuationRule {
+    pub fn new(condition: ContinuationCondition, max_legs: usize) -> Self {
+        assert!(max_legs >= 1, "a turn has at least one leg");
+        Self { condition, max_legs }
+    }
+
+    pub fn from_format(format: &ContinuationFormat) -> Result<Self> {
+        let condition = match format.get_condition().as_str() {
+            "always" => ContinuationCondition::Always,
+            "after_capture" => ContinuationCondition::AfterCapture,
+            condition => bail!("unknown continuation condition {condition:?}"),
+        };
+        if format.get_max_legs() == 0 {
+            bail!("max_legs must be at least 1");
+        }
+        Ok(Self::new(condition, format.get_max_legs()))
+    }
+
+    #[inline(always)]
+    pub fn get_condition(&self) -> ContinuationCondition {
+        self.condition
+    }
+
+    #[inline(always)]
+    pub fn max_legs(&self) -> usize {
+        self.max_legs
+    }
+}
+
+/// ## Continuation
+/// 아직 끝나지 않은 차례. 차례인 플레이어는 `piece` 칸의 기물로 한 번 더 두거나 `PASS`로 차례를 끝냅니다.
+/// `legs_left`는 이 다음 수를 포함해 남은 수이고, 수를 둘 때마다 줄어서 차례는 반드시 끝납니다.
+/// 다른 기물에게 받은 수(바드)는 `legs_left`가 1이고, 그 수로 다시 수를 주거나 이어 둘 수 없습니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Continuation<const D: usize> {
+    piece: AbsolutePosition<D>,
+    legs_left: usize,
+    condition: ContinuationCondition,
+}
+
+impl<const D: usize> Continuation<D> {
+    pub fn new(piece: AbsolutePosition<D>, legs_left: usize, condition: ContinuationCondition) -> Self {
+        Self { piece, legs_left, condition }
+    }
+
+    #[inline(always)]
+    pub fn get_piece(&self) -> &AbsolutePosition<D> {
+        &self.piece
+    }
+
+    #[inline(always)]
+    pub fn legs_left(&self) -> usize {
+        self.legs_left
+    }
+
+    #[inline(always)]
+    pub fn get_condition(&self) -> ContinuationCondition {
+        self.condition
+    }
+}
+
+impl<const D: usize> GameState<D> {
+    /// 방금 둔 `mv` 뒤에 같은 플레이어가 이어서 둘 수 있는지. `make_move`가 수를 적용한 뒤에 부릅니다.
+    pub(crate) fn continuation_after(&self, mv: &Move<D>) -> Option<Continuation<D>> {
+        if mv.is_pass() || mv.is_drop() {
+            return None;
+        }
+        if let Some(continuation) = self.get_continuation() {
+            return (continuation.legs_left > 1 && continuation.condition.holds(mv))
+                .then(|| Continuation::new(*mv.get_to(), continuation.legs_left - 1, continuation.condition));
+        }
+        if let Some(target) = mv.get_grant() {
+            return Some(Continuation::new(*target, 1, ContinuationCondition::Always));
+        }
+        let piece = self.get_board().get(mv.get_to())?;
+        let rule = self.get_rules().get_definition(piece)?.get_continuation()?;
+        (rule.max_legs > 1 && rule.condition.holds(mv))
+            .then(|| Continuation::new(*mv.get_to(), rule.max_legs - 1, rule.condition))
+    }
+
+    /// 이어 두는 중이면 그 기물의 수와 `PASS`만 둘 수 있습니다.
+    pub(crate) fn continuation_moves(&self, continuation: &Continuation<D>, moves: &mut Vec<Move<D>>) {
+        let at = continuation.piece;
+        if let Some(piece) = self.get_board().get(&at).filter(|piece| self.controls(self.side_to_move(), piece) && !self.is_immobilized(&at)) {
+            self.piece_moves(&at, piece, moves);
+        }
+        moves.push(Move::pass(at));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{fen::parse_square, presets};
+
+    #[test]
+    fn rabbit_moves_twice_and_bard_grants_a_move() {
+        let mut game = GameState::from_fen("4k3/8/8/8/8/8/8/Y1VNK3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_hash_check(true);
+        let hash = game.hash();
+        let leg = game.legal_moves().into_iter().find(|mv| mv.to_string() == "a1b3").unwrap();
+        game.make_move(leg);
+        assert_eq!(game.side_to_move(), 0);
+        let moves = game.legal_moves();
+        assert!(moves.iter().all(|mv| *mv.get_from() == parse_square("b3").unwrap()));
+        game.make_move(moves.iter().find(|mv| !mv.is_pass()).unwrap().clone());
+        assert_eq!(game.side_to_move(), 1);
+        game.unmake_move();
+        game.make_move(moves.into_iter().find(|mv| mv.is_pass()).unwrap());
+        assert_eq!(game.side_to_move(), 1);
+        assert!(game.get_board().get(&parse_square("b3").unwrap()).is_some());
+        game.unmake_move();
+        game.unmake_move();
+        assert_eq!(game.hash(), hash);
+
+        let grant = game.legal_moves().into_iter().find(|mv| mv.to_string() == "c1gd1").unwrap();
+        game.make_move(grant);
+        assert_eq!(game.side_to_move(), 0);
+        let moves = game.legal_moves();
+        assert!(moves.iter().all(|mv| *mv.get_from() == parse_square("d1").unwrap()));
+        game.make_move(moves.into_iter().find(|mv| !mv.is_pass()).unwrap());
+        assert_eq!(game.side_to_move(), 1);
+    }
+
+    #[test]
+    fn runaway_continues_only_after_capture() {
+        let mut game = GameState::from_fen("4k3/8/8/8/8/2p1p3/8/3UK3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        let capture = game.legal_moves().into_iter().find(|mv| mv.to_string() == "d1c3").unwrap();
+        game.make_move(capture);
+        assert_eq!(game.side_to_move(), 0);
+        let second = game.legal_moves().into_iter().find(|mv| mv.to_string() == "c3b5").unwrap();
+        game.make_move(second);
+        assert_eq!(game.side_to_move(), 1);
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeSet;
+use crate::{
+    game::GameState,
+    movement::MOVED_STATE,
+    ownership::Owner,
+    piece::{Piece, PlayerId},
+    position::{AbsolutePosition, Board},
+    rules::{PieceDefinition, ROYAL},
+};
+
+/// 이 이름으로 시작하는 상태는 주인에게만 보입니다.
+pub const HIDDEN_STATE_PREFIX: &str = "hidden_";
+/// 암행어사. 잡히면 잡은 기물도 같이 사라집니다.
+pub const INSPECTOR_STATE: &str = "hidden_inspector";
+/// 기물을 잡으면 다른 플레이어에게 잡힌 기물로 보이게 됨. (보이스피싱)
+pub const MIMIC: &str = "mimic";
+
+/// ## Identity
+/// 기물이 무엇으로 보이는지. 보드에 보내는 이름, 표기, 점수입니다.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Identity {
+    pub name: String,
+    pub short_name: String,
+    pub score: i32,
+}
+
+impl Identity {
+    pub fn of_piece(piece: &Piece) -> Self {
+        Self { name: piece.get_name().clone(), short_name: piece.get_short_name().clone(), score: piece.piece_score() }
+    }
+
+    pub fn of_definition<const D: usize>(definition: &PieceDefinition<D>) -> Self {
+        Self { name: definition.get_name().clone(), short_name: definition.get_short_name().clone(), score: definition.get_point() }
+    }
+}
+
+/// ## RevealEvent
+/// `players`에게 `position`에 있(었)던 기물의 진짜 정체를 알려주는 사건.
+/// 서버는 `reveal_events_for`로 그 플레이어가 받을 사건만 골라서 보냅니다.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevealEvent<const D: usize> {
+    pub position: AbsolutePosition<D>,
+    pub players: Vec<PlayerId>,
+    pub identity: Identity,
+}
+
+impl<const D: usize> GameState<D> {
+    /// `player`가 보는 보드. 기물마다 `player`에게 보이는 정체만 남기고,
+    /// 다른 플레이어 기물의 숨은 상태(`HIDDEN_STATE_PREFIX`)는 지웁니다.
+    /// 변장한 기물은 보이는 정체의 기물이 가질 상태(`MOVED_STATE`)만 남겨 진짜 기물과 구별되지 않게 합니다.
+    /// 안개 게임이면 `player`가 보지 못하는 칸의 기물도 뺍니다.
+    /// 돌려주는 보드에는 진짜 정체가 들어있지 않으므로 그대로 클라이언트에 보내도 됩니다.
+    pub fn view_for(&self, player: PlayerId) -> Board<D> {
+        let board = self.get_board();
+        let mut view = Board::new(*board.get_min_position(), *board.get_max_position(), *board.get_zobrist());
+        let visible = self.is_fog_of_war().then(|| self.visible_squares(player));
+        for (position, piece) in board.pieces().filter(|(position, _)| visible.as_ref().is_none_or(|visible| visible.contains(position))) {
+            view.set_piece(*position, self.seen_by(player, piece));
+        }
+        view
+    }
+
+    /// `player`에게 보이는 `piece`. 보이는 정체와 `player`가 알 수 있는 상태, 효과만 남깁니다. (`view_for`)
+    pub(crate) fn seen_by(&self, player: PlayerId, piece: &Piece) -> Piece {
+        let identity = piece.get_appearance(player);
+        let mut seen = Piece::new(identity.name.clone(), identity.short_name, identity.score, piece.get_owner());
+        if piece.is_disguised_to(player) {
+            let apparent = self.get_rules().get(self.get_rules().definition_player(piece.get_owner()), &identity.name);
+            if let Some(moved) = piece.get_state().get(MOVED_STATE).filter(|_| apparent.is_some_and(PieceDefinition::uses_moved_state)) {
+                seen.get_state_mut().set(MOVED_STATE, moved.clone());
+            }
+        } else {
+            for (state, value) in piece.get_state().iter() {
+                if self.controls(player, piece) || !state.starts_with(HIDDEN_STATE_PREFIX) {
+                    seen.get_state_mut().set(state.clone(), value.clone());
+                }
+            }
+        }
+        seen.get_effects_mut().extend(piece.get_effects().iter().cloned());
+        seen
+    }
+
+    /// `position`의 기물을 `players`에게 드러냅니다. 이미 진짜 정체를 보고 있던 플레이어는 빠집니다.
+    pub fn reveal(&mut self, position: &AbsolutePosition<D>, players: &[PlayerId]) {
+        let Some(piece) = self.get_board().get(position) else {
+            return;
+        };
+        let players: Vec<_> = players.iter().copied().filter(|&player| piece.is_disguised_to(player)).collect();
+        if players.is_empty() {
+            return;
+        }
+        let identity = Identity::of_piece(piece);
+        self.update_piece(position, |piece| players.iter().for_each(|&player| piece.reveal_to(player)));
+        self.push_reveal(RevealEvent { position: *position, players, identity });
+    }
+
+    /// `player`의 royal이 아닌 기물 중 하나를 무작위로 암행어사로 정합니다. 게임을 준비할 때 씁니다.
+    pub fn assign_inspector(&mut self, player: PlayerId) -> Option<AbsolutePosition<D>> {
+        let rules = self.get_rules();
+        let candidates: BTreeSet<_> = self.get_board().pieces()
+            .filter(|(_, piece)| piece.get_owner() == Owner::Player(player) && rules.get_definition(piece).is_some_and(|definition| !definition.has_attribute(ROYAL)))
+            .map(|(position, _)| *position)
+            .collect();
+        let inspector = *self.choose_squares(candidates.into_iter().collect(), 1).first()?;
+        self.update_piece(&inspector, |piece| { piece.get_state_mut().set(INSPECTOR_STATE, "true"); });
+        Some(inspector)
+    }
+
+    /// `player`가 받아야 하는 드러남 사건들.
+    pub fn reveal_events_for(&self, player: PlayerId) -> impl Iterator<Item = &RevealEvent<D>> {
+        self.reveal_events().iter().filter(move |event| event.players.contains(&player))
+    }
+
+    /// 잡힌 기물들을 모두에게 드러냅니다. 암행어사를 잡은 기물이 사라지는 것은 잡기 훅이 처리합니다.
+    /// 잡은 기물이 `MIMIC`이면 다른 플레이어에게는 잡힌 기물로 보이게 됩니다.
+    pub(crate) fn resolve_hidden_captures(&mut self, captor: &AbsolutePosition<D>, captured: &[(AbsolutePosition<D>, Piece)]) {
+        let everyone: Vec<_> = (0..self.player_count()).collect();
+        for (position, piece) in captured {
+            let hidden_state = piece.get_state().iter().any(|(state, _)| state.starts_with(HIDDEN_STATE_PREFIX));
+            let players: Vec<_> = everyone.iter().copied()
+                .filter(|&player| piece.is_disguised_to(player) || (hidden_state && !self.controls(player, piece)))
+                .collect();
+            if !players.is_empty() {
+                self.push_reveal(RevealEvent { position: *position, players, identity: Identity::of_piece(piece) });
+            }
+        }
+        let Some(mover) = self.get_board().get(captor).cloned() else {
+            return;
+        };
+        let mimics = self.get_rules().get_definition(&mover).is_some_and(|definition| definition.has_attribute(MIMIC));
+        if let Some((_, victim)) = captured.last().filter(|_| mimics) {
+            let others: Vec<_> = everyone.into_iter().filter(|&player| !self.controls(player, &mover)).collect();
+            self.update_piece(captor, |piece| {
+                for player in others {
+                    piece.set_appearance(player, victim.get_appearance(player));
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{fen::parse_square, moves::Move, movement::{CAPTURE, MOVE}, presets};
+
+    #[test]
+    fn views_hide_true_identities() {
+        let mut game = GameState::from_fen("4k3/8/8/8/3p4/8/2W5/4K2R w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_hash_check(true);
+        let fraud = parse_square("c2").unwrap();
+        game.update_piece(&parse_square("h1").unwrap(), |piece| { piece.get_state_mut().set(INSPECTOR_STATE, "true"); });
+
+        let black = game.view_for(1);
+        assert_eq!(black.get(&fraud).unwrap().get_name(), "king");
+        assert!(black.get(&parse_square("h1").unwrap()).unwrap().get_state().get(INSPECTOR_STATE).is_none());
+        assert_eq!(game.view_for(0).get(&fraud).unwrap().get_name(), "fraud");
+
+        game.make_move(Move::new(fraud, parse_square("c3").unwrap(), MOVE));
+        game.make_move(Move::new(parse_square("d4").unwrap(), parse_square("c3").unwrap(), CAPTURE));
+        let event = game.reveal_events_for(1).next().unwrap();
+        assert_eq!(event.identity.name, "fraud");
+        assert!(game.reveal_events_for(0).next().is_none());
+        game.unmake_move();
+        assert!(game.reveal_events().is_empty());
+
+        let mut game = GameState::from_fen("4k2r/8/8/8/8/8/8/4K2R b - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        let inspector = parse_square("h1").unwrap();
+        game.update_piece(&inspector, |piece| { piece.get_state_mut().set(INSPECTOR_STATE, "true"); });
+        game.make_move(Move::new(parse_square("h8").unwrap(), inspector, CAPTURE));
+        assert!(game.get_board().get(&inspector).is_none());
+        assert_eq!(game.reveal_events_for(1).last().unwrap().identity.name, "rook");
+
+        let mut game = GameState::from_fen("4k3/8/8/8/8/8/PPP5/4K3 w - - 0 1", presets::standard_board(), Arc::new(presets::fairy_chess())).unwrap();
+        game.set_seed(3);
+        let inspector = game.assign_inspector(0).unwrap();
+        assert_eq!(game.get_board().get(&inspector).unwrap().get_name(), "pawn");
+        assert!(game.view_for(1).get(&inspector).unwrap().get_state().get(INSPECTOR_STATE).is_none());
+    }
+
+    #[test]
+    fn disguised_pieces_look_like_genuine_ones() {
+        let rules = Arc::new(presets::fairy_chess());
+        let mut disguised = GameState::from_fen("4k3/8/8/8/8/8/2W5/4K3 w - - 0 1", presets::standard_board(), Arc::clone(&rules)).unwrap();
+        let mut genuine = GameState::from_fen("4k3/8/8/8/8/8/2K5/8 w - - 0 1", presets::standard_board(), rules).unwrap();
+        let (from, to) = (parse_square("c2").unwrap(), parse_square("c3").unwrap());
+        assert_eq!(disguised.view_for(1).get(&from), genuine.view_for(1).get(&from));
+
+        disguised.make_move(Move::new(from, to, MOVE));
+        genuine.make_move(Move::new(from, to, MOVE));
+        assert_eq!(disguised.view_for(1).get(&to), genuine.view_for(1).get(&to));
+        assert!(disguised.view_for(1).get(&to).unwrap().get_state().get(MOVED_STATE).is_some());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    effects::Effect,
+    piece::{Piece, PlayerId},
+    position::AbsolutePosition,
+    turns::Continuation,
+};
+
+const DEFAULT_SEED: u64 = 0x5EED_C0DE_CAFE_F00D;
+
+const PIECE_DOMAIN: u64 = 0x01;
+const STATE_DOMAIN: u64 = 0x02;
+const SIDE_DOMAIN: u64 = 0x03;
+const CASTLING_DOMAIN: u64 = 0x04;
+const EN_PASSANT_DOMAIN: u64 = 0x05;
+const PIECE_EFFECT_DOMAIN: u64 = 0x06;
+const SQUARE_EFFECT_DOMAIN: u64 = 0x07;
+const CONTINUATION_DOMAIN: u64 = 0x08;
+const APPEARANCE_DOMAIN: u64 = 0x09;
+const RESOURCE_DOMAIN: u64 = 0x0A;
+const HAND_DOMAIN: u64 = 0x0B;
+const PLY_DOMAIN: u64 = 0x0C;
+const EFFECT_CLOCK_DOMAIN: u64 = 0x0D;
+const COUNTER_DOMAIN: u64 = 0x0E;
+
+/// ## Zobrist
+/// Zobrist 키 생성기.
+/// 기물 종류는 파일에서 읽어오고 차원 D도 정해져 있지 않아서 키 테이블을 미리 만들 수 없습니다.
+/// 대신 seed와 (기물 이름, 색, 칸) 같은 입력을 섞어서 키를 바로 계산합니다.
+/// 같은 seed면 항상 같은 키가 나오므로 테이블을 만든 것과 똑같이 쓸 수 있습니다.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Zobrist {
+    seed: u64,
+}
+
+impl Default for Zobrist {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+impl Zobrist {
+    pub const fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// 기물 종류 × 색 × 칸 키에 기물의 `State` 값들, 효과들, 플레이어별로 보이는 정체를 XOR한 키.
+    /// 상태 값의 순서와 상관없이 같은 키가 나옵니다.
+    pub fn piece<const D: usize>(&self, piece: &Piece, position: &AbsolutePosition<D>) -> u64 {
+        let square = position_key(position);
+        let mut key = self.mix(&[PIECE_DOMAIN, fnv1a(piece.get_name().as_bytes()), piece.get_owner().key(), square]);
+        for (state, value) in piece.get_state().iter() {
+            key ^= self.mix(&[STATE_DOMAIN, fnv1a(state.as_bytes()), fnv1a(value.as_bytes()), square]);
+        }
+        key ^= self.effects(PIECE_EFFECT_DOMAIN, piece.get_effects(), square);
+        for (player, identity) in piece.appearances() {
+            key ^= self.mix(&[APPEARANCE_DOMAIN, *player as u64, fnv1a(identity.name.as_bytes()), square]);
+        }
+        key
+    }
+
+    /// 칸에 걸린 효과들의 키.
+    pub fn square_effects<const D: usize>(&self, position: &AbsolutePosition<D>, effects: &[Effect]) -> u64 {
+        self.effects(SQUARE_EFFECT_DOMAIN, effects, position_key(position))
+    }
+
+    /// 효과 종류, 건 플레이어, 끝나는지 여부의 키. 끝나는 ply는 넣지 않습니다. (`piece_effect_clock`)
+    /// 같은 효과를 겹쳐 걸면(`Stacking::Stack`) 몇 번째인지도 넣어서 둘이 XOR로 지워지지 않게 합니다.
+    fn effects(&self, domain: u64, effects: &[Effect], square: u64) -> u64 {
+        effects.iter().enumerate().fold(0, |key, (index, effect)| {
+            let stacked = effects[..index].iter().filter(|other| other.get_kind() == effect.get_kind() && other.get_source() == effect.get_source()).count();
+            let timed = effect.expires_at().is_some() as u64;
+            key ^ self.mix(&[domain, fnv1a(effect.get_kind().name().as_bytes()), effect.get_source() as u64, timed, stacked as u64, square])
+        })
+    }
+
+    /// 끝나는 효과들의 남은 ply 키. 끝나는 ply 값 그대로 넣으면 같은 국면이 언제 나왔는지에 따라 키가 달라지고,
+    /// 넣지 않으면 남은 시간이 다른 국면이 같은 키가 됩니다. 남은 시간은 ply마다 바뀌므로 `GameState::hash`가 그때그때 더합니다.
+    pub fn piece_effect_clock<const D: usize>(&self, position: &AbsolutePosition<D>, effects: &[Effect], ply: usize) -> u64 {
+        self.effect_clock(PIECE_EFFECT_DOMAIN, effects, position_key(position), ply)
+    }
+
+    pub fn square_effect_clock<const D: usize>(&self, position: &AbsolutePosition<D>, effects: &[Effect], ply: usize) -> u64 {
+        self.effect_clock(SQUARE_EFFECT_DOMAIN, effects, position_key(position), ply)
+    }
+
+    fn effect_clock(&self, domain: u64, effects: &[Effect], square: u64, ply: usize) -> u64 {
+        effects.iter().enumerate().fold(0, |key, (index, effect)| {
+            let Some(expires_at) = effect.expires_at() else {
+                return key;
+            };
+            let stacked = effects[..index].iter().filter(|other| *other == effect).count();
+            let remaining = expires_at.saturating_sub(ply) as u64;
+            key ^ self.mix(&[EFFECT_CLOCK_DOMAIN, domain, fnv1a(effect.get_kind().name().as_bytes()), effect.get_source() as u64, remaining, stacked as u64, square])
+        })
+    }
+
+    pub fn side_to_move(&self, player: PlayerId) -> u64 {
+        self.mix(&[SIDE_DOMAIN, player as u64])
+    }
+
+    /// 캐슬링 권리는 캐슬링 상대 기물(룩)이 있는 칸으로 저장합니다.
+    pub fn castling<const D: usize>(&self, position: &AbsolutePosition<D>) -> u64 {
+        self.mix(&[CASTLING_DOMAIN, position_key(position)])
+    }
+
+    pub fn en_passant<const D: usize>(&self, position: &AbsolutePosition<D>) -> u64 {
+        self.mix(&[EN_PASSANT_DOMAIN, position_key(position)])
+    }
+
+    /// 이어 두는 중인 차례. 같은 배치라도 이어 두는 중이면 다른 국면입니다.
+    pub fn continuation<const D: usize>(&self, continuation: &Continuation<D>) -> u64 {
+        let condition = continuation.get_condition() as u64;
+        self.mix(&[CONTINUATION_DOMAIN, position_key(continuation.get_piece()), continuation.legs_left() as u64, condition])
+    }
+
+    /// 플레이어 자원 값. 0이면 키가 없습니다.
+    pub fn resource(&self, player: PlayerId, name: &str, value: i64) -> u64 {
+        if value == 0 { 0 } else { self.mix(&[RESOURCE_DOMAIN, player as u64, fnv1a(name.as_bytes()), value as u64]) }
+    }
+
+    /// 플레이어가 손에 든 기물 수. 0이면 키가 없습니다.
+    pub fn hand(&self, player: PlayerId, name: &str, count: usize) -> u64 {
+        if count == 0 { 0 } else { self.mix(&[HAND_DOMAIN, player as u64, fnv1a(name.as_bytes()), count as u64]) }
+    }
+
+    /// 게임 전체 카운터 값. 0이면 키가 없습니다.
+    pub fn counter(&self, name: &str, value: i64) -> u64 {
+        if value == 0 { 0 } else { self.mix(&[COUNTER_DOMAIN, fnv1a(name.as_bytes()), value as u64]) }
+    }
+
+    /// 이동 조건이 읽는 ply 값. (`PlyDependence::key_of`)
+    pub fn ply(&self, phase: usize) -> u64 {
+        self.mix(&[PLY_DOMAIN, phase as u64])
+    }
+
+    fn mix(&self, values: &[u64]) -> u64 {
+        values.iter().fold(splitmix64(self.seed), |acc, value| splitmix64(acc ^ value))
+    }
+}
+
+pub(crate) const fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xCBF2_9CE4_8422_2325u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+        i += 1;
+    }
+    hash
+}
+
+/// 좌표를 차원 수와 상관없이 하나의 값으로 섞습니다.
+fn position_key<const D: usize>(position: &AbsolutePosition<D>) -> u64 {
+    position.iter().fold(D as u64, |acc, &axis| splitmix64(acc ^ axis as u64))
+}